@@ -0,0 +1,25 @@
+struct Vertex {
+    @location(0) position: vec2<f32>,
+    @location(1) tex_coord: vec2<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coord: vec2<f32>,
+};
+
+@vertex
+fn vs_main(v: Vertex) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(v.position, 0.0, 1.0);
+    out.tex_coord = v.tex_coord;
+    return out;
+}
+
+@group(0) @binding(0) var image_texture: texture_2d<f32>;
+@group(0) @binding(1) var image_sampler: sampler;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(image_texture, image_sampler, in.tex_coord);
+}
@@ -0,0 +1,72 @@
+use crate::animation::spring::CriticallyDampedSpring;
+
+/// How quickly the flash fades (spring angular frequency).
+const FADE_OMEGA: f32 = 14.0;
+
+/// Visual bell: each ring kicks a flash to full strength, and a critically
+/// damped spring eases it back to nothing.
+#[derive(Debug, Clone)]
+pub struct VisualBell {
+    spring: CriticallyDampedSpring,
+}
+
+impl Default for VisualBell {
+    fn default() -> Self {
+        Self { spring: CriticallyDampedSpring::new(FADE_OMEGA) }
+    }
+}
+
+impl VisualBell {
+    pub fn ring(&mut self) {
+        self.spring.position = 1.0;
+        self.spring.velocity = 0.0;
+    }
+
+    pub fn tick(&mut self, dt: f32) {
+        if self.is_animating() {
+            self.spring.tick(dt);
+        } else {
+            self.spring.snap_to_target();
+        }
+    }
+
+    pub fn is_animating(&self) -> bool {
+        !self.spring.is_settled(0.01)
+    }
+
+    /// Flash strength, 0.0 to 1.0.
+    pub fn intensity(&self) -> f32 {
+        self.spring.position.clamp(0.0, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ring_flashes_then_fades() {
+        let mut bell = VisualBell::default();
+        assert_eq!(bell.intensity(), 0.0);
+        assert!(!bell.is_animating());
+        bell.ring();
+        assert_eq!(bell.intensity(), 1.0);
+        bell.tick(0.05);
+        let mid = bell.intensity();
+        assert!(mid > 0.0 && mid < 1.0);
+        while bell.is_animating() {
+            bell.tick(1.0 / 60.0);
+        }
+        bell.tick(1.0 / 60.0);
+        assert_eq!(bell.intensity(), 0.0);
+    }
+
+    #[test]
+    fn ringing_again_restarts_the_flash() {
+        let mut bell = VisualBell::default();
+        bell.ring();
+        bell.tick(0.2);
+        bell.ring();
+        assert_eq!(bell.intensity(), 1.0);
+    }
+}
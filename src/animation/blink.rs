@@ -0,0 +1,110 @@
+/// Fraction of each half-cycle spent fading rather than fully on or off.
+const FADE_FRACTION: f32 = 0.35;
+
+/// Seconds without activity after which the cursor stops blinking and
+/// stays visible, so an idle terminal doesn't keep rendering frames.
+const IDLE_TIMEOUT: f32 = 15.0;
+
+/// Smooth cursor blink: the cursor holds visible for one interval and
+/// hidden for the next, easing between the two instead of toggling.
+#[derive(Debug, Clone)]
+pub struct CursorBlink {
+    pub enabled: bool,
+    /// Length of the visible (and of the hidden) half of a cycle, in seconds.
+    pub interval: f32,
+    elapsed: f32,
+}
+
+impl CursorBlink {
+    pub fn new(interval: f32) -> Self {
+        Self { enabled: false, interval, elapsed: 0.0 }
+    }
+
+    /// Restart the cycle from fully visible (typing, cursor movement).
+    pub fn reset(&mut self) {
+        self.elapsed = 0.0;
+    }
+
+    pub fn tick(&mut self, dt: f32) {
+        if self.is_animating() {
+            self.elapsed += dt;
+        }
+    }
+
+    /// Whether the blink still needs frames. Blinking stops once the idle
+    /// timeout has passed and the cursor is back to fully visible.
+    pub fn is_animating(&self) -> bool {
+        self.enabled && (self.elapsed < IDLE_TIMEOUT || self.opacity_at(self.elapsed) < 1.0)
+    }
+
+    /// Current cursor opacity, 0.0 to 1.0.
+    pub fn opacity(&self) -> f32 {
+        if self.is_animating() {
+            self.opacity_at(self.elapsed)
+        } else {
+            1.0
+        }
+    }
+
+    fn opacity_at(&self, elapsed: f32) -> f32 {
+        let interval = self.interval.max(0.01);
+        // Position in the cycle: 0..1 visible half, 1..2 hidden half
+        let t = (elapsed / interval) % 2.0;
+        let ease = |x: f32| {
+            let x = x.clamp(0.0, 1.0);
+            x * x * (3.0 - 2.0 * x)
+        };
+        if t < 1.0 {
+            1.0 - ease((t - (1.0 - FADE_FRACTION)) / FADE_FRACTION)
+        } else {
+            ease((t - (2.0 - FADE_FRACTION)) / FADE_FRACTION)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blink() -> CursorBlink {
+        let mut b = CursorBlink::new(0.5);
+        b.enabled = true;
+        b
+    }
+
+    #[test]
+    fn disabled_is_always_visible() {
+        let mut b = CursorBlink::new(0.5);
+        b.tick(0.75);
+        assert_eq!(b.opacity(), 1.0);
+        assert!(!b.is_animating());
+    }
+
+    #[test]
+    fn fades_out_and_back_in() {
+        let mut b = blink();
+        assert_eq!(b.opacity(), 1.0);
+        b.tick(0.25);
+        assert_eq!(b.opacity(), 1.0);
+        b.tick(0.15);
+        assert!(b.opacity() > 0.0 && b.opacity() < 1.0);
+        b.tick(0.2);
+        assert_eq!(b.opacity(), 0.0);
+        b.tick(0.3);
+        assert!(b.opacity() > 0.0 && b.opacity() < 1.0);
+        b.reset();
+        assert_eq!(b.opacity(), 1.0);
+    }
+
+    #[test]
+    fn stops_visible_when_idle() {
+        let mut b = blink();
+        while b.is_animating() {
+            b.tick(1.0 / 60.0);
+        }
+        assert!(b.elapsed >= IDLE_TIMEOUT);
+        assert_eq!(b.opacity(), 1.0);
+        b.reset();
+        assert!(b.is_animating());
+    }
+}
@@ -1,2 +1,4 @@
+pub mod bell;
+pub mod blink;
 pub mod scroll;
 pub mod spring;
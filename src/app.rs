@@ -1,26 +1,40 @@
-use crate::config::{Config, OPEN_CONFIG_REQUESTED};
-use crate::input::{handle_key_event, handle_scroll, InputAction};
+use crate::config::{BellConfig, ClipboardConfig, ClipboardReadPolicy, Config, OPEN_CONFIG_REQUESTED};
+use crate::input::{
+    encode_mouse_event, handle_key_event, handle_scroll, InputAction, MouseReportButton,
+    MouseReportKind,
+};
+use crate::notifier::Notifier;
 use crate::pane::Direction;
 use crate::pane::layout::Rect;
 use crate::pane::PaneTree;
-use crate::renderer::{Renderer, Selection};
+use crate::renderer::{Renderer, Selection, UrlSpan};
+use crate::terminal::clipboard::{decode_osc52_payload, encode_osc52_response, ClipboardRequest};
+use crate::terminal::grid::{LineSize, MouseEncoding, MouseTracking};
 use crate::terminal::url::detect_urls;
 use crossbeam_channel::Receiver;
 use notify::{RecommendedWatcher, RecursiveMode, Watcher};
 use std::collections::HashMap;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, OnceLock};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use winit::application::ApplicationHandler;
 use winit::event::{ElementState, MouseButton, WindowEvent};
 use winit::event_loop::{ActiveEventLoop, ControlFlow, EventLoopProxy};
 use winit::keyboard::ModifiersState;
-use winit::window::{Window, WindowAttributes, WindowId};
+use winit::window::{UserAttentionType, Window, WindowAttributes, WindowId};
 
 /// Global event-loop proxy so PTY reader threads can wake the event loop
 /// without needing to thread the proxy through every constructor.
 static EVENT_LOOP_PROXY: OnceLock<EventLoopProxy<()>> = OnceLock::new();
 
+/// Shortest time between two OSC 52 read prompts in one window; reads
+/// asked for sooner are denied.
+const CLIPBOARD_PROMPT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Shortest time between two bell sounds or attention requests in one
+/// window.
+const BELL_ALERT_INTERVAL: Duration = Duration::from_millis(200);
+
 /// Set to `true` by PTY reader threads when new data arrives.
 /// Cleared after the data is drained in `RedrawRequested`.
 pub static PTY_DATA_PENDING: AtomicBool = AtomicBool::new(false);
@@ -109,10 +123,21 @@ struct WindowState {
     selection_pane: usize,
     /// True while the left mouse button is held down (for drag selection).
     mouse_button_down: bool,
-    /// Currently hovered URL: (pane_id, abs_row, col_start, col_end_exclusive, url_string)
-    hovered_url: Option<(usize, usize, usize, usize, String)>,
+    /// Button whose press was reported to the focused pane's application
+    /// (mouse tracking mode), so its release and drags are reported too.
+    mouse_report_button: Option<MouseReportButton>,
+    /// Last cell a mouse report was sent for, to avoid duplicate motion reports.
+    last_mouse_report_cell: Option<(usize, usize)>,
+    /// Sub-line wheel movement not yet reported as wheel button events.
+    mouse_wheel_remainder: f32,
+    /// Currently hovered URL: (pane_id, span, url_string)
+    hovered_url: Option<(usize, UrlSpan, String)>,
     /// Last tab title set via NSWindowTab, used to avoid redundant ObjC calls.
     last_tab_title: String,
+    /// When the last OSC 52 read prompt was answered.
+    last_clipboard_prompt: Option<Instant>,
+    /// When the bell last beeped or asked for attention.
+    last_bell_alert: Option<Instant>,
 }
 
 impl WindowState {
@@ -144,10 +169,14 @@ impl WindowState {
             Some(p) => p,
             None => return,
         };
-        let folder = cwd
+        let mut folder = cwd
             .file_name()
             .map(|s| s.to_string_lossy().into_owned())
             .unwrap_or_else(|| cwd.to_string_lossy().into_owned());
+        // Mark tabs with a pane whose bell hasn't been seen
+        if self.pane_tree.panes.iter().any(|p| p.bell) {
+            folder = format!("🔔 {}", folder);
+        }
         if folder == self.last_tab_title {
             return;
         }
@@ -204,8 +233,6 @@ impl WindowState {
         let grid = pane.terminal.grid.lock();
         let scrollback_len = grid.scrollback.len();
         let visible_rows = grid.rows;
-        let cols = grid.cols;
-        drop(grid);
 
         // y = pane_rect.y + row_idx * cell_h + scroll_offset
         // row_idx = abs_row - scrollback_len
@@ -222,39 +249,244 @@ impl WindowState {
             return None;
         }
 
-        let col = ((px - pane_rect.x) / cell_w).floor() as i64;
+        // Columns of double-width and double-height rows are two cells wide
+        let (col_w, cols) = match grid.abs_row_line_size(abs_row) {
+            LineSize::Single => (cell_w, grid.cols),
+            _ => (cell_w * 2.0, grid.line_cols(abs_row - scrollback_len)),
+        };
+        drop(grid);
+        let col = ((px - pane_rect.x) / col_w).floor() as i64;
         let col = col.clamp(0, cols as i64 - 1) as usize;
 
         Some((abs_row, col))
     }
 
-    /// Check if a URL exists at the given cell position in a pane.
-    /// Returns (col_start, col_end_exclusive, url_string) if found.
-    fn url_at_cell(&self, pane_id: usize, abs_row: usize, col: usize) -> Option<(usize, usize, String)> {
+    /// Convert a physical-pixel position to a visible-grid (row, col), clamped
+    /// to the pane. Used for mouse reporting, which ignores scrollback.
+    fn pixel_to_grid_cell(&self, px: f32, py: f32, pane_rect: Rect, pane_id: usize) -> Option<(usize, usize)> {
         let pane = self.pane_tree.pane_by_id(pane_id)?;
         let grid = pane.terminal.grid.lock();
-        let scrollback_len = grid.scrollback.len();
+        let row = ((py - pane_rect.y) / self.renderer.cell_h).floor() as i64;
+        let row = row.clamp(0, grid.rows as i64 - 1) as usize;
+        // Columns of double-width and double-height rows are two cells wide
+        let cell_w = self.renderer.cell_w;
+        let (col_w, cols) = match grid.abs_row_line_size(grid.scrollback.len() + row) {
+            LineSize::Single => (cell_w, grid.cols),
+            _ => (cell_w * 2.0, grid.line_cols(row)),
+        };
+        let col = ((px - pane_rect.x) / col_w).floor() as i64;
+        let col = col.clamp(0, cols as i64 - 1) as usize;
+        Some((row, col))
+    }
 
-        let row_cells = if abs_row < scrollback_len {
-            &grid.scrollback[abs_row]
-        } else {
-            let vis_row = abs_row - scrollback_len;
-            if vis_row < grid.cells.len() {
-                &grid.cells[vis_row]
-            } else {
-                return None;
-            }
+    /// Mouse tracking mode and encoding requested by the focused pane's
+    /// application. `None` when reporting is off or Shift is held, so that
+    /// Shift+click always falls back to local selection.
+    fn focused_mouse_reporting(&self) -> Option<(MouseTracking, MouseEncoding)> {
+        if self.modifiers.shift_key() {
+            return None;
+        }
+        let pane = self.pane_tree.focused_pane()?;
+        let grid = pane.terminal.grid.lock();
+        if grid.mouse_tracking == MouseTracking::Off {
+            return None;
+        }
+        Some((grid.mouse_tracking, grid.mouse_encoding))
+    }
+
+    /// Send a mouse event at the current cursor position to the focused pane.
+    fn report_mouse(&mut self, kind: MouseReportKind, button: MouseReportButton, content_rect: Rect) {
+        let Some((tracking, encoding)) = self.focused_mouse_reporting() else {
+            return;
+        };
+        let focused_id = self.pane_tree.focused_id;
+        let layout_rects = self.pane_tree.layout.compute_rects(content_rect);
+        let Some(&(_, pane_rect)) = layout_rects.iter().find(|(id, _)| *id == focused_id) else {
+            return;
         };
+        let (px, py) = self.cursor_pos;
+        let Some(cell) = self.pixel_to_grid_cell(px, py, pane_rect, focused_id) else {
+            return;
+        };
+        if kind == MouseReportKind::Motion
+            && (!tracking.reports_motion(self.mouse_report_button.is_some())
+                || self.last_mouse_report_cell == Some(cell))
+        {
+            return;
+        }
+        self.last_mouse_report_cell = Some(cell);
+        let (row, col) = cell;
+        if let Some(bytes) = encode_mouse_event(kind, button, col, row, self.modifiers, encoding) {
+            if let Some(pane) = self.pane_tree.focused_pane_mut() {
+                let _ = pane.terminal.write_input(&bytes);
+            }
+        }
+    }
 
+    /// Focus the pane under the given pixel position, if any.
+    fn focus_pane_at(&mut self, px: f32, py: f32, content_rect: Rect) {
+        let layout_rects = self.pane_tree.layout.compute_rects(content_rect);
+        for (pane_id, pane_rect) in &layout_rects {
+            if px >= pane_rect.x
+                && px < pane_rect.x + pane_rect.width
+                && py >= pane_rect.y
+                && py < pane_rect.y + pane_rect.height
+            {
+                self.pane_tree.focused_id = *pane_id;
+                break;
+            }
+        }
+    }
+
+    /// Check if a URL exists at the given cell position in a pane.
+    /// Explicit OSC 8 hyperlinks take precedence over URLs detected in the text.
+    /// Returns the URL's span and string if found.
+    fn url_at_cell(&self, pane_id: usize, abs_row: usize, col: usize) -> Option<(UrlSpan, String)> {
+        let pane = self.pane_tree.pane_by_id(pane_id)?;
+        let grid = pane.terminal.grid.lock();
+
+        if let Some((start_row, start_col, end_row, end_col, uri)) = grid.hyperlink_range(abs_row, col) {
+            let span = UrlSpan { start: (start_row, start_col), end: (end_row, end_col + 1) };
+            return Some((span, uri.to_string()));
+        }
+
+        let row_cells = grid.abs_row_cells(abs_row)?;
         let urls = detect_urls(row_cells);
         for (start, end, url) in urls {
             if col >= start && col < end {
-                return Some((start, end, url));
+                return Some((UrlSpan { start: (abs_row, start), end: (abs_row, end) }, url));
             }
         }
         None
     }
 
+    /// React to BEL from any pane: flash it, beep, flag it until the user
+    /// looks at it, and ask for attention when the window is in the background.
+    fn handle_bells(&mut self, config: &BellConfig) {
+        let window_focused = self.window.has_focus();
+        let focused_id = self.pane_tree.focused_id;
+        let mut rang = false;
+        for pane in &mut self.pane_tree.panes {
+            let seen = window_focused && pane.id == focused_id;
+            if std::mem::take(&mut pane.terminal.grid.lock().bell) {
+                rang = true;
+                if config.visual {
+                    self.renderer.ring_bell(pane.id);
+                }
+                pane.bell |= !seen;
+            }
+            if seen {
+                pane.bell = false;
+            }
+        }
+        // The flash runs every time; the sound and the attention request
+        // are rate limited so a stream of BELs doesn't turn into a buzz
+        if !rang || self.last_bell_alert.is_some_and(|t| t.elapsed() < BELL_ALERT_INTERVAL) {
+            return;
+        }
+        self.last_bell_alert = Some(Instant::now());
+        if config.audible {
+            #[cfg(target_os = "macos")]
+            unsafe { objc2_app_kit::NSBeep() };
+        }
+        if config.urgent && !window_focused {
+            self.window.request_user_attention(Some(UserAttentionType::Informational));
+        }
+    }
+
+    /// Show OSC 9 / OSC 777 notifications, except from the pane the user
+    /// is already looking at.
+    fn handle_notifications(&mut self, notifier: &mut Notifier, now: Instant) {
+        let window_focused = self.window.has_focus();
+        let focused_id = self.pane_tree.focused_id;
+        for pane in &self.pane_tree.panes {
+            let (notifications, title) = {
+                let mut grid = pane.terminal.grid.lock();
+                (std::mem::take(&mut grid.notifications), grid.title.clone())
+            };
+            if window_focused && pane.id == focused_id {
+                continue;
+            }
+            for mut notification in notifications {
+                // OSC 9 carries no title; name the pane it came from
+                if notification.title.is_empty() {
+                    notification.title =
+                        if title.is_empty() { "Smooth Terminal".to_string() } else { title.clone() };
+                }
+                notifier.deliver(&notification, now);
+            }
+        }
+    }
+
+    /// Carry out OSC 52 clipboard requests queued by each pane's parser.
+    /// Writes go to the system clipboard; reads are answered through the
+    /// pane's response queue according to the configured policy.
+    fn handle_clipboard_requests(&mut self, config: &ClipboardConfig) {
+        // With the `ask` policy a pane's reads in one frame share a single
+        // prompt. Reads from other panes that frame, or soon after the last
+        // prompt, are denied so a looping application can't lock the UI.
+        let mut prompted = false;
+        let last_prompt = &mut self.last_clipboard_prompt;
+        for pane in &mut self.pane_tree.panes {
+            let requests = {
+                let mut grid = pane.terminal.grid.lock();
+                grid.clipboard_max_bytes = config.osc52_max_bytes;
+                std::mem::take(&mut grid.clipboard_requests)
+            };
+            if requests.is_empty() {
+                continue;
+            }
+            let mut read_allowed = None;
+            for request in requests {
+                match request {
+                    ClipboardRequest::Write { data } => {
+                        let Some(text) = decode_osc52_payload(&data, config.osc52_max_bytes) else {
+                            log::warn!("Ignoring invalid or oversized OSC 52 clipboard write");
+                            continue;
+                        };
+                        #[cfg(target_os = "macos")]
+                        App::macos_copy_to_clipboard(&text);
+                        #[cfg(not(target_os = "macos"))]
+                        let _ = text;
+                    }
+                    ClipboardRequest::Read { selection, bell_terminated } => {
+                        let allowed = *read_allowed.get_or_insert_with(|| match config.osc52_read {
+                            ClipboardReadPolicy::Allow => true,
+                            ClipboardReadPolicy::Deny => false,
+                            ClipboardReadPolicy::Ask => {
+                                let recent = last_prompt.is_some_and(|t| t.elapsed() < CLIPBOARD_PROMPT_INTERVAL);
+                                if prompted || recent {
+                                    false
+                                } else {
+                                    prompted = true;
+                                    #[cfg(target_os = "macos")]
+                                    let allowed = unsafe { macos_confirm_clipboard_read() };
+                                    #[cfg(not(target_os = "macos"))]
+                                    let allowed = false;
+                                    *last_prompt = Some(Instant::now());
+                                    allowed
+                                }
+                            }
+                        });
+                        if !allowed {
+                            continue;
+                        }
+                        #[cfg(target_os = "macos")]
+                        let text = App::macos_paste_from_clipboard();
+                        #[cfg(not(target_os = "macos"))]
+                        let text: Option<String> = None;
+                        let Some(text) = text.filter(|t| t.len() <= config.osc52_max_bytes) else {
+                            continue;
+                        };
+                        let response = encode_osc52_response(&selection, &text, bell_terminated);
+                        pane.terminal.grid.lock().response_queue.push(response);
+                    }
+                }
+            }
+            pane.terminal.flush_responses();
+        }
+    }
+
     /// Write input bytes to the focused pane and snap scroll to bottom.
     fn write_to_focused_pane(&mut self, bytes: &[u8]) {
         if let Some(pane) = self.pane_tree.focused_pane_mut() {
@@ -279,6 +511,8 @@ pub struct App {
     // Windows to remove after the current event batch (deferred to avoid
     // dropping the winit Window while macOS still has pending events for it).
     pending_close: Vec<WindowId>,
+    /// Shows OSC 9 / OSC 777 notifications; shared so the rate limit is global.
+    notifier: Notifier,
     // Retained NSEvent monitor for double-click tab renaming (macOS only).
     #[cfg(target_os = "macos")]
     _event_monitor: Option<objc2::rc::Retained<objc2::runtime::AnyObject>>,
@@ -300,6 +534,7 @@ impl App {
     pub fn new(config: Config) -> Self {
         Self {
             windows: HashMap::new(),
+            notifier: Notifier::new(&config.notifications),
             config,
             first_window_id: None,
             pending_close: Vec::new(),
@@ -369,6 +604,7 @@ impl App {
         for state in self.windows.values_mut() {
             let scale = state.window.scale_factor() as f32;
             let metrics_changed = state.renderer.apply_config(new_config.clone(), scale);
+            state.pane_tree.set_theme(new_config.colors.theme());
             if metrics_changed {
                 let rect = state.content_rect(&new_config);
                 let layout_rects = state.pane_tree.layout.compute_rects(rect);
@@ -409,7 +645,8 @@ impl App {
         let cols = cols.max(1);
         let rows = rows.max(1);
 
-        let pane_tree = PaneTree::new(cols, rows, cwd).expect("create pane tree");
+        let mut pane_tree = PaneTree::new(cols, rows, (cell_w, cell_h), cwd).expect("create pane tree");
+        pane_tree.set_theme(config.colors.theme());
 
         // Set up config file watcher for hot-reload
         let config_path = Config::config_path();
@@ -447,8 +684,13 @@ impl App {
             selection: None,
             selection_pane: 0,
             mouse_button_down: false,
+            mouse_report_button: None,
+            last_mouse_report_cell: None,
+            mouse_wheel_remainder: 0.0,
             hovered_url: None,
             last_tab_title: String::new(),
+            last_clipboard_prompt: None,
+            last_bell_alert: None,
         };
 
         (window_id, state)
@@ -457,7 +699,7 @@ impl App {
     /// Open a new tab by creating an in-process window and attaching it as a
     /// macOS native tab of the given "parent" window.
     fn open_new_tab(&mut self, event_loop: &ActiveEventLoop, parent_id: WindowId) {
-        let parent_cwd = self.windows.get(&parent_id).and_then(|s| s.pane_tree.focused_cwd());
+        let parent_cwd = self.windows.get(&parent_id).and_then(|s| s.pane_tree.spawn_cwd());
         let (new_id, new_state) = Self::create_window_state(event_loop, &self.config, parent_cwd.as_deref());
 
         #[cfg(target_os = "macos")]
@@ -772,6 +1014,30 @@ unsafe fn macos_show_rename_dialog(ns_window: &objc2::runtime::AnyObject) {
     }
 }
 
+/// Ask the user whether an application may read the clipboard (OSC 52
+/// with the `ask` policy). Runs a modal NSAlert on the main thread.
+#[cfg(target_os = "macos")]
+unsafe fn macos_confirm_clipboard_read() -> bool {
+    use objc2::{class, msg_send, msg_send_id};
+    use objc2::rc::Retained;
+    use objc2::runtime::AnyObject;
+    use objc2_foundation::NSString;
+
+    let alert: Retained<AnyObject> = msg_send_id![class!(NSAlert), new];
+    let msg_text = NSString::from_str("Allow Clipboard Access?");
+    let _: () = msg_send![&*alert, setMessageText: &*msg_text];
+    let info_text = NSString::from_str("A program running in the terminal wants to read the clipboard.");
+    let _: () = msg_send![&*alert, setInformativeText: &*info_text];
+    let allow_str = NSString::from_str("Allow");
+    let _: () = msg_send![&*alert, addButtonWithTitle: &*allow_str];
+    let deny_str = NSString::from_str("Deny");
+    let _: () = msg_send![&*alert, addButtonWithTitle: &*deny_str];
+
+    // NSAlertFirstButtonReturn == 1000
+    let response: isize = msg_send![&*alert, runModal];
+    response == 1000
+}
+
 /// Swizzle WinitView's mouse-event methods to guard against a winit bug
 /// where `self.window()` panics (via `expect`) when the view's weak
 /// NSWindow reference is nil.  This can happen during window close
@@ -928,7 +1194,9 @@ impl ApplicationHandler for App {
 
         let pty_pending = PTY_DATA_PENDING.load(Ordering::Acquire);
         let animations_active = self.windows.values().any(|s| !s.renderer.animations_settled());
-        let needs_frame = pty_pending || animations_active || self.benchmark_mode;
+        // Keep drawing while an update is held back, so it can time out
+        let sync_pending = self.windows.values().any(|s| s.pane_tree.any_synchronized_update());
+        let needs_frame = pty_pending || animations_active || sync_pending || self.benchmark_mode;
 
         if needs_frame {
             let fps = self.config.animation.target_fps.max(1) as u64;
@@ -1006,17 +1274,20 @@ impl ApplicationHandler for App {
             }
 
             WindowEvent::KeyboardInput { event, .. } => {
-                let (modifiers, app_cursor_keys) = self
+                let (modifiers, app_cursor_keys, keyboard_flags, modify_other_keys) = self
                     .windows
                     .get(&window_id)
                     .map(|s| {
-                        let ack = s.pane_tree.focused_pane()
-                            .map(|p| p.terminal.grid.lock().application_cursor_keys)
-                            .unwrap_or(false);
-                        (s.modifiers, ack)
+                        let (ack, flags, other_keys) = s.pane_tree.focused_pane()
+                            .map(|p| {
+                                let grid = p.terminal.grid.lock();
+                                (grid.application_cursor_keys, grid.keyboard_flags(), grid.modify_other_keys)
+                            })
+                            .unwrap_or_default();
+                        (s.modifiers, ack, flags, other_keys)
                     })
                     .unwrap_or_default();
-                let action = handle_key_event(&event, modifiers, app_cursor_keys);
+                let action = handle_key_event(&event, modifiers, app_cursor_keys, keyboard_flags, modify_other_keys);
                 match action {
                     InputAction::WriteBytes(bytes) => {
                         if !bytes.is_empty() {
@@ -1291,6 +1562,7 @@ impl ApplicationHandler for App {
                         for state in self.windows.values_mut() {
                             let scale = state.window.scale_factor() as f32;
                             let metrics_changed = state.renderer.apply_config(new_config.clone(), scale);
+                            state.pane_tree.set_theme(new_config.colors.theme());
                             if metrics_changed {
                                 let rect = state.content_rect(&new_config);
                                 let layout_rects = state.pane_tree.layout.compute_rects(rect);
@@ -1298,6 +1570,18 @@ impl ApplicationHandler for App {
                             }
                         }
                     }
+                    InputAction::ResetTerminal => {
+                        if let Some(state) = self.windows.get_mut(&window_id) {
+                            let focused_id = state.pane_tree.focused_id;
+                            if state.selection_pane == focused_id {
+                                state.selection = None;
+                            }
+                            if let Some(pane) = state.pane_tree.focused_pane_mut() {
+                                pane.terminal.reset();
+                            }
+                            state.window.request_redraw();
+                        }
+                    }
                     InputAction::ZoomIn => {
                         self.config.font.size = (self.config.font.size + 1.0).min(72.0);
                         self.apply_config_to_all_windows();
@@ -1318,6 +1602,13 @@ impl ApplicationHandler for App {
                 if let Some(state) = self.windows.get_mut(&window_id) {
                     state.cursor_pos = (position.x as f32, position.y as f32);
 
+                    // Report motion to applications with mouse tracking enabled
+                    if !state.mouse_button_down && state.focused_mouse_reporting().is_some() {
+                        let button = state.mouse_report_button.unwrap_or(MouseReportButton::None);
+                        let rect = state.content_rect(&self.config);
+                        state.report_mouse(MouseReportKind::Motion, button, rect);
+                    }
+
                     // Extend selection if mouse button is held
                     if state.mouse_button_down {
                         let (px, py) = state.cursor_pos;
@@ -1346,8 +1637,8 @@ impl ApplicationHandler for App {
                             let pane_rect = *pane_rect;
                             let pane_id = *pane_id;
                             if let Some((abs_row, col)) = state.pixel_to_cell(px, py, pane_rect, pane_id) {
-                                if let Some((col_start, col_end, url)) = state.url_at_cell(pane_id, abs_row, col) {
-                                    state.hovered_url = Some((pane_id, abs_row, col_start, col_end, url));
+                                if let Some((span, url)) = state.url_at_cell(pane_id, abs_row, col) {
+                                    state.hovered_url = Some((pane_id, span, url));
                                     state.window.set_cursor(winit::window::CursorIcon::Pointer);
                                     found_url = true;
                                 }
@@ -1368,22 +1659,21 @@ impl ApplicationHandler for App {
                 ..
             } => {
                 if let Some(state) = self.windows.get_mut(&window_id) {
-                    state.mouse_button_down = true;
                     let rect = state.content_rect(&self.config);
                     let layout_rects = state.pane_tree.layout.compute_rects(rect);
                     let (cx, cy) = state.cursor_pos;
 
                     // First update focus (click-to-focus pane)
-                    for (pane_id, pane_rect) in &layout_rects {
-                        if cx >= pane_rect.x
-                            && cx < pane_rect.x + pane_rect.width
-                            && cy >= pane_rect.y
-                            && cy < pane_rect.y + pane_rect.height
-                        {
-                            state.pane_tree.focused_id = *pane_id;
-                            break;
-                        }
+                    state.focus_pane_at(cx, cy, rect);
+
+                    // Applications with mouse tracking get the click instead
+                    if state.focused_mouse_reporting().is_some() {
+                        state.selection = None;
+                        state.mouse_report_button = Some(MouseReportButton::Left);
+                        state.report_mouse(MouseReportKind::Press, MouseReportButton::Left, rect);
+                        return;
                     }
+                    state.mouse_button_down = true;
 
                     // Start a new selection at the click position
                     let focused_id = state.pane_tree.focused_id;
@@ -1405,12 +1695,18 @@ impl ApplicationHandler for App {
                 ..
             } => {
                 if let Some(state) = self.windows.get_mut(&window_id) {
+                    if state.mouse_report_button == Some(MouseReportButton::Left) {
+                        state.mouse_report_button = None;
+                        let rect = state.content_rect(&self.config);
+                        state.report_mouse(MouseReportKind::Release, MouseReportButton::Left, rect);
+                        return;
+                    }
                     state.mouse_button_down = false;
                     // Finalize selection: if anchor == head, it's a click (clear selection)
                     if let Some(sel) = &state.selection {
                         if sel.is_empty() {
                             // It was a click, not a drag — open URL if hovered
-                            if let Some((_, _, _, _, ref url)) = state.hovered_url {
+                            if let Some((_, _, ref url)) = state.hovered_url {
                                 // Open the URL on a background thread so any
                                 // AppKit re-entrant events triggered by the
                                 // focus change don't fire inside winit's
@@ -1426,10 +1722,55 @@ impl ApplicationHandler for App {
                 }
             }
 
+            WindowEvent::MouseInput { state: button_state, button, .. } => {
+                // Middle/right buttons are only meaningful to applications
+                // with mouse tracking enabled.
+                let report_button = match button {
+                    MouseButton::Middle => MouseReportButton::Middle,
+                    MouseButton::Right => MouseReportButton::Right,
+                    _ => return,
+                };
+                if let Some(state) = self.windows.get_mut(&window_id) {
+                    let rect = state.content_rect(&self.config);
+                    if button_state == ElementState::Pressed {
+                        let (cx, cy) = state.cursor_pos;
+                        state.focus_pane_at(cx, cy, rect);
+                        if state.focused_mouse_reporting().is_some() {
+                            state.mouse_report_button = Some(report_button);
+                            state.report_mouse(MouseReportKind::Press, report_button, rect);
+                        }
+                    } else if state.mouse_report_button == Some(report_button) {
+                        state.mouse_report_button = None;
+                        state.report_mouse(MouseReportKind::Release, report_button, rect);
+                    }
+                }
+            }
+
             WindowEvent::MouseWheel { delta, .. } => {
                 if let Some(state) = self.windows.get_mut(&window_id) {
                     let scale = state.window.scale_factor();
                     let dy = handle_scroll(delta, scale);
+
+                    // Applications with mouse tracking get wheel button events
+                    // (one per line) instead of local scrollback.
+                    if state.focused_mouse_reporting().is_some() {
+                        state.mouse_wheel_remainder += dy;
+                        let cell_h = state.renderer.cell_h.max(1.0);
+                        let lines = (state.mouse_wheel_remainder / cell_h).trunc();
+                        state.mouse_wheel_remainder -= lines * cell_h;
+                        let button = if lines > 0.0 {
+                            MouseReportButton::WheelUp
+                        } else {
+                            MouseReportButton::WheelDown
+                        };
+                        let rect = state.content_rect(&self.config);
+                        for _ in 0..lines.abs() as usize {
+                            state.last_mouse_report_cell = None;
+                            state.report_mouse(MouseReportKind::Press, button, rect);
+                        }
+                        return;
+                    }
+
                     let focused = state.pane_tree.focused_id;
                     state.renderer.ensure_pane_state(focused);
                     if let Some(spring) = state.renderer.scroll_springs.get_mut(&focused) {
@@ -1480,8 +1821,10 @@ impl ApplicationHandler for App {
                     {
                         let new_config = Config::load_or_default();
                         self.config = new_config.clone();
+                        self.notifier.apply_config(&self.config.notifications);
                         let rect = state.content_rect(&self.config);
                         let scale = state.window.scale_factor() as f32;
+                        state.pane_tree.set_theme(new_config.colors.theme());
                         let metrics_changed = state.renderer.apply_config(new_config, scale);
                         if metrics_changed {
                             let layout_rects = state.pane_tree.layout.compute_rects(rect);
@@ -1510,6 +1853,9 @@ impl ApplicationHandler for App {
                     // Drain PTY output
                     state.pane_tree.drain_all_pty_output();
                     PTY_DATA_PENDING.store(false, Ordering::Release);
+                    state.handle_clipboard_requests(&self.config.clipboard);
+                    state.handle_bells(&self.config.bell);
+                    state.handle_notifications(&mut self.notifier, now);
 
                     // Window title is always "Smooth Terminal vX.Y.Z" — tab title shows cwd.
                     #[cfg(target_os = "macos")]
@@ -1539,6 +1885,8 @@ impl ApplicationHandler for App {
                                 grid.reverse_cursor = None;
                             }
                             let reverse_cursor = grid.reverse_cursor;
+                            let cursor_line = reverse_cursor.map_or(row, |(r, _)| r);
+                            let double_width = grid.line_sizes[cursor_line] != LineSize::Single;
                             drop(grid);
 
                             // Inset pane_rect by the border+padding offset so the cursor
@@ -1558,7 +1906,7 @@ impl ApplicationHandler for App {
                                 .unwrap_or((col, row));
 
                             if reverse_cursor.is_some() || cursor_visible {
-                                state.renderer.update_cursor_for_pane(*pane_id, eff_col, eff_row, cursor_rect);
+                                state.renderer.update_cursor_for_pane(*pane_id, eff_col, eff_row, double_width, cursor_rect);
                             }
                             state.renderer.set_cursor_visible(*pane_id, cursor_visible);
 
@@ -1583,7 +1931,7 @@ impl ApplicationHandler for App {
 
                     // Build selection reference for renderer
                     let sel_ref = state.selection.as_ref().map(|s| (state.selection_pane, s));
-                    let hover_ref = state.hovered_url.as_ref().map(|(pid, row, cs, ce, _)| (*pid, *row, *cs, *ce));
+                    let hover_ref = state.hovered_url.as_ref().map(|(pid, span, _)| (*pid, *span));
 
                     // Render
                     match state.renderer.render(&state.pane_tree, rect, sel_ref, hover_ref) {
@@ -1,3 +1,6 @@
+use crate::terminal::clipboard::OSC52_MAX_BYTES;
+use crate::terminal::colors::Theme;
+use crate::terminal::grid::CursorShape;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
@@ -144,6 +147,16 @@ impl ColorsConfig {
         }
         palette
     }
+
+    /// Colors terminal panes start from, before any OSC 4/10/11/12 changes.
+    pub fn theme(&self) -> Theme {
+        Theme {
+            palette: self.ansi_palette(),
+            foreground: parse_hex_color(&self.foreground).unwrap_or([0.8, 0.84, 0.96, 1.0]),
+            background: parse_hex_color(&self.background).unwrap_or([0.118, 0.118, 0.18, 1.0]),
+            cursor: parse_hex_color(&self.cursor).unwrap_or([0.75, 0.0, 1.0, 1.0]),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -165,6 +178,85 @@ impl Default for AnimationConfig {
     }
 }
 
+/// Cursor look until an application asks for another with DECSCUSR.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CursorConfig {
+    pub shape: CursorShape,
+    pub blinking: bool,
+    /// Time the cursor stays shown (and then hidden) while blinking.
+    pub blink_interval_ms: u32,
+}
+
+impl Default for CursorConfig {
+    fn default() -> Self {
+        Self {
+            shape: CursorShape::Block,
+            blinking: false,
+            blink_interval_ms: 500,
+        }
+    }
+}
+
+/// What happens when an application rings the bell (BEL).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BellConfig {
+    /// Briefly flash the pane that rang.
+    pub visual: bool,
+    /// Play the system alert sound.
+    pub audible: bool,
+    /// Ask for attention (bounce the Dock icon) when the window is not focused.
+    pub urgent: bool,
+}
+
+impl Default for BellConfig {
+    fn default() -> Self {
+        Self {
+            visual: true,
+            audible: false,
+            urgent: true,
+        }
+    }
+}
+
+/// Desktop notifications requested by applications with OSC 9 / OSC 777.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationConfig {
+    pub enabled: bool,
+    /// Program and arguments run for each notification. `{title}` and
+    /// `{body}` in an argument are replaced; no shell is involved.
+    pub command: Vec<String>,
+    /// Most notifications shown in any minute; further ones are dropped.
+    pub max_per_minute: u32,
+}
+
+fn default_notification_command() -> Vec<String> {
+    let command: &[&str] = if cfg!(target_os = "macos") {
+        &[
+            "osascript",
+            "-e", "on run argv",
+            "-e", "display notification (item 2 of argv) with title (item 1 of argv)",
+            "-e", "end run",
+            "{title}", "{body}",
+        ]
+    } else {
+        &["notify-send", "{title}", "{body}"]
+    };
+    command.iter().map(|s| s.to_string()).collect()
+}
+
+impl Default for NotificationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            command: default_notification_command(),
+            max_per_minute: 10,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct BackgroundConfig {
     pub image_path: Option<String>,
@@ -192,6 +284,33 @@ impl Default for KeybindingsConfig {
     }
 }
 
+/// Whether applications may read the clipboard via OSC 52.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClipboardReadPolicy {
+    Allow,
+    Deny,
+    /// Prompt each time an application asks.
+    Ask,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClipboardConfig {
+    pub osc52_read: ClipboardReadPolicy,
+    /// Largest OSC 52 payload (decoded bytes) accepted for writes or sent for reads.
+    pub osc52_max_bytes: usize,
+}
+
+impl Default for ClipboardConfig {
+    fn default() -> Self {
+        Self {
+            osc52_read: ClipboardReadPolicy::Ask,
+            osc52_max_bytes: OSC52_MAX_BYTES,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Config {
     #[serde(default)]
@@ -203,9 +322,17 @@ pub struct Config {
     #[serde(default)]
     pub animation: AnimationConfig,
     #[serde(default)]
+    pub cursor: CursorConfig,
+    #[serde(default)]
+    pub bell: BellConfig,
+    #[serde(default)]
+    pub notifications: NotificationConfig,
+    #[serde(default)]
     pub background: BackgroundConfig,
     #[serde(default)]
     pub keybindings: KeybindingsConfig,
+    #[serde(default)]
+    pub clipboard: ClipboardConfig,
 }
 
 impl Config {
@@ -454,6 +581,43 @@ mod tests {
         assert_eq!(cfg.colors.background, cfg2.colors.background);
     }
 
+    #[test]
+    fn clipboard_section_parses() {
+        let cfg: Config = toml::from_str("[clipboard]\nosc52_read = \"deny\"\n").unwrap();
+        assert_eq!(cfg.clipboard.osc52_read, ClipboardReadPolicy::Deny);
+        assert_eq!(cfg.clipboard.osc52_max_bytes, ClipboardConfig::default().osc52_max_bytes);
+    }
+
+    #[test]
+    fn cursor_section_parses() {
+        let cfg: Config = toml::from_str("[cursor]\nshape = \"bar\"\n").unwrap();
+        assert_eq!(cfg.cursor.shape, CursorShape::Bar);
+        assert!(!cfg.cursor.blinking);
+        assert_eq!(cfg.cursor.blink_interval_ms, 500);
+    }
+
+    #[test]
+    fn bell_section_parses() {
+        let cfg: Config = toml::from_str("[bell]
+audible = true
+").unwrap();
+        assert!(cfg.bell.visual && cfg.bell.audible && cfg.bell.urgent);
+        let cfg: Config = toml::from_str("").unwrap();
+        assert!(!cfg.bell.audible);
+    }
+
+    #[test]
+    fn notifications_section_parses() {
+        let cfg: Config = toml::from_str("[notifications]
+command = [\"my-notify\", \"{body}\"]
+").unwrap();
+        assert!(cfg.notifications.enabled);
+        assert_eq!(cfg.notifications.command, vec!["my-notify", "{body}"]);
+        assert_eq!(cfg.notifications.max_per_minute, 10);
+        let cfg: Config = toml::from_str("").unwrap();
+        assert_eq!(cfg.notifications.command, default_notification_command());
+    }
+
     // ── dark_colors / light_colors ──────────────────────────────────────
 
     #[test]
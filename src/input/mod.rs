@@ -1,5 +1,8 @@
 use winit::event::{ElementState, KeyEvent, MouseScrollDelta};
 use winit::keyboard::{Key, KeyCode, ModifiersState, NamedKey, PhysicalKey};
+use winit::platform::modifier_supplement::KeyEventExtModifierSupplement;
+
+use crate::terminal::grid::{KeyboardFlags, MouseEncoding};
 
 pub enum InputAction {
     WriteBytes(Vec<u8>),
@@ -34,20 +37,39 @@ pub enum InputAction {
     ResizePaneUp,
     ResizePaneDown,
     ToggleTheme,
+    ResetTerminal,
     ZoomIn,
     ZoomOut,
     ZoomReset,
     None,
 }
 
+/// Press, repeat or release, as reported by the kitty keyboard protocol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyEventKind {
+    Press,
+    Repeat,
+    Release,
+}
+
 pub fn handle_key_event(
     event: &KeyEvent,
     modifiers: ModifiersState,
     application_cursor_keys: bool,
+    keyboard_flags: KeyboardFlags,
+    modify_other_keys: u8,
 ) -> InputAction {
     if event.state != ElementState::Pressed {
+        // Releases only go to applications that asked for them
+        if keyboard_flags.contains(KeyboardFlags::REPORT_EVENT_TYPES) && !modifiers.super_key() {
+            let unmodified = event.key_without_modifiers();
+            return InputAction::WriteBytes(encode_kitty_key(
+                &event.logical_key, &unmodified, modifiers, KeyEventKind::Release, keyboard_flags,
+            ));
+        }
         return InputAction::None;
     }
+    let kind = if event.repeat { KeyEventKind::Repeat } else { KeyEventKind::Press };
 
     let cmd = modifiers.super_key();
     let shift = modifiers.shift_key();
@@ -106,6 +128,10 @@ pub fn handle_key_event(
             if cmd && shift && !ctrl && lc == "l" {
                 return InputAction::ToggleTheme;
             }
+            // Cmd+Shift+R: reset the focused pane's terminal
+            if cmd && shift && !ctrl && lc == "r" {
+                return InputAction::ResetTerminal;
+            }
             // Cmd+A: select all
             if cmd && !shift && !ctrl && lc == "a" {
                 return InputAction::SelectAll;
@@ -150,6 +176,15 @@ pub fn handle_key_event(
             if cmd {
                 return InputAction::None; // Don't pass Cmd shortcuts to shell
             }
+            if !keyboard_flags.is_empty() {
+                let unmodified = event.key_without_modifiers();
+                return InputAction::WriteBytes(encode_kitty_key(
+                    &event.logical_key, &unmodified, modifiers, kind, keyboard_flags,
+                ));
+            }
+            if let Some(bytes) = encode_modify_other_keys(&event.logical_key, modifiers, modify_other_keys) {
+                return InputAction::WriteBytes(bytes);
+            }
             return InputAction::WriteBytes(encode_key_character(ch, ctrl, alt));
         }
         Key::Named(named) => {
@@ -187,6 +222,15 @@ pub fn handle_key_event(
             if cmd {
                 return InputAction::None;
             }
+            if !keyboard_flags.is_empty() {
+                let unmodified = event.key_without_modifiers();
+                return InputAction::WriteBytes(encode_kitty_key(
+                    &event.logical_key, &unmodified, modifiers, kind, keyboard_flags,
+                ));
+            }
+            if let Some(bytes) = encode_modify_other_keys(&event.logical_key, modifiers, modify_other_keys) {
+                return InputAction::WriteBytes(bytes);
+            }
             return InputAction::WriteBytes(encode_named_key(named, modifiers, application_cursor_keys));
         }
         _ => {}
@@ -293,6 +337,217 @@ fn encode_arrow(key: NamedKey, has_mods: bool, shift: bool, ctrl: bool, alt: boo
     }
 }
 
+/// Encode a key with the kitty keyboard protocol (`CSI key ; mods u` and
+/// friends), as enabled by `flags`. `key` is the logical key and
+/// `unmodified` the same key without modifiers applied, which gives the
+/// key code of text keys. An empty result means nothing is sent.
+pub(crate) fn encode_kitty_key(
+    key: &Key,
+    unmodified: &Key,
+    modifiers: ModifiersState,
+    kind: KeyEventKind,
+    flags: KeyboardFlags,
+) -> Vec<u8> {
+    let all_keys = flags.contains(KeyboardFlags::REPORT_ALL_KEYS);
+    let event_types = flags.contains(KeyboardFlags::REPORT_EVENT_TYPES);
+    if kind == KeyEventKind::Release && !event_types {
+        return vec![];
+    }
+    let shift = modifiers.shift_key();
+    let ctrl = modifiers.control_key();
+    let alt = modifiers.alt_key();
+    let sup = modifiers.super_key();
+    let mods = 1 + shift as u32 + 2 * alt as u32 + 4 * ctrl as u32 + 8 * sup as u32;
+
+    // The text a key types, and its code: the unshifted character
+    let text = match key {
+        Key::Character(s) => Some(s.as_str()),
+        Key::Named(NamedKey::Space) => Some(" "),
+        _ => None,
+    };
+    let (code, final_byte, is_modifier) = if text.is_some() {
+        let base = match unmodified {
+            Key::Character(base) => base.chars().next(),
+            _ => Some(' '),
+        };
+        let Some(base) = base else { return vec![] };
+        (base.to_lowercase().next().unwrap_or(base) as u32, b'u', false)
+    } else if let Key::Named(named) = key {
+        let Some((code, final_byte)) = kitty_functional_key(named) else { return vec![] };
+        (code, final_byte, is_modifier_key(named))
+    } else {
+        return vec![];
+    };
+
+    if is_modifier && !all_keys {
+        return vec![];
+    }
+    if !all_keys {
+        // Plain text and unmodified Enter/Tab/Backspace keep their legacy
+        // bytes, so a shell stays usable if an application exits without
+        // popping its flags
+        if let Some(text) = text {
+            if !ctrl && !alt && !sup {
+                return if kind == KeyEventKind::Release { vec![] } else { text.as_bytes().to_vec() };
+            }
+        }
+        if mods == 1 && matches!(key, Key::Named(NamedKey::Enter | NamedKey::Tab | NamedKey::Backspace)) {
+            return match (kind, key) {
+                (KeyEventKind::Release, _) => vec![],
+                (_, Key::Named(NamedKey::Enter)) => vec![b'\r'],
+                (_, Key::Named(NamedKey::Tab)) => vec![b'\t'],
+                _ => vec![0x7f],
+            };
+        }
+    }
+
+    let mut key_part = code.to_string();
+    if flags.contains(KeyboardFlags::REPORT_ALTERNATE_KEYS) && shift {
+        if let Some(shifted) = text.and_then(|t| t.chars().next()) {
+            if shifted as u32 != code {
+                key_part.push_str(&format!(":{}", shifted as u32));
+            }
+        }
+    }
+    let event = match kind {
+        KeyEventKind::Press => 1,
+        KeyEventKind::Repeat => 2,
+        KeyEventKind::Release => 3,
+    };
+    let mods_part = if event_types && event != 1 {
+        format!("{}:{}", mods, event)
+    } else if mods != 1 {
+        mods.to_string()
+    } else {
+        String::new()
+    };
+    let text_part = match text {
+        Some(text) if all_keys && flags.contains(KeyboardFlags::REPORT_TEXT) && kind != KeyEventKind::Release && !ctrl => {
+            text.chars().map(|c| (c as u32).to_string()).collect::<Vec<_>>().join(":")
+        }
+        _ => String::new(),
+    };
+
+    let mut params = if final_byte != b'u' && code == 1 && mods_part.is_empty() {
+        // CSI A rather than CSI 1 A
+        String::new()
+    } else {
+        key_part
+    };
+    if !mods_part.is_empty() || !text_part.is_empty() {
+        params.push(';');
+        params.push_str(&mods_part);
+    }
+    if !text_part.is_empty() {
+        params.push(';');
+        params.push_str(&text_part);
+    }
+    let mut bytes = format!("\x1b[{}", params).into_bytes();
+    bytes.push(final_byte);
+    bytes
+}
+
+/// Encode a modified key as xterm does with modifyOtherKeys enabled:
+/// `CSI 27 ; mods ; code ~`. At level 1 only keys without a usable legacy
+/// encoding (Ctrl+digit, Ctrl+Shift+letter, Ctrl+Enter, ...) are encoded;
+/// level 2 covers every modified key. `None` means the legacy encoding
+/// applies.
+pub(crate) fn encode_modify_other_keys(key: &Key, modifiers: ModifiersState, level: u8) -> Option<Vec<u8>> {
+    if level == 0 {
+        return None;
+    }
+    let shift = modifiers.shift_key();
+    let ctrl = modifiers.control_key();
+    let alt = modifiers.alt_key();
+    let (c, is_text) = match key {
+        Key::Character(s) => {
+            let mut chars = s.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => (c, true),
+                _ => return None,
+            }
+        }
+        Key::Named(NamedKey::Enter) => ('\r', false),
+        Key::Named(NamedKey::Tab) => ('\t', false),
+        Key::Named(NamedKey::Backspace) => ('\x7f', false),
+        Key::Named(NamedKey::Escape) => ('\x1b', false),
+        Key::Named(NamedKey::Space) => (' ', true),
+        _ => return None,
+    };
+    // Shift alone just types the shifted character
+    if !ctrl && !alt && (!shift || is_text) {
+        return None;
+    }
+    let has_legacy = match (ctrl, alt) {
+        // Alt+key: ESC prefix
+        (false, true) => true,
+        // Ctrl+key: a C0 control code, which can't carry Shift
+        (true, _) => !shift && is_text && (c.is_ascii_alphabetic() || "@[\\]^_ ".contains(c)),
+        (false, false) => true,
+    };
+    if level < 2 && has_legacy {
+        return None;
+    }
+    let c = if shift { c.to_ascii_uppercase() } else { c };
+    let mods = 1 + shift as u32 + 2 * alt as u32 + 4 * ctrl as u32;
+    Some(format!("\x1b[27;{};{}~", mods, c as u32).into_bytes())
+}
+
+/// Code and final byte of a non-text key in the kitty keyboard protocol.
+fn kitty_functional_key(key: &NamedKey) -> Option<(u32, u8)> {
+    let encoding = match key {
+        NamedKey::Escape => (27, b'u'),
+        NamedKey::Enter => (13, b'u'),
+        NamedKey::Tab => (9, b'u'),
+        NamedKey::Backspace => (127, b'u'),
+        NamedKey::Insert => (2, b'~'),
+        NamedKey::Delete => (3, b'~'),
+        NamedKey::ArrowLeft => (1, b'D'),
+        NamedKey::ArrowRight => (1, b'C'),
+        NamedKey::ArrowUp => (1, b'A'),
+        NamedKey::ArrowDown => (1, b'B'),
+        NamedKey::PageUp => (5, b'~'),
+        NamedKey::PageDown => (6, b'~'),
+        NamedKey::Home => (1, b'H'),
+        NamedKey::End => (1, b'F'),
+        NamedKey::F1 => (1, b'P'),
+        NamedKey::F2 => (1, b'Q'),
+        // Not CSI R, which would read as a cursor position report
+        NamedKey::F3 => (13, b'~'),
+        NamedKey::F4 => (1, b'S'),
+        NamedKey::F5 => (15, b'~'),
+        NamedKey::F6 => (17, b'~'),
+        NamedKey::F7 => (18, b'~'),
+        NamedKey::F8 => (19, b'~'),
+        NamedKey::F9 => (20, b'~'),
+        NamedKey::F10 => (21, b'~'),
+        NamedKey::F11 => (23, b'~'),
+        NamedKey::F12 => (24, b'~'),
+        NamedKey::CapsLock => (57358, b'u'),
+        NamedKey::ScrollLock => (57359, b'u'),
+        NamedKey::NumLock => (57360, b'u'),
+        NamedKey::PrintScreen => (57361, b'u'),
+        NamedKey::Pause => (57362, b'u'),
+        NamedKey::ContextMenu => (57363, b'u'),
+        // winit doesn't say which side; report the left one
+        NamedKey::Shift => (57441, b'u'),
+        NamedKey::Control => (57442, b'u'),
+        NamedKey::Alt => (57443, b'u'),
+        NamedKey::Super => (57444, b'u'),
+        NamedKey::Hyper => (57445, b'u'),
+        NamedKey::Meta => (57446, b'u'),
+        _ => return None,
+    };
+    Some(encoding)
+}
+
+fn is_modifier_key(key: &NamedKey) -> bool {
+    matches!(
+        key,
+        NamedKey::Shift | NamedKey::Control | NamedKey::Alt | NamedKey::Super | NamedKey::Hyper | NamedKey::Meta
+    )
+}
+
 pub fn handle_scroll(delta: MouseScrollDelta, _scale_factor: f64) -> f32 {
     match delta {
         MouseScrollDelta::LineDelta(_, y) => y * 20.0,
@@ -300,6 +555,71 @@ pub fn handle_scroll(delta: MouseScrollDelta, _scale_factor: f64) -> f32 {
     }
 }
 
+/// A mouse button as seen by the xterm mouse protocol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseReportButton {
+    Left,
+    Middle,
+    Right,
+    WheelUp,
+    WheelDown,
+    /// Motion with no button held (mode 1003 only).
+    None,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseReportKind {
+    Press,
+    Release,
+    Motion,
+}
+
+/// Encode a mouse event for an application that enabled mouse tracking.
+/// `col`/`row` are 0-based visible grid coordinates. Returns `None` when the
+/// position cannot be represented in the legacy encoding (beyond column 223).
+pub fn encode_mouse_event(
+    kind: MouseReportKind,
+    button: MouseReportButton,
+    col: usize,
+    row: usize,
+    modifiers: ModifiersState,
+    encoding: MouseEncoding,
+) -> Option<Vec<u8>> {
+    let mut code: u32 = match button {
+        MouseReportButton::Left => 0,
+        MouseReportButton::Middle => 1,
+        MouseReportButton::Right => 2,
+        MouseReportButton::None => 3,
+        MouseReportButton::WheelUp => 64,
+        MouseReportButton::WheelDown => 65,
+    };
+    // Legacy encodings can't say which button was released
+    if kind == MouseReportKind::Release && encoding != MouseEncoding::Sgr {
+        code = 3;
+    }
+    if kind == MouseReportKind::Motion {
+        code += 32;
+    }
+    if modifiers.shift_key() { code += 4; }
+    if modifiers.alt_key() { code += 8; }
+    if modifiers.control_key() { code += 16; }
+
+    let (x, y) = (col + 1, row + 1);
+    match encoding {
+        MouseEncoding::Sgr => {
+            let suffix = if kind == MouseReportKind::Release { 'm' } else { 'M' };
+            Some(format!("\x1b[<{};{};{}{}", code, x, y, suffix).into_bytes())
+        }
+        MouseEncoding::Urxvt => Some(format!("\x1b[{};{};{}M", code + 32, x, y).into_bytes()),
+        MouseEncoding::Default => {
+            if x > 223 || y > 223 {
+                return None;
+            }
+            Some(vec![0x1b, b'[', b'M', (code + 32) as u8, (x + 32) as u8, (y + 32) as u8])
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -487,4 +807,186 @@ mod tests {
         // Shift alone = modifier 2
         assert_eq!(encode_named_key(&NamedKey::ArrowUp, mods(true, false, false), false), vec![0x1b, b'[', b'1', b';', b'2', b'A']);
     }
+
+    // ── Kitty keyboard protocol ─────────────────────────────────────────
+
+    fn ch(s: &str) -> Key {
+        Key::Character(s.into())
+    }
+
+    fn named(key: NamedKey) -> Key {
+        Key::Named(key)
+    }
+
+    #[test]
+    fn kitty_encoding_table() {
+        use KeyEventKind::*;
+        let none = mods(false, false, false);
+        let shift = mods(true, false, false);
+        let ctrl = mods(false, true, false);
+        let ctrl_shift = mods(true, true, false);
+        let alt = mods(false, false, true);
+        let d = KeyboardFlags(1);
+        let events = KeyboardFlags(1 | 2);
+        let all = KeyboardFlags(1 | 8);
+        let alternate = KeyboardFlags(1 | 4 | 8);
+        let text = KeyboardFlags(1 | 8 | 16);
+
+        // (key, unmodified key, modifiers, kind, flags, expected)
+        type Case = (Key, Key, ModifiersState, KeyEventKind, KeyboardFlags, &'static [u8]);
+        let cases: Vec<Case> = vec![
+            // Disambiguate: plain text stays text, modified keys become CSI u
+            (ch("a"), ch("a"), none, Press, d, b"a"),
+            (ch("A"), ch("a"), shift, Press, d, b"A"),
+            (ch("a"), ch("a"), ctrl, Press, d, b"\x1b[97;5u"),
+            (ch("A"), ch("a"), ctrl_shift, Press, d, b"\x1b[97;6u"),
+            (ch("a"), ch("a"), alt, Press, d, b"\x1b[97;3u"),
+            (named(NamedKey::Escape), named(NamedKey::Escape), none, Press, d, b"\x1b[27u"),
+            (named(NamedKey::Enter), named(NamedKey::Enter), none, Press, d, b"\r"),
+            (named(NamedKey::Enter), named(NamedKey::Enter), ctrl, Press, d, b"\x1b[13;5u"),
+            (named(NamedKey::Tab), named(NamedKey::Tab), ctrl, Press, d, b"\x1b[9;5u"),
+            (named(NamedKey::Tab), named(NamedKey::Tab), shift, Press, d, b"\x1b[9;2u"),
+            (named(NamedKey::Backspace), named(NamedKey::Backspace), none, Press, d, b"\x7f"),
+            (named(NamedKey::Space), named(NamedKey::Space), ctrl, Press, d, b"\x1b[32;5u"),
+            (named(NamedKey::ArrowUp), named(NamedKey::ArrowUp), none, Press, d, b"\x1b[A"),
+            (named(NamedKey::ArrowLeft), named(NamedKey::ArrowLeft), ctrl, Press, d, b"\x1b[1;5D"),
+            (named(NamedKey::F3), named(NamedKey::F3), none, Press, d, b"\x1b[13~"),
+            (named(NamedKey::F1), named(NamedKey::F1), shift, Press, d, b"\x1b[1;2P"),
+            (named(NamedKey::Delete), named(NamedKey::Delete), alt, Press, d, b"\x1b[3;3~"),
+            (named(NamedKey::Shift), named(NamedKey::Shift), shift, Press, d, b""),
+            // Releases and repeats need the event types flag
+            (ch("a"), ch("a"), ctrl, Release, d, b""),
+            (ch("a"), ch("a"), ctrl, Release, events, b"\x1b[97;5:3u"),
+            (ch("a"), ch("a"), ctrl, Repeat, events, b"\x1b[97;5:2u"),
+            (named(NamedKey::ArrowUp), named(NamedKey::ArrowUp), none, Release, events, b"\x1b[1;1:3A"),
+            (ch("a"), ch("a"), none, Release, events, b""),
+            (named(NamedKey::Enter), named(NamedKey::Enter), none, Release, events, b""),
+            // All keys as escape codes, including modifiers themselves
+            (ch("a"), ch("a"), none, Press, all, b"\x1b[97u"),
+            (named(NamedKey::Enter), named(NamedKey::Enter), none, Press, all, b"\x1b[13u"),
+            (named(NamedKey::Shift), named(NamedKey::Shift), shift, Press, all, b"\x1b[57441;2u"),
+            // Alternate (shifted) key and associated text
+            (ch("A"), ch("a"), shift, Press, alternate, b"\x1b[97:65;2u"),
+            (ch("a"), ch("a"), none, Press, alternate, b"\x1b[97u"),
+            (ch("A"), ch("a"), shift, Press, text, b"\x1b[97;2;65u"),
+            (ch("a"), ch("a"), none, Press, text, b"\x1b[97;;97u"),
+            (ch("a"), ch("a"), ctrl, Press, text, b"\x1b[97;5u"),
+        ];
+        for (key, unmodified, modifiers, kind, flags, expected) in cases {
+            assert_eq!(
+                encode_kitty_key(&key, &unmodified, modifiers, kind, flags),
+                expected.to_vec(),
+                "{:?} {:?} {:?} flags {}",
+                key, modifiers, kind, flags.0
+            );
+        }
+    }
+
+    #[test]
+    fn modify_other_keys_table() {
+        let none = mods(false, false, false);
+        let shift = mods(true, false, false);
+        let ctrl = mods(false, true, false);
+        let ctrl_shift = mods(true, true, false);
+        let alt = mods(false, false, true);
+
+        // (key, modifiers, level, expected; None = legacy encoding)
+        type Case = (Key, ModifiersState, u8, Option<&'static [u8]>);
+        let cases: Vec<Case> = vec![
+            (ch("a"), ctrl, 0, None),
+            (ch("a"), none, 2, None),
+            (ch("A"), shift, 2, None),
+            // Level 1: only keys legacy encodings can't express
+            (ch("a"), ctrl, 1, None),
+            (ch("a"), alt, 1, None),
+            (ch("A"), ctrl_shift, 1, Some(b"\x1b[27;6;65~")),
+            (ch("1"), ctrl, 1, Some(b"\x1b[27;5;49~")),
+            (ch(";"), ctrl, 1, Some(b"\x1b[27;5;59~")),
+            (named(NamedKey::Enter), ctrl, 1, Some(b"\x1b[27;5;13~")),
+            (named(NamedKey::Tab), ctrl, 1, Some(b"\x1b[27;5;9~")),
+            (named(NamedKey::Tab), shift, 1, None),
+            (named(NamedKey::Space), ctrl, 1, None),
+            // Level 2: every modified key
+            (ch("a"), ctrl, 2, Some(b"\x1b[27;5;97~")),
+            (ch("a"), alt, 2, Some(b"\x1b[27;3;97~")),
+            (named(NamedKey::Tab), shift, 2, Some(b"\x1b[27;2;9~")),
+            (named(NamedKey::Escape), alt, 2, Some(b"\x1b[27;3;27~")),
+            (named(NamedKey::ArrowUp), ctrl, 2, None),
+        ];
+        for (key, modifiers, level, expected) in cases {
+            assert_eq!(
+                encode_modify_other_keys(&key, modifiers, level),
+                expected.map(|e| e.to_vec()),
+                "{:?} {:?} level {}",
+                key, modifiers, level
+            );
+        }
+    }
+
+    // ── encode_mouse_event ──────────────────────────────────────────────
+
+    #[test]
+    fn mouse_sgr_press_and_release() {
+        let none = mods(false, false, false);
+        assert_eq!(
+            encode_mouse_event(MouseReportKind::Press, MouseReportButton::Left, 0, 0, none, MouseEncoding::Sgr),
+            Some(b"\x1b[<0;1;1M".to_vec())
+        );
+        assert_eq!(
+            encode_mouse_event(MouseReportKind::Release, MouseReportButton::Right, 9, 4, none, MouseEncoding::Sgr),
+            Some(b"\x1b[<2;10;5m".to_vec())
+        );
+    }
+
+    #[test]
+    fn mouse_sgr_drag_with_ctrl() {
+        assert_eq!(
+            encode_mouse_event(MouseReportKind::Motion, MouseReportButton::Left, 2, 3, mods(false, true, false), MouseEncoding::Sgr),
+            Some(b"\x1b[<48;3;4M".to_vec())
+        );
+    }
+
+    #[test]
+    fn mouse_sgr_wheel_and_plain_motion() {
+        let none = mods(false, false, false);
+        assert_eq!(
+            encode_mouse_event(MouseReportKind::Press, MouseReportButton::WheelDown, 0, 0, none, MouseEncoding::Sgr),
+            Some(b"\x1b[<65;1;1M".to_vec())
+        );
+        assert_eq!(
+            encode_mouse_event(MouseReportKind::Motion, MouseReportButton::None, 0, 0, none, MouseEncoding::Sgr),
+            Some(b"\x1b[<35;1;1M".to_vec())
+        );
+    }
+
+    #[test]
+    fn mouse_legacy_encoding() {
+        let none = mods(false, false, false);
+        assert_eq!(
+            encode_mouse_event(MouseReportKind::Press, MouseReportButton::Left, 0, 0, none, MouseEncoding::Default),
+            Some(vec![0x1b, b'[', b'M', 32, 33, 33])
+        );
+        // Release always reports button 3
+        assert_eq!(
+            encode_mouse_event(MouseReportKind::Release, MouseReportButton::Left, 4, 1, none, MouseEncoding::Default),
+            Some(vec![0x1b, b'[', b'M', 35, 37, 34])
+        );
+    }
+
+    #[test]
+    fn mouse_legacy_out_of_range() {
+        let none = mods(false, false, false);
+        assert_eq!(
+            encode_mouse_event(MouseReportKind::Press, MouseReportButton::Left, 223, 0, none, MouseEncoding::Default),
+            None
+        );
+    }
+
+    #[test]
+    fn mouse_urxvt_encoding() {
+        assert_eq!(
+            encode_mouse_event(MouseReportKind::Press, MouseReportButton::Middle, 299, 0, mods(true, false, false), MouseEncoding::Urxvt),
+            Some(b"\x1b[37;300;1M".to_vec())
+        );
+    }
 }
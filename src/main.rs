@@ -3,6 +3,7 @@ mod app;
 mod config;
 mod input;
 mod menubar;
+mod notifier;
 mod pane;
 mod renderer;
 mod terminal;
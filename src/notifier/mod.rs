@@ -0,0 +1,166 @@
+use crate::config::NotificationConfig;
+use crate::terminal::notification::Notification;
+use anyhow::{Context, Result};
+use std::collections::VecDeque;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// Somewhere to show desktop notifications.
+pub trait NotificationBackend {
+    fn notify(&mut self, notification: &Notification) -> Result<()>;
+}
+
+/// Shows notifications by running a configured command, e.g. `notify-send`.
+pub struct CommandBackend {
+    command: Vec<String>,
+}
+
+impl CommandBackend {
+    pub fn new(command: Vec<String>) -> Self {
+        Self { command }
+    }
+
+    /// The command for one notification, with `{title}` and `{body}`
+    /// substituted in each argument. `None` if no program is configured.
+    fn command(&self, notification: &Notification) -> Option<Command> {
+        let (program, args) = self.command.split_first()?;
+        let mut command = Command::new(program);
+        command.args(args.iter().map(|arg| {
+            arg.replace("{title}", &notification.title).replace("{body}", &notification.body)
+        }));
+        Some(command)
+    }
+}
+
+impl NotificationBackend for CommandBackend {
+    fn notify(&mut self, notification: &Notification) -> Result<()> {
+        let mut command = self.command(notification).context("no notification command configured")?;
+        let mut child = command
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .with_context(|| format!("failed to run {:?}", self.command[0]))?;
+        // Reap it in the background so a slow notifier never stalls a frame
+        std::thread::spawn(move || child.wait());
+        Ok(())
+    }
+}
+
+/// Allows at most `max` events in any `window`-long span.
+struct RateLimiter {
+    max: usize,
+    window: Duration,
+    recent: VecDeque<Instant>,
+}
+
+impl RateLimiter {
+    fn new(max: usize, window: Duration) -> Self {
+        Self { max, window, recent: VecDeque::new() }
+    }
+
+    fn allow(&mut self, now: Instant) -> bool {
+        while self.recent.front().is_some_and(|&t| now.duration_since(t) >= self.window) {
+            self.recent.pop_front();
+        }
+        if self.recent.len() >= self.max {
+            return false;
+        }
+        self.recent.push_back(now);
+        true
+    }
+}
+
+/// Rate-limited delivery of application notifications to a backend.
+pub struct Notifier {
+    enabled: bool,
+    backend: Box<dyn NotificationBackend>,
+    limiter: RateLimiter,
+}
+
+impl Notifier {
+    pub fn new(config: &NotificationConfig) -> Self {
+        Self::with_backend(config, Box::new(CommandBackend::new(config.command.clone())))
+    }
+
+    pub fn with_backend(config: &NotificationConfig, backend: Box<dyn NotificationBackend>) -> Self {
+        Self {
+            enabled: config.enabled,
+            backend,
+            limiter: RateLimiter::new(config.max_per_minute as usize, Duration::from_secs(60)),
+        }
+    }
+
+    /// Pick up a reloaded config. Recent notifications still count
+    /// against the new limit.
+    pub fn apply_config(&mut self, config: &NotificationConfig) {
+        self.enabled = config.enabled;
+        self.backend = Box::new(CommandBackend::new(config.command.clone()));
+        self.limiter.max = config.max_per_minute as usize;
+    }
+
+    /// Show `notification` unless notifications are off or over the rate
+    /// limit. Returns whether it was passed to the backend.
+    pub fn deliver(&mut self, notification: &Notification, now: Instant) -> bool {
+        if !self.enabled || !self.limiter.allow(now) {
+            return false;
+        }
+        if let Err(e) = self.backend.notify(notification) {
+            log::warn!("Failed to show notification: {:#}", e);
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    struct Recorder(Arc<Mutex<Vec<Notification>>>);
+
+    impl NotificationBackend for Recorder {
+        fn notify(&mut self, notification: &Notification) -> Result<()> {
+            self.0.lock().unwrap().push(notification.clone());
+            Ok(())
+        }
+    }
+
+    fn note(body: &str) -> Notification {
+        Notification { title: "t".to_string(), body: body.to_string() }
+    }
+
+    #[test]
+    fn command_substitutes_placeholders() {
+        let backend = CommandBackend::new(
+            ["printf", "%s|%s", "[{title}]", "{body}"].iter().map(|s| s.to_string()).collect(),
+        );
+        let n = Notification { title: "make".to_string(), body: "done; $HOME `x`".to_string() };
+        let output = backend.command(&n).unwrap().output().unwrap();
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), "[make]|done; $HOME `x`");
+        assert!(CommandBackend::new(Vec::new()).command(&n).is_none());
+    }
+
+    #[test]
+    fn deliveries_are_rate_limited() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let config = NotificationConfig { max_per_minute: 2, ..Default::default() };
+        let mut notifier = Notifier::with_backend(&config, Box::new(Recorder(seen.clone())));
+        let start = Instant::now();
+        assert!(notifier.deliver(&note("a"), start));
+        assert!(notifier.deliver(&note("b"), start + Duration::from_secs(1)));
+        assert!(!notifier.deliver(&note("c"), start + Duration::from_secs(2)));
+        assert!(notifier.deliver(&note("d"), start + Duration::from_secs(60)));
+        let bodies: Vec<_> = seen.lock().unwrap().iter().map(|n| n.body.clone()).collect();
+        assert_eq!(bodies, ["a", "b", "d"]);
+    }
+
+    #[test]
+    fn disabled_notifier_drops_everything() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let config = NotificationConfig { enabled: false, ..Default::default() };
+        let mut notifier = Notifier::with_backend(&config, Box::new(Recorder(seen.clone())));
+        assert!(!notifier.deliver(&note("a"), Instant::now()));
+        assert!(seen.lock().unwrap().is_empty());
+    }
+}
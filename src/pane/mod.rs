@@ -7,17 +7,20 @@ use std::path::{Path, PathBuf};
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Direction { Left, Right, Up, Down }
 
+use crate::terminal::colors::Theme;
 use crate::terminal::Terminal;
 
 pub struct Pane {
     pub id: usize,
     pub terminal: Terminal,
+    /// The bell rang while the pane wasn't focused; shown until it is.
+    pub bell: bool,
 }
 
 impl Pane {
-    pub fn new(id: usize, cols: usize, rows: usize, cwd: Option<&Path>) -> Result<Self> {
-        let terminal = Terminal::new(cols, rows, cwd)?;
-        Ok(Self { id, terminal })
+    pub fn new(id: usize, cols: usize, rows: usize, cell_size: (f32, f32), cwd: Option<&Path>) -> Result<Self> {
+        let terminal = Terminal::new(cols, rows, cell_size, cwd)?;
+        Ok(Self { id, terminal, bell: false })
     }
 }
 
@@ -26,20 +29,34 @@ pub struct PaneTree {
     pub layout: Layout,
     pub focused_id: usize,
     next_id: usize,
+    /// Configured colors, applied to every pane including ones split off later.
+    theme: Theme,
 }
 
 impl PaneTree {
-    pub fn new(cols: usize, rows: usize, cwd: Option<&Path>) -> Result<Self> {
-        let pane = Pane::new(0, cols, rows, cwd)?;
+    pub fn new(cols: usize, rows: usize, cell_size: (f32, f32), cwd: Option<&Path>) -> Result<Self> {
+        let pane = Pane::new(0, cols, rows, cell_size, cwd)?;
         let layout = Layout::Leaf(0);
         Ok(Self {
             panes: vec![pane],
             layout,
             focused_id: 0,
             next_id: 1,
+            theme: Theme::default(),
         })
     }
 
+    /// Set the configured colors for all panes. Colors changed at runtime
+    /// by applications (OSC 4/10/11/12) stay in effect.
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+        for pane in &self.panes {
+            let mut grid = pane.terminal.grid.lock();
+            grid.colors.set_theme(theme);
+            grid.generation = grid.generation.wrapping_add(1);
+        }
+    }
+
     pub fn pane_by_id(&self, id: usize) -> Option<&Pane> {
         self.panes.iter().find(|p| p.id == id)
     }
@@ -56,14 +73,26 @@ impl PaneTree {
         self.pane_by_id_mut(self.focused_id)
     }
 
+    /// Working directory of the focused pane: the shell's OSC 7 report when
+    /// available, otherwise looked up from the shell process.
     pub fn focused_cwd(&self) -> Option<PathBuf> {
-        self.focused_pane().and_then(|p| p.terminal.pty.get_cwd())
+        let pane = self.focused_pane()?;
+        let reported = pane.terminal.grid.lock().cwd.clone();
+        reported.or_else(|| pane.terminal.pty.get_cwd())
+    }
+
+    /// Directory to start a new pane or tab in. Like `focused_cwd`, but an
+    /// OSC 7 path that doesn't exist here (e.g. reported over ssh) is skipped.
+    pub fn spawn_cwd(&self) -> Option<PathBuf> {
+        self.focused_cwd()
+            .filter(|p| p.is_dir())
+            .or_else(|| self.focused_pane()?.terminal.pty.get_cwd())
     }
 
     /// Split focused pane side by side (left | right)
     pub fn split_horizontal(&mut self, cell_w: f32, cell_h: f32, rect: Rect) -> Result<()> {
         let focused = self.focused_id;
-        let cwd = self.focused_cwd();
+        let cwd = self.spawn_cwd();
         let new_id = self.next_id;
         self.next_id += 1;
 
@@ -79,7 +108,8 @@ impl PaneTree {
         let cols = cols.max(1);
         let rows = rows.max(1);
 
-        let pane = Pane::new(new_id, cols, rows, cwd.as_deref())?;
+        let pane = Pane::new(new_id, cols, rows, (cell_w, cell_h), cwd.as_deref())?;
+        pane.terminal.grid.lock().colors.set_theme(self.theme);
         self.panes.push(pane);
 
         let layout = std::mem::replace(&mut self.layout, Layout::Leaf(0));
@@ -91,7 +121,7 @@ impl PaneTree {
     /// Split focused pane top/bottom
     pub fn split_vertical(&mut self, cell_w: f32, cell_h: f32, rect: Rect) -> Result<()> {
         let focused = self.focused_id;
-        let cwd = self.focused_cwd();
+        let cwd = self.spawn_cwd();
         let new_id = self.next_id;
         self.next_id += 1;
 
@@ -106,7 +136,8 @@ impl PaneTree {
         let cols = cols.max(1);
         let rows = rows.max(1);
 
-        let pane = Pane::new(new_id, cols, rows, cwd.as_deref())?;
+        let pane = Pane::new(new_id, cols, rows, (cell_w, cell_h), cwd.as_deref())?;
+        pane.terminal.grid.lock().colors.set_theme(self.theme);
         self.panes.push(pane);
 
         let layout = std::mem::replace(&mut self.layout, Layout::Leaf(0));
@@ -218,6 +249,11 @@ impl PaneTree {
         }
     }
 
+    /// Whether any pane is holding back output for a synchronized update.
+    pub fn any_synchronized_update(&self) -> bool {
+        self.panes.iter().any(|pane| pane.terminal.in_synchronized_update())
+    }
+
     pub fn resize_panes(&mut self, layout_rects: &[(usize, Rect)], cell_w: f32, cell_h: f32) {
         for (id, rect) in layout_rects {
             if let Some(pane) = self.panes.iter_mut().find(|p| p.id == *id) {
@@ -225,7 +261,7 @@ impl PaneTree {
                 let rows = (rect.height / cell_h).floor() as usize;
                 let cols = cols.max(1);
                 let rows = rows.max(1);
-                let _ = pane.terminal.resize(cols, rows);
+                let _ = pane.terminal.resize(cols, rows, (cell_w, cell_h));
             }
         }
     }
@@ -239,10 +275,10 @@ mod tests {
     /// which exits immediately, so they're lightweight.
     fn test_tree(ids: &[usize], layout: Layout, focused: usize) -> PaneTree {
         let panes: Vec<Pane> = ids.iter().map(|&id| {
-            Pane::new(id, 80, 24, None).expect("spawn pane for test")
+            Pane::new(id, 80, 24, (8.0, 16.0), None).expect("spawn pane for test")
         }).collect();
         let next_id = ids.iter().max().unwrap_or(&0) + 1;
-        PaneTree { panes, layout, focused_id: focused, next_id }
+        PaneTree { panes, layout, focused_id: focused, next_id, theme: Theme::default() }
     }
 
     // ── focus_next / focus_prev ──
@@ -456,7 +492,7 @@ mod tests {
 
     #[test]
     fn split_horizontal_adds_pane_and_focuses_new() {
-        let mut tree = PaneTree::new(80, 24, None).unwrap();
+        let mut tree = PaneTree::new(80, 24, (8.0, 16.0), None).unwrap();
         let rect = Rect::new(0.0, 0.0, 800.0, 600.0);
         tree.split_horizontal(10.0, 20.0, rect).unwrap();
         assert_eq!(tree.panes.len(), 2);
@@ -466,7 +502,7 @@ mod tests {
 
     #[test]
     fn split_vertical_adds_pane_and_focuses_new() {
-        let mut tree = PaneTree::new(80, 24, None).unwrap();
+        let mut tree = PaneTree::new(80, 24, (8.0, 16.0), None).unwrap();
         let rect = Rect::new(0.0, 0.0, 800.0, 600.0);
         tree.split_vertical(10.0, 20.0, rect).unwrap();
         assert_eq!(tree.panes.len(), 2);
@@ -495,4 +531,23 @@ mod tests {
         let mut tree = test_tree(&[0, 1], layout, 0);
         assert_eq!(tree.focused_pane_mut().unwrap().id, 0);
     }
+
+    // ── focused_cwd / spawn_cwd ──
+
+    #[test]
+    fn focused_cwd_prefers_osc7_report() {
+        let tree = test_tree(&[0], Layout::Leaf(0), 0);
+        tree.panes[0].terminal.grid.lock().cwd = Some(PathBuf::from("/remote/only/dir"));
+        assert_eq!(tree.focused_cwd(), Some(PathBuf::from("/remote/only/dir")));
+    }
+
+    #[test]
+    fn spawn_cwd_skips_missing_osc7_dir() {
+        let tree = test_tree(&[0], Layout::Leaf(0), 0);
+        tree.panes[0].terminal.grid.lock().cwd = Some(PathBuf::from("/remote/only/dir"));
+        assert_ne!(tree.spawn_cwd(), Some(PathBuf::from("/remote/only/dir")));
+        let tmp = std::env::temp_dir();
+        tree.panes[0].terminal.grid.lock().cwd = Some(tmp.clone());
+        assert_eq!(tree.spawn_cwd(), Some(tmp));
+    }
 }
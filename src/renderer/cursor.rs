@@ -1,5 +1,7 @@
+use crate::animation::blink::CursorBlink;
 use crate::animation::spring::Spring2D;
 use crate::renderer::cell_bg::CellBgVertex;
+use crate::terminal::grid::CursorShape;
 
 /// Cursor animator using 4 corner springs.
 /// Each corner of the cursor block has its own spring.
@@ -13,6 +15,12 @@ pub struct CursorAnimator {
     pub cell_w: f32,
     pub cell_h: f32,
     pub trail_enabled: bool,
+    pub shape: CursorShape,
+    /// Draw only the outline of a block (unfocused panes).
+    pub hollow: bool,
+    pub blink: CursorBlink,
+    /// On a double-width or double-height line, where a column spans two cells.
+    pub double_width: bool,
     base_omega: f32,
     /// Snap instead of animate for the first N ticks so the shell prompt
     /// appears instantly rather than sliding in from the corner.
@@ -20,7 +28,14 @@ pub struct CursorAnimator {
 }
 
 impl CursorAnimator {
-    pub fn new(omega: f32, color: [f32; 4], cell_w: f32, cell_h: f32, trail_enabled: bool) -> Self {
+    pub fn new(
+        omega: f32,
+        color: [f32; 4],
+        cell_w: f32,
+        cell_h: f32,
+        trail_enabled: bool,
+        blink_interval: f32,
+    ) -> Self {
         let corners = [
             Spring2D::new(omega),
             Spring2D::new(omega),
@@ -35,6 +50,10 @@ impl CursorAnimator {
             cell_w,
             cell_h,
             trail_enabled,
+            shape: CursorShape::Block,
+            hollow: false,
+            blink: CursorBlink::new(blink_interval),
+            double_width: false,
             base_omega: omega,
             startup_snaps: 30,
         }
@@ -48,12 +67,13 @@ impl CursorAnimator {
 
     /// Compute the 4 corner pixel positions (TL, TR, BR, BL) for a grid cell.
     fn corner_targets(&self, col: usize, row: usize, pane_x: f32, pane_y: f32, scroll_offset: f32) -> [(f32, f32); 4] {
-        let px = pane_x + col as f32 * self.cell_w;
+        let w = if self.double_width { self.cell_w * 2.0 } else { self.cell_w };
+        let px = pane_x + col as f32 * w;
         let py = pane_y + row as f32 * self.cell_h + scroll_offset;
         [
             (px, py),
-            (px + self.cell_w, py),
-            (px + self.cell_w, py + self.cell_h),
+            (px + w, py),
+            (px + w, py + self.cell_h),
             (px, py + self.cell_h),
         ]
     }
@@ -134,21 +154,53 @@ impl CursorAnimator {
         for corner in &mut self.corners {
             corner.tick(dt);
         }
+        self.blink.tick(dt);
     }
 
-    /// Build vertices for the animated cursor quad (deformed by corner springs)
-    pub fn build_vertices(&self, surface_w: f32, surface_h: f32) -> [CellBgVertex; 4] {
+    /// Whether the character under the cursor should be drawn in the
+    /// cursor text color: only a solid block covers it, and only while
+    /// the block is mostly shown.
+    pub fn covers_text(&self) -> bool {
+        self.shape == CursorShape::Block && !self.hollow && self.blink.opacity() > 0.5
+    }
+
+    /// Parts of the cursor as fractions of the cell, `[left, top, right,
+    /// bottom]`, mapped onto the spring-deformed quad.
+    fn parts(&self) -> Vec<[f32; 4]> {
+        // Lines are about a tenth of the cell, but at least a pixel wide
+        let line_x = (self.cell_w.max(1.0) * 0.12).max(1.0) / self.cell_w.max(1.0);
+        let line_y = (self.cell_h.max(1.0) * 0.08).max(1.0) / self.cell_h.max(1.0);
+        match self.shape {
+            _ if self.hollow => vec![
+                [0.0, 0.0, 1.0, line_y],
+                [0.0, 1.0 - line_y, 1.0, 1.0],
+                [0.0, line_y, line_x, 1.0 - line_y],
+                [1.0 - line_x, line_y, 1.0, 1.0 - line_y],
+            ],
+            CursorShape::Block => vec![[0.0, 0.0, 1.0, 1.0]],
+            CursorShape::Underline => vec![[0.0, 1.0 - line_y, 1.0, 1.0]],
+            CursorShape::Bar => vec![[0.0, 0.0, line_x, 1.0]],
+        }
+    }
+
+    /// Build vertices for the animated cursor: one quad per part of the
+    /// shape, each deformed by the corner springs and faded by the blink.
+    pub fn build_vertices(&self, surface_w: f32, surface_h: f32) -> Vec<CellBgVertex> {
         let to_ndc_x = |px: f32| (px / surface_w) * 2.0 - 1.0;
         let to_ndc_y = |py: f32| 1.0 - (py / surface_h) * 2.0;
 
-        let color = self.color;
-        let corners = &self.corners;
-        [
-            CellBgVertex { position: [to_ndc_x(corners[0].x.position), to_ndc_y(corners[0].y.position)], color },
-            CellBgVertex { position: [to_ndc_x(corners[1].x.position), to_ndc_y(corners[1].y.position)], color },
-            CellBgVertex { position: [to_ndc_x(corners[2].x.position), to_ndc_y(corners[2].y.position)], color },
-            CellBgVertex { position: [to_ndc_x(corners[3].x.position), to_ndc_y(corners[3].y.position)], color },
-        ]
+        let mut color = self.color;
+        color[3] *= self.blink.opacity();
+        let [tl, tr, br, bl] = self.corners.each_ref().map(|c| (c.x.position, c.y.position));
+        let lerp = |a: (f32, f32), b: (f32, f32), t: f32| (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t);
+        // Bilinear position of (u, v) within the deformed cell
+        let point = |u: f32, v: f32| {
+            let (x, y) = lerp(lerp(tl, tr, u), lerp(bl, br, u), v);
+            CellBgVertex { position: [to_ndc_x(x), to_ndc_y(y)], color }
+        };
+        self.parts()
+            .into_iter()
+            .flat_map(|[l, t, r, b]| [point(l, t), point(r, t), point(r, b), point(l, b)])
+            .collect()
     }
-
 }
@@ -0,0 +1,327 @@
+use crate::pane::layout::Rect;
+use crate::terminal::graphics::ImageData;
+use bytemuck::{Pod, Zeroable};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::Arc;
+use wgpu::util::DeviceExt;
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct ImageVertex {
+    position: [f32; 2],
+    tex_coord: [f32; 2],
+}
+
+impl ImageVertex {
+    const ATTRIBS: [wgpu::VertexAttribute; 2] = wgpu::vertex_attr_array![
+        0 => Float32x2,
+        1 => Float32x2,
+    ];
+
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<ImageVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &Self::ATTRIBS,
+        }
+    }
+}
+
+/// One inline image to draw this frame, in physical pixels.
+pub struct ImageQuad {
+    pub image: Arc<ImageData>,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    /// Part of the texture shown: (u0, v0, u1, v1).
+    pub uv: [f32; 4],
+    /// Pane area the image is clipped to.
+    pub clip: Rect,
+}
+
+struct GpuImage {
+    _texture: wgpu::Texture,
+    bind_group: wgpu::BindGroup,
+}
+
+/// Draws inline terminal images (sixel, kitty) as textured quads. Textures
+/// are uploaded on first use and dropped once no pane shows the image.
+///
+/// All quads of a frame go through one `prepare`; `render` then draws a
+/// range of them, so images below and above the text share one buffer.
+pub struct ImageRenderer {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: wgpu::Buffer,
+    max_quads: usize,
+    textures: HashMap<u64, GpuImage>,
+    /// Image id and scissor rect of each prepared quad.
+    prepared: Vec<(u64, Option<[u32; 4]>)>,
+}
+
+impl ImageRenderer {
+    pub fn new(device: &wgpu::Device, surface_format: wgpu::TextureFormat) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("image_shader"),
+            source: wgpu::ShaderSource::Wgsl(
+                include_str!("../../assets/shaders/image.wgsl").into(),
+            ),
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("image_bgl"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("image_layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("image_pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[ImageVertex::desc()],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: surface_format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let max_quads = 1024;
+        let vertex_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("image_vb"),
+            size: (max_quads * 4 * std::mem::size_of::<ImageVertex>()) as u64,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let indices: Vec<u32> = (0..max_quads as u32)
+            .flat_map(|i| {
+                let base = i * 4;
+                [base, base + 1, base + 2, base, base + 2, base + 3]
+            })
+            .collect();
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("image_ib"),
+            contents: bytemuck::cast_slice(&indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        Self {
+            pipeline,
+            bind_group_layout,
+            sampler,
+            vertex_buffer,
+            index_buffer,
+            max_quads,
+            textures: HashMap::new(),
+            prepared: Vec::new(),
+        }
+    }
+
+    /// Upload textures and vertices for this frame's quads.
+    pub fn prepare(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        quads: &[ImageQuad],
+        surface_w: f32,
+        surface_h: f32,
+    ) {
+        // Forget textures of images that are no longer on screen
+        self.textures.retain(|id, _| quads.iter().any(|q| q.image.id == *id));
+        self.prepared.clear();
+        if quads.is_empty() {
+            return;
+        }
+        let quads = &quads[..quads.len().min(self.max_quads)];
+
+        for quad in quads {
+            if !self.textures.contains_key(&quad.image.id) {
+                let gpu_image = self.upload(device, queue, &quad.image);
+                self.textures.insert(quad.image.id, gpu_image);
+            }
+        }
+
+        let to_ndc_x = |px: f32| (px / surface_w) * 2.0 - 1.0;
+        let to_ndc_y = |py: f32| 1.0 - (py / surface_h) * 2.0;
+        let vertices: Vec<ImageVertex> = quads
+            .iter()
+            .flat_map(|q| {
+                let (x0, x1) = (to_ndc_x(q.x), to_ndc_x(q.x + q.width));
+                let (y0, y1) = (to_ndc_y(q.y), to_ndc_y(q.y + q.height));
+                let [u0, v0, u1, v1] = q.uv;
+                [
+                    ImageVertex { position: [x0, y0], tex_coord: [u0, v0] },
+                    ImageVertex { position: [x1, y0], tex_coord: [u1, v0] },
+                    ImageVertex { position: [x1, y1], tex_coord: [u1, v1] },
+                    ImageVertex { position: [x0, y1], tex_coord: [u0, v1] },
+                ]
+            })
+            .collect();
+        queue.write_buffer(&self.vertex_buffer, 0, bytemuck::cast_slice(&vertices));
+
+        // Clip to the pane; images scrolled entirely out of it get no rect
+        self.prepared = quads
+            .iter()
+            .map(|q| {
+                let left = q.clip.x.max(0.0);
+                let top = q.clip.y.max(0.0);
+                let right = (q.clip.x + q.clip.width).min(surface_w);
+                let bottom = (q.clip.y + q.clip.height).min(surface_h);
+                let scissor = (right - left >= 1.0 && bottom - top >= 1.0)
+                    .then_some([left as u32, top as u32, (right - left) as u32, (bottom - top) as u32]);
+                (q.image.id, scissor)
+            })
+            .collect();
+    }
+
+    /// Draw the prepared quads in `range`.
+    pub fn render(&self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView, range: Range<usize>) {
+        let range = range.start.min(self.prepared.len())..range.end.min(self.prepared.len());
+        if range.is_empty() {
+            return;
+        }
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("image_pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        pass.set_pipeline(&self.pipeline);
+        pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+        for i in range {
+            let (id, scissor) = self.prepared[i];
+            let (Some([x, y, w, h]), Some(gpu_image)) = (scissor, self.textures.get(&id)) else {
+                continue;
+            };
+            pass.set_scissor_rect(x, y, w, h);
+            pass.set_bind_group(0, &gpu_image.bind_group, &[]);
+            let first = (i * 6) as u32;
+            pass.draw_indexed(first..first + 6, 0, 0..1);
+        }
+    }
+
+    fn upload(&self, device: &wgpu::Device, queue: &wgpu::Queue, image: &ImageData) -> GpuImage {
+        // Texture coordinates are normalized, so a smaller texture shows
+        // the same part of the image
+        let max = device.limits().max_texture_dimension_2d;
+        let scaled;
+        let image = if image.width > max || image.height > max {
+            scaled = downscale(image, max);
+            &scaled
+        } else {
+            image
+        };
+        let size = wgpu::Extent3d {
+            width: image.width,
+            height: image.height,
+            depth_or_array_layers: 1,
+        };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("image_texture"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        queue.write_texture(
+            wgpu::TexelCopyTextureInfo {
+                texture: &texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            &image.rgba,
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * image.width),
+                rows_per_image: Some(image.height),
+            },
+            size,
+        );
+        let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("image_bind_group"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: wgpu::BindingResource::TextureView(&texture_view) },
+                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::Sampler(&self.sampler) },
+            ],
+        });
+        GpuImage { _texture: texture, bind_group }
+    }
+}
+
+/// Shrink `image` so neither side exceeds `max` pixels, keeping its aspect
+/// ratio.
+fn downscale(image: &ImageData, max: u32) -> ImageData {
+    let scale = max as f32 / image.width.max(image.height) as f32;
+    let width = ((image.width as f32 * scale) as u32).clamp(1, max);
+    let height = ((image.height as f32 * scale) as u32).clamp(1, max);
+    let source = image::RgbaImage::from_raw(image.width, image.height, image.rgba.clone())
+        .expect("image data matches its size");
+    let resized = image::imageops::resize(&source, width, height, image::imageops::FilterType::Triangle);
+    ImageData::new(width, height, resized.into_raw())
+}
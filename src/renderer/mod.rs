@@ -1,8 +1,10 @@
 pub mod background;
 pub mod cell_bg;
 pub mod cursor;
+pub mod image;
 pub mod text_renderer;
 
+use crate::animation::bell::VisualBell;
 use crate::animation::scroll::ScrollSpring;
 use crate::config::{parse_hex_color, Config};
 use crate::pane::layout::Rect;
@@ -10,10 +12,13 @@ use crate::pane::PaneTree;
 use crate::renderer::background::BackgroundRenderer;
 use crate::renderer::cell_bg::{cell_quad_vertices, CellBgRenderer, CellBgVertex};
 use crate::renderer::cursor::CursorAnimator;
+use crate::renderer::image::{ImageQuad, ImageRenderer};
 use crate::renderer::text_renderer::{
-    build_scrollback_span_buffers, build_span_buffers, to_glyphon_color, PaneTextRenderer,
-    SelectionRange, SpanBuildParams, SpanBuffer,
+    build_scrollback_span_buffers, build_span_buffers, to_glyphon_color, DecorationKind,
+    PaneTextRenderer, SelectionRange, SpanBuildParams, SpanBuffer,
 };
+use crate::terminal::colors::TerminalColors;
+use crate::terminal::grid::LineSize;
 use glyphon::{TextArea, TextBounds};
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -46,6 +51,14 @@ impl Selection {
     }
 }
 
+/// Extent of a hovered URL in (abs_row, col) coordinates. `end.1` is
+/// exclusive. Explicit OSC 8 links may span several wrapped rows.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UrlSpan {
+    pub start: (usize, usize),
+    pub end: (usize, usize),
+}
+
 /// Cached visible-row span buffers for a pane.
 struct TextCache {
     generation: u64,
@@ -68,18 +81,22 @@ pub struct Renderer {
     pub config: wgpu::SurfaceConfiguration,
 
     pub cell_bg_renderer: CellBgRenderer,
-    /// Separate renderer for post-text overlay quads (pane borders).
+    /// Separate renderer for post-text overlay quads (text decorations, pane borders).
     /// Must NOT share a vertex buffer with cell_bg_renderer: wgpu batches all
     /// write_buffer calls before any GPU draw executes, so multiple writes to
     /// the same buffer in one frame are collapsed to the last write.
     pub border_renderer: CellBgRenderer,
     pub text_renderer: PaneTextRenderer,
+    /// Inline images (sixel, kitty), drawn below or above the text by z-index.
+    pub image_renderer: ImageRenderer,
     pub background_renderer: Option<BackgroundRenderer>,
 
     pub cursor_animators: HashMap<usize, CursorAnimator>,
     /// Per-pane cursor visibility (DECTCEM). TUI apps hide the terminal cursor.
     pub cursor_visible: HashMap<usize, bool>,
     pub scroll_springs: HashMap<usize, ScrollSpring>,
+    /// Visual bell flash per pane.
+    bell_flashes: HashMap<usize, VisualBell>,
     text_cache: HashMap<usize, TextCache>,
     scrollback_text_cache: HashMap<usize, ScrollbackCache>,
 
@@ -149,11 +166,12 @@ impl Renderer {
         let cell_bg_renderer = CellBgRenderer::new(&device, surface_format);
         let border_renderer = CellBgRenderer::new(&device, surface_format);
         let mut text_renderer = PaneTextRenderer::new(&device, &queue, surface_format);
+        let image_renderer = ImageRenderer::new(&device, surface_format);
 
         // Load background image if configured
         let background_renderer = app_config.background.image_path.as_ref().and_then(|path| {
             let opacity = app_config.background.image_opacity.unwrap_or(0.3);
-            match image::open(path) {
+            match ::image::open(path) {
                 Ok(img) => {
                     let rgba = img.to_rgba8();
                     let (w, h) = rgba.dimensions();
@@ -196,10 +214,12 @@ impl Renderer {
             cell_bg_renderer,
             border_renderer,
             text_renderer,
+            image_renderer,
             background_renderer,
             cursor_animators: HashMap::new(),
             cursor_visible: HashMap::new(),
             scroll_springs: HashMap::new(),
+            bell_flashes: HashMap::new(),
             text_cache: HashMap::new(),
             scrollback_text_cache: HashMap::new(),
             cell_w,
@@ -231,20 +251,27 @@ impl Renderer {
         let cursor_color = parse_hex_color(&self.app_config.colors.cursor)
             .unwrap_or(DEFAULT_CURSOR_COLOR);
         let trail = self.app_config.animation.cursor_trail_enabled;
+        let blink_interval = self.app_config.cursor.blink_interval_ms as f32 / 1000.0;
 
         self.cursor_animators.entry(pane_id).or_insert_with(|| {
-            CursorAnimator::new(cursor_freq, cursor_color, self.cell_w, self.cell_h, trail)
+            CursorAnimator::new(cursor_freq, cursor_color, self.cell_w, self.cell_h, trail, blink_interval)
         });
         self.scroll_springs.entry(pane_id).or_insert_with(|| {
             ScrollSpring::new(scroll_freq)
         });
     }
 
+    /// Flash a pane for the visual bell.
+    pub fn ring_bell(&mut self, pane_id: usize) {
+        self.bell_flashes.entry(pane_id).or_default().ring();
+    }
+
     /// Returns true when all cursor and scroll springs have settled (no motion).
     pub fn animations_settled(&self) -> bool {
         self.cursor_animators.values().all(|a| {
-            a.corners.iter().all(|c| c.is_settled(0.5))
+            a.corners.iter().all(|c| c.is_settled(0.5)) && !a.blink.is_animating()
         }) && self.scroll_springs.values().all(|s| s.is_settled())
+            && !self.bell_flashes.values().any(VisualBell::is_animating)
     }
 
     pub fn tick_animations(&mut self, dt: f32) {
@@ -254,6 +281,9 @@ impl Renderer {
         for spring in self.scroll_springs.values_mut() {
             spring.tick(dt);
         }
+        for flash in self.bell_flashes.values_mut() {
+            flash.tick(dt);
+        }
     }
 
     pub fn render(
@@ -261,7 +291,7 @@ impl Renderer {
         pane_tree: &PaneTree,
         window_rect: Rect,
         selection: Option<(usize, &Selection)>, // (focused_pane_id, selection)
-        hovered_url: Option<(usize, usize, usize, usize)>, // (pane_id, abs_row, col_start, col_end)
+        hovered_url: Option<(usize, UrlSpan)>, // (pane_id, span)
     ) -> Result<(), SurfaceError> {
         let output = self.surface.get_current_texture()?;
         let view = output
@@ -276,7 +306,8 @@ impl Renderer {
         let window_opacity = self.app_config.window.opacity;
         let fg_color = parse_hex_color(&self.app_config.colors.foreground)
             .unwrap_or([0.8, 0.84, 0.96, 1.0]);
-        let palette = self.app_config.colors.ansi_palette();
+        // Theme palette; panes substitute their own (OSC 4 may change it)
+        let palette = TerminalColors::new(self.app_config.colors.theme()).palette();
 
         let mut encoder =
             self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
@@ -330,6 +361,7 @@ impl Renderer {
             font_family: &font_family,
             cell_w,
             fg_color,
+            bg_color,
             palette: &palette,
             selection: None, // set per-pane below
         };
@@ -343,6 +375,17 @@ impl Renderer {
             let scrollback_len = grid.scrollback.len();
             let visible_rows = grid.rows;
             let current_gen = grid.generation;
+            let pane_palette = grid.colors.palette();
+            let (pane_fg, pane_bg) = grid.default_colors();
+            let focused = *pane_id == pane_tree.focused_id;
+            let mut cursor_covers_text = false;
+            if let Some(anim) = self.cursor_animators.get_mut(pane_id) {
+                anim.color = grid.colors.cursor();
+                anim.shape = grid.cursor_shape.unwrap_or(self.app_config.cursor.shape);
+                anim.hollow = !focused;
+                anim.blink.enabled = focused && grid.cursor_blinking.unwrap_or(self.app_config.cursor.blinking);
+                cursor_covers_text = anim.covers_text();
+            }
 
             // Update scroll spring max_offset from actual scrollback size
             if let Some(spring) = self.scroll_springs.get_mut(pane_id) {
@@ -355,7 +398,7 @@ impl Renderer {
                 .unwrap_or(0.0);
 
             // Rebuild visible span buffer cache if grid changed or selection changed
-            let cursor_pos = if *pane_id == pane_tree.focused_id {
+            let cursor_pos = if focused && cursor_covers_text {
                 Some((grid.cursor_row, grid.cursor_col))
             } else {
                 None
@@ -370,9 +413,11 @@ impl Renderer {
             if !cache_valid {
                 let cursor_text_color = parse_hex_color(&self.app_config.colors.cursor_text)
                     .unwrap_or(bg_color);
-                let mut pane_params = SpanBuildParams { selection: pane_sel, ..span_params };
+                let mut pane_params = SpanBuildParams {
+                    selection: pane_sel, fg_color: pane_fg, bg_color: pane_bg, ..span_params
+                };
                 pane_params.font_family = &font_family;
-                pane_params.palette = &palette;
+                pane_params.palette = &pane_palette;
                 let buffers = build_span_buffers(
                     &mut self.text_renderer.font_system,
                     &grid,
@@ -399,9 +444,11 @@ impl Renderer {
                     .map_or(false, |c| c.key == cache_key && c.selection == pane_sel);
 
                 if !cache_hit {
-                    let mut pane_params = SpanBuildParams { selection: pane_sel, ..span_params };
+                    let mut pane_params = SpanBuildParams {
+                        selection: pane_sel, fg_color: pane_fg, bg_color: pane_bg, ..span_params
+                    };
                     pane_params.font_family = &font_family;
-                    pane_params.palette = &palette;
+                    pane_params.palette = &pane_palette;
                     let contiguous = grid.scrollback.make_contiguous();
                     let rows_slice = &contiguous[first_abs..last_abs];
                     let buffers = build_scrollback_span_buffers(
@@ -438,6 +485,22 @@ impl Renderer {
         // Batching selection and cursor into one render call avoids clobbering either.
         let mut bg_vertices: Vec<CellBgVertex> = Vec::new();
 
+        // Panes whose background was changed with OSC 11 (or reversed with
+        // DECSCNM) paint over the clear color
+        for (pane_id, pane_rect) in &layout_rects {
+            if let Some(pane) = pane_tree.pane_by_id(*pane_id) {
+                let (_, pane_bg) = pane.terminal.grid.lock().default_colors();
+                if pane_bg != bg_color {
+                    let color = [pane_bg[0], pane_bg[1], pane_bg[2], window_opacity];
+                    bg_vertices.extend_from_slice(&cell_quad_vertices(
+                        pane_rect.x, pane_rect.y, pane_rect.width, pane_rect.height,
+                        color,
+                        surface_w, surface_h,
+                    ));
+                }
+            }
+        }
+
         if let Some((sel_pane_id, sel)) = selection {
             if !sel.is_empty() {
                 if let Some(pane_rect) = layout_rects.iter().find(|(id, _)| *id == sel_pane_id).map(|(_, r)| r) {
@@ -446,6 +509,14 @@ impl Renderer {
                         let scrollback_len = grid.scrollback.len();
                         let visible_rows = grid.rows;
                         let cols = grid.cols;
+                        // Double-width and double-height rows have half as
+                        // many columns, each two cells wide
+                        let row_layout: Vec<(f32, usize)> = (0..visible_rows)
+                            .map(|r| match grid.line_sizes[r] {
+                                LineSize::Single => (1.0, cols),
+                                _ => (2.0, grid.line_cols(r)),
+                            })
+                            .collect();
                         drop(grid);
 
                         let scroll_offset = self.scroll_springs
@@ -467,14 +538,18 @@ impl Renderer {
                                 continue;
                             }
 
+                            let (scale, row_cols) = abs_row
+                                .checked_sub(scrollback_len)
+                                .map_or((1.0, cols), |r| row_layout[r]);
+                            let col_w = cell_w * scale;
                             let col_start = if abs_row == start.0 { start.1 } else { 0 };
-                            let col_end = if abs_row == end.0 { end.1 } else { cols.saturating_sub(1) };
-                            let col_end = col_end.min(cols.saturating_sub(1));
+                            let col_end = if abs_row == end.0 { end.1 } else { row_cols.saturating_sub(1) };
+                            let col_end = col_end.min(row_cols.saturating_sub(1));
 
                             for col in col_start..=col_end {
-                                let x = cx + col as f32 * cell_w;
+                                let x = cx + col as f32 * col_w;
                                 let verts = cell_quad_vertices(
-                                    x, y, cell_w, cell_h,
+                                    x, y, col_w, cell_h,
                                     sel_color,
                                     surface_w, surface_h,
                                 );
@@ -489,24 +564,29 @@ impl Renderer {
             }
         }
 
-        // Cursor block — always rendered for the focused pane.
+        // Cursor — always rendered for the focused pane.
         // All PTY output is drained before rendering, so by this point the
         // cursor position is stable (at the input area, not mid-render-cycle).
         // We ignore DECTCEM (cursor_visible) because TUI apps like Claude Code
         // hide the terminal cursor to draw their own styled text cursor, but we
         // want our GPU-animated cursor to always appear at the active position.
+        // Other panes show a hollow block where their cursor is visible.
         let focused_id = pane_tree.focused_id;
-        if let Some(anim) = self.cursor_animators.get(&focused_id) {
-            let verts = anim.build_vertices(surface_w, surface_h);
-            bg_vertices.extend_from_slice(&verts);
+        for (pane_id, _) in &layout_rects {
+            let show = *pane_id == focused_id || self.cursor_visible.get(pane_id) == Some(&true);
+            if let Some(anim) = self.cursor_animators.get(pane_id).filter(|_| show) {
+                bg_vertices.extend(anim.build_vertices(surface_w, surface_h));
+            }
         }
 
         // Hovered URL underline
-        if let Some((url_pane_id, url_abs_row, url_col_start, url_col_end)) = hovered_url {
+        // (explicit OSC 8 links may wrap across several rows)
+        if let Some((url_pane_id, UrlSpan { start: url_start, end: url_end })) = hovered_url {
             if let Some(pane_rect) = layout_rects.iter().find(|(id, _)| *id == url_pane_id).map(|(_, r)| r) {
                 if let Some(pane) = pane_tree.pane_by_id(url_pane_id) {
                     let grid = pane.terminal.grid.lock();
                     let scrollback_len = grid.scrollback.len();
+                    let grid_cols = grid.cols;
                     drop(grid);
 
                     let scroll_offset = self.scroll_springs
@@ -514,14 +594,19 @@ impl Renderer {
                         .map(|s| s.pixel_offset())
                         .unwrap_or(0.0);
 
-                    let row_idx = url_abs_row as f32 - scrollback_len as f32;
-                    let y = pane_rect.y + row_idx * cell_h + scroll_offset;
                     let underline_h = 2.0_f32;
-                    let underline_y = y + cell_h - underline_h;
                     let cx = content_x(pane_rect.x);
                     let underline_color = [fg_color[0], fg_color[1], fg_color[2], 0.6];
 
-                    if underline_y + underline_h >= pane_rect.y && underline_y < pane_rect.y + pane_rect.height {
+                    for url_abs_row in url_start.0..=url_end.0 {
+                        let row_idx = url_abs_row as f32 - scrollback_len as f32;
+                        let y = pane_rect.y + row_idx * cell_h + scroll_offset;
+                        let underline_y = y + cell_h - underline_h;
+                        if underline_y + underline_h < pane_rect.y || underline_y >= pane_rect.y + pane_rect.height {
+                            continue;
+                        }
+                        let url_col_start = if url_abs_row == url_start.0 { url_start.1 } else { 0 };
+                        let url_col_end = if url_abs_row == url_end.0 { url_end.1 } else { grid_cols };
                         for col in url_col_start..url_col_end {
                             let x = cx + col as f32 * cell_w;
                             let verts = cell_quad_vertices(
@@ -543,6 +628,58 @@ impl Renderer {
             );
         }
 
+        // ---- Phase 3a: Inline images, anchored to their cells ----
+        // Images with a negative z-index go below the text, the rest above
+        // it (after the text pass).
+        let mut image_quads: Vec<(i32, ImageQuad)> = Vec::new();
+        for (pane_id, pane_rect) in &layout_rects {
+            let Some(pane) = pane_tree.pane_by_id(*pane_id) else { continue };
+            let scroll_offset = self.scroll_springs
+                .get(pane_id)
+                .map(|s| s.pixel_offset())
+                .unwrap_or(0.0);
+            let cx = content_x(pane_rect.x);
+            let cy = content_y(pane_rect.y);
+            let clip = Rect::new(
+                cx, cy,
+                pane_rect.x + pane_rect.width - cx,
+                pane_rect.y + pane_rect.height - cy,
+            );
+            let grid = pane.terminal.grid.lock();
+            let scrollback_len = grid.scrollback.len();
+            for placement in &grid.images {
+                let Some(abs_row) = grid.abs_row_of_line(placement.line) else { continue };
+                let row_idx = abs_row as f32 - scrollback_len as f32;
+                let y = cy + row_idx * cell_h + scroll_offset + placement.offset.1 as f32;
+                let (width, height) = placement.size;
+                if y + height < clip.y || y > clip.y + clip.height {
+                    continue;
+                }
+                let (image_w, image_h) = (placement.image.width as f32, placement.image.height as f32);
+                let (sx, sy, sw, sh) = placement.source;
+                let uv = [
+                    sx as f32 / image_w,
+                    sy as f32 / image_h,
+                    (sx + sw) as f32 / image_w,
+                    (sy + sh) as f32 / image_h,
+                ];
+                image_quads.push((placement.z_index, ImageQuad {
+                    image: placement.image.clone(),
+                    x: cx + placement.col as f32 * cell_w + placement.offset.0 as f32,
+                    y,
+                    width,
+                    height,
+                    uv,
+                    clip,
+                }));
+            }
+        }
+        image_quads.sort_by_key(|(z, _)| *z);
+        let images_below_text = image_quads.iter().take_while(|(z, _)| *z < 0).count();
+        let image_quads: Vec<ImageQuad> = image_quads.into_iter().map(|(_, quad)| quad).collect();
+        self.image_renderer.prepare(&self.device, &self.queue, &image_quads, surface_w, surface_h);
+        self.image_renderer.render(&mut encoder, &view, 0..images_below_text);
+
         // ---- Phase 3b: Build TextAreas from the caches ----
         // y formula: y = cy + row_idx * cell_h + scroll_offset
         // (scroll_offset > 0 → content moves down to reveal scrollback from top)
@@ -574,15 +711,7 @@ impl Renderer {
                         continue;
                     }
                     let x = cx + sb.col_start as f32 * cell_w + sb.x_offset;
-                    text_areas.push(TextArea {
-                        buffer: &sb.buffer,
-                        left: x,
-                        top: y,
-                        scale: 1.0,
-                        bounds,
-                        default_color,
-                        custom_glyphs: &[],
-                    });
+                    text_areas.push(sb.text_area(x, y, cell_h, bounds, default_color));
                 }
             }
 
@@ -595,15 +724,7 @@ impl Renderer {
                             continue;
                         }
                         let x = cx + sb.col_start as f32 * cell_w + sb.x_offset;
-                        text_areas.push(TextArea {
-                            buffer: &sb.buffer,
-                            left: x,
-                            top: y,
-                            scale: 1.0,
-                            bounds,
-                            default_color,
-                            custom_glyphs: &[],
-                        });
+                        text_areas.push(sb.text_area(x, y, cell_h, bounds, default_color));
                     }
                 }
             }
@@ -634,11 +755,66 @@ impl Renderer {
             });
             let _ = self.text_renderer.render(&mut pass);
         }
+        self.image_renderer.render(&mut encoder, &view, images_below_text..image_quads.len());
+
+        // ---- Phase 4: Text decorations + pane separator borders ----
+        // Underline and strikethrough are drawn after the text pass so
+        // strikethrough lines sit on top of the glyphs.
+        let mut overlay_verts: Vec<CellBgVertex> = Vec::new();
+        let line_thickness = (font_size_px / 14.0).round().max(1.0);
+        let glyph_top = (cell_h - font_size_px) / 2.0;
+
+        for (pane_id, pane_rect) in &layout_rects {
+            let scroll_offset = self.scroll_springs
+                .get(pane_id)
+                .map(|s| s.pixel_offset())
+                .unwrap_or(0.0);
+            let cx = content_x(pane_rect.x);
+            let cy = content_y(pane_rect.y);
+            let pane_bottom = pane_rect.y + pane_rect.height;
+            let pane_right = pane_rect.x + pane_rect.width;
+
+            let visible = self.text_cache.get(pane_id).map(|c| c.buffers.as_slice()).unwrap_or(&[]);
+            let scrollback = if scroll_offset > 0.5 {
+                self.scrollback_text_cache.get(pane_id).map(|c| c.buffers.as_slice()).unwrap_or(&[])
+            } else {
+                &[]
+            };
+            for sb in visible.iter().chain(scrollback.iter()) {
+                let row_y = cy + sb.row_idx as f32 * cell_h + scroll_offset;
+                let (scale, rows_above) = sb.text_scale();
+                let text_top = row_y - rows_above * cell_h;
+                for deco in &sb.decorations {
+                    let line_y = match deco.kind {
+                        DecorationKind::Underline(_) => text_top + (glyph_top + font_size_px * 0.9) * scale,
+                        DecorationKind::Strikethrough => text_top + (glyph_top + font_size_px * 0.55) * scale,
+                    };
+                    for [rx, ry, rw, rh] in deco.rects(cell_w * sb.col_scale(), line_thickness * scale) {
+                        let (x, y) = (cx + rx, line_y + ry);
+                        if y < cy || y + rh > pane_bottom {
+                            continue;
+                        }
+                        // Tall lines only draw the part that falls in their row
+                        if scale != 1.0 && (y < row_y || y >= row_y + cell_h) {
+                            continue;
+                        }
+                        let w = rw.min(pane_right - x);
+                        if w <= 0.0 {
+                            continue;
+                        }
+                        let verts = cell_quad_vertices(
+                            x, y, w, rh,
+                            deco.color,
+                            surface_w, surface_h,
+                        );
+                        overlay_verts.extend_from_slice(&verts);
+                    }
+                }
+            }
+        }
 
-        // ---- Phase 4: Pane separator borders ----
         if layout_rects.len() > 1 {
             let border_color = [fg_color[0] * 0.4, fg_color[1] * 0.4, fg_color[2] * 0.4, 0.4];
-            let mut border_verts: Vec<CellBgVertex> = Vec::new();
 
             for (_, pane_rect) in &layout_rects {
                 if pane_rect.x > window_rect.x + 0.5 {
@@ -648,7 +824,7 @@ impl Renderer {
                         border_color,
                         surface_w, surface_h,
                     );
-                    border_verts.extend_from_slice(&verts);
+                    overlay_verts.extend_from_slice(&verts);
                 }
                 if pane_rect.y > window_rect.y + 0.5 {
                     let verts = cell_quad_vertices(
@@ -657,16 +833,43 @@ impl Renderer {
                         border_color,
                         surface_w, surface_h,
                     );
-                    border_verts.extend_from_slice(&verts);
+                    overlay_verts.extend_from_slice(&verts);
                 }
             }
+        }
 
-            let quad_count = border_verts.len() / 4;
-            if quad_count > 0 {
-                self.border_renderer.render(
-                    &mut encoder, &view, &self.queue, &border_verts, quad_count,
-                );
+        // Bell: a fading flash over the pane that rang, and an outline on
+        // panes that rang while unfocused
+        let attention_color = palette[3];
+        for (pane_id, pane_rect) in &layout_rects {
+            let Rect { x, y, width, height } = *pane_rect;
+            let intensity = self.bell_flashes.get(pane_id).map_or(0.0, VisualBell::intensity);
+            if intensity > 0.0 {
+                let flash = [fg_color[0], fg_color[1], fg_color[2], 0.2 * intensity];
+                overlay_verts.extend_from_slice(&cell_quad_vertices(x, y, width, height, flash, surface_w, surface_h));
             }
+            if pane_tree.pane_by_id(*pane_id).is_some_and(|p| p.bell) {
+                const OUTLINE_W: f32 = 2.0;
+                for [ex, ey, ew, eh] in [
+                    [x, y, width, OUTLINE_W],
+                    [x, y + height - OUTLINE_W, width, OUTLINE_W],
+                    [x, y, OUTLINE_W, height],
+                    [x + width - OUTLINE_W, y, OUTLINE_W, height],
+                ] {
+                    overlay_verts.extend_from_slice(&cell_quad_vertices(
+                        ex, ey, ew, eh,
+                        attention_color,
+                        surface_w, surface_h,
+                    ));
+                }
+            }
+        }
+
+        let quad_count = overlay_verts.len() / 4;
+        if quad_count > 0 {
+            self.border_renderer.render(
+                &mut encoder, &view, &self.queue, &overlay_verts, quad_count,
+            );
         }
 
         self.queue.submit(std::iter::once(encoder.finish()));
@@ -680,6 +883,7 @@ impl Renderer {
         pane_id: usize,
         col: usize,
         row: usize,
+        double_width: bool,
         pane_rect: Rect,
     ) {
         let scroll_offset = self.scroll_springs
@@ -687,18 +891,24 @@ impl Renderer {
             .map(|s| s.pixel_offset())
             .unwrap_or(0.0);
         self.ensure_pane_state(pane_id);
+        // Columns on double-width lines span two cells
+        let col_w = if double_width { self.cell_w * 2.0 } else { self.cell_w };
         if let Some(anim) = self.cursor_animators.get_mut(&pane_id) {
             anim.set_cell_size(self.cell_w, self.cell_h);
+            let resized = anim.double_width != double_width;
+            anim.double_width = double_width;
             if anim.is_warming_up() {
                 anim.snap_to(col, row, pane_rect.x, pane_rect.y, scroll_offset);
-            } else if anim.target_col != col || anim.target_row != row {
+            } else if resized || anim.target_col != col || anim.target_row != row {
+                // Keep the cursor solid while it moves
+                anim.blink.reset();
                 // Only snap for large jumps (>5 cells in either axis) so the
                 // spring can animate smoothly during normal typing and small
                 // cursor movements. Big jumps (page up/down, search, etc.)
                 // still snap to avoid a long slide across the screen.
                 let rendered_x = anim.corners[0].x.position;
                 let rendered_y = anim.corners[0].y.position;
-                let new_target_x = pane_rect.x + col as f32 * self.cell_w;
+                let new_target_x = pane_rect.x + col as f32 * col_w;
                 let new_target_y = pane_rect.y + row as f32 * self.cell_h + scroll_offset;
                 let dx = (rendered_x - new_target_x).abs();
                 let dy = (rendered_y - new_target_y).abs();
@@ -717,7 +927,7 @@ impl Renderer {
                 // far from where it should be.
                 let rendered_x = anim.corners[0].x.position;
                 let rendered_y = anim.corners[0].y.position;
-                let expected_x = pane_rect.x + col as f32 * self.cell_w;
+                let expected_x = pane_rect.x + col as f32 * col_w;
                 let expected_y = pane_rect.y + row as f32 * self.cell_h + scroll_offset;
                 let dx = (rendered_x - expected_x).abs();
                 let dy = (rendered_y - expected_y).abs();
@@ -741,8 +951,10 @@ impl Renderer {
 
         let cursor_color = parse_hex_color(&self.app_config.colors.cursor)
             .unwrap_or(DEFAULT_CURSOR_COLOR);
+        let blink_interval = self.app_config.cursor.blink_interval_ms as f32 / 1000.0;
         for anim in self.cursor_animators.values_mut() {
             anim.color = cursor_color;
+            anim.blink.interval = blink_interval;
         }
 
         if metrics_changed {
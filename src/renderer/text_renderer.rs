@@ -1,8 +1,10 @@
 use glyphon::{
-    Attrs, Buffer, Cache, Color, Family, FontSystem, Metrics, Resolution, Shaping, SwashCache,
-    TextArea, TextAtlas, TextRenderer as GlyphonTextRenderer, Viewport,
+    Attrs, Buffer, Cache, Color, Family, FontSystem, Metrics, Resolution, Shaping, Style,
+    SwashCache, TextArea, TextAtlas, TextBounds, TextRenderer as GlyphonTextRenderer, Viewport, Weight,
 };
 use unicode_width::UnicodeWidthChar;
+use crate::terminal::cell::UnderlineStyle;
+use crate::terminal::grid::LineSize;
 
 pub struct PaneTextRenderer {
     pub font_system: FontSystem,
@@ -89,6 +91,130 @@ pub struct SpanBuffer {
     pub row_idx: i32,
     /// Horizontal offset (pixels) to center the glyph within its cell.
     pub x_offset: f32,
+    /// Underline / strikethrough runs for this row, drawn as quads after the text pass.
+    pub decorations: Vec<LineDecoration>,
+    /// DECDWL/DECDHL size of the row.
+    pub line_size: LineSize,
+}
+
+impl SpanBuffer {
+    /// Width of one of this row's columns in screen cells.
+    pub fn col_scale(&self) -> f32 {
+        if self.line_size == LineSize::Single { 1.0 } else { 2.0 }
+    }
+
+    /// Glyph scale for the text pass, and how far above the row (in rows)
+    /// the scaled text starts. Glyphon only scales uniformly, so double-width
+    /// lines keep normal glyphs spaced two cells apart; double-height lines
+    /// draw twice-sized text and show its top or bottom half.
+    pub fn text_scale(&self) -> (f32, f32) {
+        match self.line_size {
+            LineSize::Single | LineSize::DoubleWidth => (1.0, 0.0),
+            LineSize::DoubleHeightTop => (2.0, 0.0),
+            LineSize::DoubleHeightBottom => (2.0, 1.0),
+        }
+    }
+
+    /// The glyphon text area for this row at `left`/`top`. Double-height
+    /// rows are clipped to their own row.
+    pub fn text_area(&self, left: f32, top: f32, cell_h: f32, bounds: TextBounds, default_color: Color) -> TextArea<'_> {
+        let (scale, rows_above) = self.text_scale();
+        let bounds = if scale == 1.0 {
+            bounds
+        } else {
+            TextBounds {
+                top: bounds.top.max(top as i32),
+                bottom: bounds.bottom.min((top + cell_h) as i32),
+                ..bounds
+            }
+        };
+        TextArea {
+            buffer: &self.buffer,
+            left,
+            top: top - rows_above * cell_h,
+            scale,
+            bounds,
+            default_color,
+            custom_glyphs: &[],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecorationKind {
+    Underline(UnderlineStyle),
+    Strikethrough,
+}
+
+/// A horizontal line drawn across a run of cells (SGR 4 / SGR 9).
+/// Glyphon has no text decorations, so these are rendered as quads.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LineDecoration {
+    pub kind: DecorationKind,
+    pub col_start: usize,
+    /// Exclusive end column.
+    pub col_end: usize,
+    pub color: [f32; 4],
+}
+
+impl LineDecoration {
+    /// Rectangles `[x, y, w, h]` that draw this decoration, with `x`
+    /// relative to the pane's first column and `y` to the top of the line.
+    /// Patterns are phased by column so runs that meet line up.
+    pub fn rects(&self, cell_w: f32, thickness: f32) -> Vec<[f32; 4]> {
+        let start = self.col_start as f32 * cell_w;
+        let width = (self.col_end - self.col_start) as f32 * cell_w;
+        let style = match self.kind {
+            DecorationKind::Underline(style) => style,
+            DecorationKind::Strikethrough => UnderlineStyle::Single,
+        };
+        match style {
+            UnderlineStyle::None => Vec::new(),
+            UnderlineStyle::Single => vec![[start, 0.0, width, thickness]],
+            UnderlineStyle::Double => vec![
+                [start, 0.0, width, thickness],
+                [start, thickness * 2.0, width, thickness],
+            ],
+            UnderlineStyle::Dotted => {
+                let period = thickness * 2.0;
+                let mut x = (start / period).ceil() * period;
+                let mut rects = Vec::new();
+                while x + thickness <= start + width {
+                    rects.push([x, 0.0, thickness, thickness]);
+                    x += period;
+                }
+                rects
+            }
+            // One dash centered in each cell
+            UnderlineStyle::Dashed => (self.col_start..self.col_end)
+                .map(|col| [(col as f32 + 0.25) * cell_w, 0.0, cell_w * 0.5, thickness])
+                .collect(),
+            // One wave per cell, drawn as 1px columns that each span the
+            // curve over their width so steep parts have no gaps
+            UnderlineStyle::Curly => {
+                let amplitude = thickness * 1.5;
+                let wave = |x: f32| amplitude * (1.0 - (x / cell_w * std::f32::consts::TAU).cos());
+                let columns = width.ceil() as usize;
+                (0..columns)
+                    .map(|i| {
+                        let x = start + i as f32;
+                        let step = (start + width - x).min(1.0);
+                        let (y0, y1) = (wave(x), wave(x + step));
+                        [x, y0.min(y1), step, (y1 - y0).abs() + thickness]
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// One run of consecutive cells that share color, weight and style.
+#[derive(Debug, Clone, PartialEq)]
+struct TextRun {
+    text: String,
+    color: Color,
+    bold: bool,
+    italic: bool,
 }
 
 /// Scan a row of cells for hex color codes (#RRGGBB) and return a map of
@@ -142,6 +268,9 @@ pub type SelectionRange = ((usize, usize), (usize, usize));
 /// Light gray color used for text inside a selection highlight.
 const SELECTION_TEXT_COLOR: [f32; 4] = [0.9, 0.9, 0.9, 1.0];
 
+/// How far dim (faint) text is blended from its foreground toward the background.
+const DIM_BLEND: f32 = 0.5;
+
 fn is_cell_selected(sel: &SelectionRange, abs_row: usize, col: usize, cols: usize) -> bool {
     let (start, end) = sel;
     if abs_row < start.0 || abs_row > end.0 {
@@ -160,12 +289,14 @@ pub struct SpanBuildParams<'a> {
     pub font_family: &'a str,
     pub cell_w: f32,
     pub fg_color: [f32; 4],
-    pub palette: &'a [[f32; 4]; 16],
+    /// Default background, used to blend dim (SGR 2) text.
+    pub bg_color: [f32; 4],
+    pub palette: &'a [[f32; 4]; 256],
     pub selection: Option<SelectionRange>,
 }
 
 /// Resolve the foreground color for a single cell, accounting for selection,
-/// cursor position, hex color overrides, and reverse video.
+/// cursor position, hex color overrides, reverse video and dim.
 fn resolve_cell_fg(
     cell: &crate::terminal::cell::Cell,
     col_idx: usize,
@@ -188,11 +319,139 @@ fn resolve_cell_fg(
     if let Some((_, color)) = hex_overrides.iter().find(|(c, _)| *c == col_idx) {
         return *color;
     }
-    if cell.attrs.reverse {
-        resolve_color(&cell.attrs.bg, params.fg_color, params.palette)
+    let (fg, bg) = if cell.attrs.reverse {
+        (resolve_color(&cell.attrs.bg, params.fg_color, params.palette), params.fg_color)
+    } else {
+        (
+            resolve_color(&cell.attrs.fg, params.fg_color, params.palette),
+            resolve_color(&cell.attrs.bg, params.bg_color, params.palette),
+        )
+    };
+    if cell.attrs.dim {
+        dim_color(fg, bg)
     } else {
-        resolve_color(&cell.attrs.fg, params.fg_color, params.palette)
+        fg
+    }
+}
+
+/// Blend a foreground color toward the background for faint (SGR 2) text.
+fn dim_color(fg: [f32; 4], bg: [f32; 4]) -> [f32; 4] {
+    let mix = |f: f32, b: f32| f + (b - f) * DIM_BLEND;
+    [mix(fg[0], bg[0]), mix(fg[1], bg[1]), mix(fg[2], bg[2]), fg[3]]
+}
+
+/// Glyphon attributes for a cell's weight and style.
+fn styled_attrs<'a>(family: Family<'a>, color: Color, bold: bool, italic: bool) -> Attrs<'a> {
+    let mut attrs = Attrs::new().family(family).color(color);
+    if bold {
+        attrs = attrs.weight(Weight::BOLD);
     }
+    if italic {
+        attrs = attrs.style(Style::Italic);
+    }
+    attrs
+}
+
+/// Split one row into styled text runs and collect its underline/strikethrough
+/// decorations. Empty cells become spaces so runs stay column-aligned.
+#[allow(clippy::too_many_arguments)]
+fn build_row_runs(
+    row: &[crate::terminal::cell::Cell],
+    abs_row: usize,
+    cols: usize,
+    hex_overrides: &[(usize, [f32; 4])],
+    params: &SpanBuildParams,
+    cursor: Option<(usize, usize, [f32; 4])>,
+    grid_row: usize,
+    runs: &mut Vec<TextRun>,
+    decorations: &mut Vec<LineDecoration>,
+) {
+    runs.clear();
+    decorations.clear();
+
+    for (col_idx, cell) in row.iter().enumerate() {
+        let blank = cell.is_empty() || cell.ch.is_control();
+        let (ch, raw_fg) = if blank {
+            (' ', params.fg_color)
+        } else {
+            (cell.ch, resolve_cell_fg(cell, col_idx, abs_row, cols, hex_overrides, params, cursor, grid_row))
+        };
+        let color = to_glyphon_color(raw_fg);
+        let bold = !blank && cell.attrs.bold;
+        let italic = !blank && cell.attrs.italic;
+
+        let underline = cell.attrs.underline;
+        if underline != UnderlineStyle::None || cell.attrs.strikethrough {
+            // Lines also cover blank cells so an underlined phrase stays continuous.
+            let line_color = if blank {
+                resolve_cell_fg(cell, col_idx, abs_row, cols, hex_overrides, params, cursor, grid_row)
+            } else {
+                raw_fg
+            };
+            if underline != UnderlineStyle::None {
+                // SGR 58 colors the underline independently of the text
+                let color = resolve_color(&cell.attrs.underline_color, line_color, params.palette);
+                push_decoration(decorations, DecorationKind::Underline(underline), col_idx, color);
+            }
+            if cell.attrs.strikethrough {
+                push_decoration(decorations, DecorationKind::Strikethrough, col_idx, line_color);
+            }
+        }
+
+        if let Some(last) = runs.last_mut() {
+            if last.color == color && last.bold == bold && last.italic == italic {
+                last.text.push(ch);
+                continue;
+            }
+        }
+        runs.push(TextRun { text: ch.to_string(), color, bold, italic });
+    }
+}
+
+/// Extend the previous decoration of the same kind and color if it ends at
+/// `col`, otherwise start a new one.
+fn push_decoration(decorations: &mut Vec<LineDecoration>, kind: DecorationKind, col: usize, color: [f32; 4]) {
+    if let Some(last) = decorations.iter_mut().rev().find(|d| d.kind == kind) {
+        if last.col_end == col && last.color == color {
+            last.col_end = col + 1;
+            return;
+        }
+    }
+    decorations.push(LineDecoration { kind, col_start: col, col_end: col + 1, color });
+}
+
+/// Lay out the text of a double-width row at normal glyph size: every
+/// column takes two cells, so narrow characters are followed by a space.
+/// Wide characters already cover two cells, and their spacer column adds
+/// the other two.
+fn spread_double_width(text: &str) -> String {
+    let mut spread = String::with_capacity(text.len() * 2);
+    for c in text.chars() {
+        spread.push(c);
+        if c.width() != Some(2) {
+            spread.push(' ');
+        }
+    }
+    spread
+}
+
+/// Shape a row's runs into a single glyphon Buffer.
+fn shape_row(
+    font_system: &mut FontSystem,
+    runs: &[TextRun],
+    metrics: Metrics,
+    family: Family,
+    buf_w: f32,
+    cell_h: f32,
+) -> Buffer {
+    let mut buffer = Buffer::new(font_system, metrics);
+    buffer.set_size(font_system, Some(buf_w), Some(cell_h));
+    let rich: Vec<(&str, Attrs)> = runs.iter()
+        .map(|run| (run.text.as_str(), styled_attrs(family, run.color, run.bold, run.italic)))
+        .collect();
+    let base = Attrs::new().family(family);
+    buffer.set_rich_text(font_system, rich, &base, Shaping::Basic, None);
+    buffer
 }
 
 /// Build a SpanBuffer for a single cell. Returns None for empty/control chars.
@@ -212,7 +471,7 @@ fn build_cell_span(
 
     let mut buffer = Buffer::new(font_system, metrics);
     buffer.set_size(font_system, Some(buf_w), Some(cell_h));
-    let attrs = Attrs::new().color(color).family(family);
+    let attrs = styled_attrs(family, color, cell.attrs.bold, cell.attrs.italic);
     buffer.set_text(font_system, &cell.ch.to_string(), &attrs, Shaping::Basic);
     buffer.shape_until_scroll(font_system, false);
 
@@ -224,7 +483,7 @@ fn build_cell_span(
     let cell_span = cell_w * char_cols as f32;
     let x_offset = ((cell_span - glyph_advance) / 2.0).max(0.0);
 
-    SpanBuffer { buffer, col_start: col_idx, row_idx, x_offset }
+    SpanBuffer { buffer, col_start: col_idx, row_idx, x_offset, decorations: Vec::new(), line_size: LineSize::Single }
 }
 
 /// Build row-level glyphon Buffers with per-character color spans.
@@ -243,59 +502,44 @@ pub fn build_span_buffers(
     let mut result = Vec::with_capacity(grid.rows);
     let cursor_info = cursor_pos.map(|(r, c)| (r, c, cursor_text_color));
     // Reuse allocations across rows
-    let mut spans: Vec<(String, Color)> = Vec::with_capacity(16);
+    let mut runs: Vec<TextRun> = Vec::with_capacity(16);
+    let mut decorations: Vec<LineDecoration> = Vec::new();
+    let buf_w = params.cell_w * (grid.cols as f32 + 1.0);
 
     for (row_idx, row) in grid.cells.iter().enumerate() {
-        if row.iter().all(|c| c.is_empty()) {
+        // Wide and tall lines only show their first half
+        let row = &row[..grid.line_cols(row_idx)];
+        if row.iter().all(|c| c.is_empty() && c.attrs.underline == UnderlineStyle::None && !c.attrs.strikethrough) {
             continue;
         }
         let has_hash = row.iter().any(|c| c.ch == '#');
         let hex_overrides = if has_hash { detect_hex_colors(row) } else { Vec::new() };
         let abs_row = scrollback_len + row_idx;
 
-        // Build spans of text with uniform color, using spaces for empty cells
-        // to maintain exact column alignment with cursor positioning.
-        spans.clear();
-
-        for (col_idx, cell) in row.iter().enumerate() {
-            let (ch, raw_fg) = if cell.is_empty() || cell.ch.is_control() {
-                (' ', params.fg_color)
-            } else {
-                (cell.ch, resolve_cell_fg(cell, col_idx, abs_row, grid.cols, &hex_overrides, params, cursor_info, row_idx))
-            };
-            let color = to_glyphon_color(raw_fg);
-
-            if let Some(last) = spans.last_mut() {
-                if last.1 == color {
-                    last.0.push(ch);
-                    continue;
-                }
-            }
-            let mut s = String::new();
-            s.push(ch);
-            spans.push((s, color));
-        }
-
-        if spans.is_empty() {
+        build_row_runs(
+            row, abs_row, grid.cols, &hex_overrides, params, cursor_info, row_idx,
+            &mut runs, &mut decorations,
+        );
+        if runs.is_empty() {
             continue;
         }
 
-        let buf_w = params.cell_w * (grid.cols as f32 + 1.0);
-        let mut buffer = Buffer::new(font_system, metrics);
-        buffer.set_size(font_system, Some(buf_w), Some(params.cell_h));
-
-        // Build rich text spans with per-color attributes
-        let rich: Vec<(&str, Attrs)> = spans.iter()
-            .map(|(text, color)| (text.as_str(), Attrs::new().family(family).color(*color)))
-            .collect();
-        let base = Attrs::new().family(family);
-        buffer.set_rich_text(font_system, rich, &base, Shaping::Basic, None);
-
+        let line_size = grid.line_sizes[row_idx];
+        let mut x_offset = 0.0;
+        if line_size == LineSize::DoubleWidth {
+            for run in &mut runs {
+                run.text = spread_double_width(&run.text);
+            }
+            x_offset = params.cell_w / 2.0;
+        }
+        let buffer = shape_row(font_system, &runs, metrics, family, buf_w, params.cell_h);
         result.push(SpanBuffer {
             buffer,
             col_start: 0,
             row_idx: row_idx as i32,
-            x_offset: 0.0,
+            x_offset,
+            decorations: decorations.clone(),
+            line_size,
         });
     }
     result
@@ -315,9 +559,11 @@ pub fn build_scrollback_span_buffers(
     let metrics = Metrics::new(params.font_size, params.cell_h);
     let family = if params.font_family.is_empty() { Family::Monospace } else { Family::Name(params.font_family) };
     let mut result = Vec::with_capacity(rows.len());
+    let mut runs: Vec<TextRun> = Vec::new();
+    let mut decorations: Vec<LineDecoration> = Vec::new();
 
     for (i, row) in rows.iter().enumerate() {
-        if row.iter().all(|c| c.is_empty()) {
+        if row.iter().all(|c| c.is_empty() && c.attrs.underline == UnderlineStyle::None && !c.attrs.strikethrough) {
             continue;
         }
         let abs_row = scrollback_start + i;
@@ -326,47 +572,23 @@ pub fn build_scrollback_span_buffers(
         let hex_overrides = if has_hash { detect_hex_colors(row) } else { Vec::new() };
         let cols = row.len();
 
-        // Build per-row rich text spans, using spaces for empty cells
-        let mut spans: Vec<(String, Color)> = Vec::new();
-
-        for (col_idx, cell) in row.iter().enumerate() {
-            let (ch, raw_fg) = if cell.is_empty() || cell.ch.is_control() {
-                (' ', params.fg_color)
-            } else {
-                (cell.ch, resolve_cell_fg(cell, col_idx, abs_row, cols, &hex_overrides, params, None, 0))
-            };
-            let color = to_glyphon_color(raw_fg);
-
-            if let Some(last) = spans.last_mut() {
-                if last.1 == color {
-                    last.0.push(ch);
-                    continue;
-                }
-            }
-            let mut s = String::new();
-            s.push(ch);
-            spans.push((s, color));
-        }
-
-        if spans.is_empty() {
+        build_row_runs(
+            row, abs_row, cols, &hex_overrides, params, None, 0,
+            &mut runs, &mut decorations,
+        );
+        if runs.is_empty() {
             continue;
         }
 
         let buf_w = params.cell_w * (cols as f32 + 1.0);
-        let mut buffer = Buffer::new(font_system, metrics);
-        buffer.set_size(font_system, Some(buf_w), Some(params.cell_h));
-        let mut rich_spans: Vec<(&str, Attrs)> = Vec::with_capacity(spans.len());
-        for (text, color) in &spans {
-            rich_spans.push((text.as_str(), Attrs::new().family(family).color(*color)));
-        }
-        let base = Attrs::new().family(family);
-        buffer.set_rich_text(font_system, rich_spans, &base, Shaping::Basic, None);
-
+        let buffer = shape_row(font_system, &runs, metrics, family, buf_w, params.cell_h);
         result.push(SpanBuffer {
             buffer,
             col_start: 0,
             row_idx: row_idx as i32,
             x_offset: 0.0,
+            decorations: decorations.clone(),
+            line_size: LineSize::Single,
         });
     }
     result
@@ -384,36 +606,196 @@ pub fn to_glyphon_color(c: [f32; 4]) -> Color {
 pub fn resolve_color(
     color: &crate::terminal::cell::Color,
     default_fg: [f32; 4],
-    palette: &[[f32; 4]; 16],
+    palette: &[[f32; 4]; 256],
 ) -> [f32; 4] {
     match color {
         crate::terminal::cell::Color::Default => default_fg,
-        crate::terminal::cell::Color::Indexed(i) => {
-            let i = *i as usize;
-            if i < 16 {
-                palette[i]
-            } else {
-                xterm256_to_rgba(i as u8)
-            }
-        }
+        crate::terminal::cell::Color::Indexed(i) => palette[*i as usize],
         crate::terminal::cell::Color::Rgb(r, g, b) => {
             [*r as f32 / 255.0, *g as f32 / 255.0, *b as f32 / 255.0, 1.0]
         }
     }
 }
 
-fn xterm256_to_rgba(i: u8) -> [f32; 4] {
-    if i < 16 {
-        return [1.0, 1.0, 1.0, 1.0];
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::terminal::cell::{Cell, CellAttributes, Color as CellColor};
+
+    const FG: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+    const BG: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+    const PALETTE: [[f32; 4]; 256] = [[0.5, 0.5, 0.5, 1.0]; 256];
+
+    fn params() -> SpanBuildParams<'static> {
+        SpanBuildParams {
+            cell_h: 20.0,
+            font_size: 16.0,
+            font_family: "",
+            cell_w: 10.0,
+            fg_color: FG,
+            bg_color: BG,
+            palette: &PALETTE,
+            selection: None,
+        }
     }
-    if i >= 232 {
-        let gray = (i - 232) as f32 / 23.0;
-        return [gray, gray, gray, 1.0];
+
+    fn row_from(text: &str, attrs: CellAttributes) -> Vec<Cell> {
+        text.chars().map(|ch| Cell::new(ch, attrs)).collect()
+    }
+
+    fn runs_for(row: &[Cell]) -> (Vec<TextRun>, Vec<LineDecoration>) {
+        let mut runs = Vec::new();
+        let mut decorations = Vec::new();
+        build_row_runs(row, 0, row.len(), &[], &params(), None, 0, &mut runs, &mut decorations);
+        (runs, decorations)
+    }
+
+    // ── Runs ────────────────────────────────────────────────────────────
+
+    #[test]
+    fn plain_row_is_one_run() {
+        let (runs, decorations) = runs_for(&row_from("hello", CellAttributes::default()));
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].text, "hello");
+        assert!(!runs[0].bold);
+        assert!(!runs[0].italic);
+        assert!(decorations.is_empty());
+    }
+
+    #[test]
+    fn bold_splits_run() {
+        let mut row = row_from("ab", CellAttributes::default());
+        row.extend(row_from("cd", CellAttributes { bold: true, ..Default::default() }));
+        let (runs, _) = runs_for(&row);
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0].text, "ab");
+        assert!(!runs[0].bold);
+        assert_eq!(runs[1].text, "cd");
+        assert!(runs[1].bold);
+    }
+
+    #[test]
+    fn italic_sets_style() {
+        let (runs, _) = runs_for(&row_from("x", CellAttributes { italic: true, ..Default::default() }));
+        assert!(runs[0].italic);
+    }
+
+    #[test]
+    fn blank_cells_are_never_bold() {
+        let mut row = row_from("a", CellAttributes { bold: true, ..Default::default() });
+        row.push(Cell::default());
+        let (runs, _) = runs_for(&row);
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[1].text, " ");
+        assert!(!runs[1].bold);
+    }
+
+    #[test]
+    fn dim_blends_toward_background() {
+        let (runs, _) = runs_for(&row_from("x", CellAttributes { dim: true, ..Default::default() }));
+        assert_eq!(runs[0].color, to_glyphon_color([0.5, 0.5, 0.5, 1.0]));
+    }
+
+    // ── Decorations ─────────────────────────────────────────────────────
+
+    #[test]
+    fn underline_merges_consecutive_cells() {
+        let mut row = row_from("ab", CellAttributes::default());
+        row.extend(row_from("cde", CellAttributes { underline: UnderlineStyle::Single, ..Default::default() }));
+        let (_, decorations) = runs_for(&row);
+        assert_eq!(decorations, vec![LineDecoration {
+            kind: DecorationKind::Underline(UnderlineStyle::Single),
+            col_start: 2,
+            col_end: 5,
+            color: FG,
+        }]);
+    }
+
+    #[test]
+    fn underline_covers_spaces() {
+        let row = row_from("a b", CellAttributes { underline: UnderlineStyle::Single, ..Default::default() });
+        let (_, decorations) = runs_for(&row);
+        assert_eq!(decorations.len(), 1);
+        assert_eq!((decorations[0].col_start, decorations[0].col_end), (0, 3));
+    }
+
+    #[test]
+    fn underline_splits_on_color_change() {
+        let mut row = row_from("a", CellAttributes { underline: UnderlineStyle::Single, ..Default::default() });
+        row.extend(row_from("b", CellAttributes {
+            underline: UnderlineStyle::Single,
+            fg: CellColor::Indexed(1),
+            ..Default::default()
+        }));
+        let (_, decorations) = runs_for(&row);
+        assert_eq!(decorations.len(), 2);
+        assert_eq!(decorations[1].color, PALETTE[1]);
+    }
+
+    #[test]
+    fn strikethrough_and_underline_tracked_separately() {
+        let attrs = CellAttributes { underline: UnderlineStyle::Single, strikethrough: true, ..Default::default() };
+        let (_, decorations) = runs_for(&row_from("ab", attrs));
+        assert_eq!(decorations.len(), 2);
+        assert!(decorations.iter().any(|d| d.kind == DecorationKind::Underline(UnderlineStyle::Single) && d.col_end == 2));
+        assert!(decorations.iter().any(|d| d.kind == DecorationKind::Strikethrough && d.col_end == 2));
+    }
+
+    #[test]
+    fn dim_underline_uses_dimmed_color() {
+        let attrs = CellAttributes { underline: UnderlineStyle::Single, dim: true, ..Default::default() };
+        let (_, decorations) = runs_for(&row_from("a", attrs));
+        assert_eq!(decorations[0].color, [0.5, 0.5, 0.5, 1.0]);
+    }
+
+    #[test]
+    fn underline_style_and_color() {
+        let mut row = row_from("ab", CellAttributes { underline: UnderlineStyle::Curly, ..Default::default() });
+        row.extend(row_from("c", CellAttributes {
+            underline: UnderlineStyle::Curly,
+            underline_color: CellColor::Rgb(255, 0, 0),
+            ..Default::default()
+        }));
+        row.extend(row_from("d", CellAttributes { underline: UnderlineStyle::Dashed, ..Default::default() }));
+        let (_, decorations) = runs_for(&row);
+        let spans: Vec<_> = decorations.iter().map(|d| (d.kind, d.col_start, d.col_end, d.color)).collect();
+        assert_eq!(spans, vec![
+            (DecorationKind::Underline(UnderlineStyle::Curly), 0, 2, FG),
+            (DecorationKind::Underline(UnderlineStyle::Curly), 2, 3, [1.0, 0.0, 0.0, 1.0]),
+            (DecorationKind::Underline(UnderlineStyle::Dashed), 3, 4, FG),
+        ]);
+    }
+
+    #[test]
+    fn decoration_rects_per_style() {
+        let deco = |style, col_start, col_end| LineDecoration {
+            kind: DecorationKind::Underline(style),
+            col_start,
+            col_end,
+            color: FG,
+        };
+        assert_eq!(deco(UnderlineStyle::Single, 1, 3).rects(10.0, 1.0), vec![[10.0, 0.0, 20.0, 1.0]]);
+        assert_eq!(deco(UnderlineStyle::Double, 0, 1).rects(10.0, 1.0).len(), 2);
+        assert_eq!(
+            deco(UnderlineStyle::Dashed, 1, 3).rects(10.0, 1.0),
+            vec![[12.5, 0.0, 5.0, 1.0], [22.5, 0.0, 5.0, 1.0]]
+        );
+        // Dots sit on a grid shared by every run
+        let dots = deco(UnderlineStyle::Dotted, 1, 2).rects(9.0, 2.0);
+        assert_eq!(dots.iter().map(|r| r[0]).collect::<Vec<_>>(), vec![12.0, 16.0]);
+        // The wave covers the run without gaps and stays within its amplitude
+        let wave = deco(UnderlineStyle::Curly, 0, 2).rects(10.0, 1.0);
+        assert_eq!(wave.len(), 20);
+        assert!(wave.windows(2).all(|w| w[0][0] + w[0][2] == w[1][0]));
+        assert!(wave.iter().all(|r| r[1] >= 0.0 && r[1] + r[3] <= 4.0 + 1e-4));
+    }
+
+    // ── Double-width rows ───────────────────────────────────────────────
+
+    #[test]
+    fn double_width_text_keeps_wide_characters_on_their_columns() {
+        assert_eq!(spread_double_width("ab"), "a b ");
+        // "中" and its spacer column cover four cells, like two narrow columns
+        assert_eq!(spread_double_width("中 x"), "中  x ");
     }
-    let i = i - 16;
-    let r = (i / 36) % 6;
-    let g = (i / 6) % 6;
-    let b = i % 6;
-    let scale = |v: u8| if v == 0 { 0.0 } else { (55.0 + v as f32 * 40.0) / 255.0 };
-    [scale(r), scale(g), scale(b), 1.0]
 }
@@ -0,0 +1,169 @@
+/// Largest APC string we collect. Longer ones are dropped.
+pub const APC_MAX_BYTES: usize = 64 * 1024 * 1024;
+
+const ESC: u8 = 0x1b;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Ground,
+    /// Saw ESC outside an APC string
+    Escape,
+    Apc,
+    /// Saw ESC inside an APC string (expecting `\`)
+    ApcEscape,
+}
+
+/// A piece of PTY output: regular output for the VTE parser, or the body
+/// of an APC string (`ESC _ ... ESC \`).
+pub enum Segment<'a> {
+    Output(&'a [u8]),
+    Apc(&'a [u8]),
+}
+
+/// Splits APC strings out of the PTY output. vte parses APC but throws the
+/// contents away, and the kitty graphics protocol lives there.
+pub struct ApcScanner {
+    state: State,
+    /// An ESC at the end of the previous chunk, held back until we know
+    /// whether it starts an APC
+    held_esc: bool,
+    buf: Vec<u8>,
+    overflow: bool,
+}
+
+impl Default for ApcScanner {
+    fn default() -> Self {
+        Self { state: State::Ground, held_esc: false, buf: Vec::new(), overflow: false }
+    }
+}
+
+impl ApcScanner {
+    pub fn split(&mut self, bytes: &[u8], mut emit: impl FnMut(Segment)) {
+        let mut run_start = 0;
+        for (i, &b) in bytes.iter().enumerate() {
+            match self.state {
+                State::Ground => {
+                    if b == ESC {
+                        self.state = State::Escape;
+                    }
+                }
+                State::Escape => {
+                    if b == b'_' {
+                        // Flush the output before the ESC that starts the APC
+                        if self.held_esc {
+                            self.held_esc = false;
+                        } else if i - 1 > run_start {
+                            emit(Segment::Output(&bytes[run_start..i - 1]));
+                        }
+                        self.state = State::Apc;
+                        self.buf.clear();
+                        self.overflow = false;
+                        run_start = i + 1;
+                    } else {
+                        if self.held_esc {
+                            self.held_esc = false;
+                            emit(Segment::Output(&[ESC]));
+                        }
+                        self.state = if b == ESC { State::Escape } else { State::Ground };
+                    }
+                }
+                State::Apc => {
+                    if b == ESC {
+                        self.state = State::ApcEscape;
+                    } else if self.buf.len() < APC_MAX_BYTES {
+                        self.buf.push(b);
+                    } else {
+                        self.overflow = true;
+                    }
+                    run_start = i + 1;
+                }
+                State::ApcEscape => {
+                    if b == b'\\' {
+                        if !self.overflow {
+                            emit(Segment::Apc(&self.buf));
+                        }
+                        self.buf = Vec::new();
+                        self.state = State::Ground;
+                        run_start = i + 1;
+                    } else {
+                        // Any other escape aborts the APC; the ESC and this
+                        // byte go to the parser as usual
+                        self.buf = Vec::new();
+                        emit(Segment::Output(&[ESC]));
+                        self.state = if b == ESC { State::Escape } else { State::Ground };
+                        run_start = i;
+                    }
+                }
+            }
+        }
+
+        match self.state {
+            State::Ground if run_start < bytes.len() => {
+                emit(Segment::Output(&bytes[run_start..]));
+            }
+            State::Escape if !self.held_esc => {
+                // Hold the trailing ESC back until the next chunk
+                if bytes.len() - 1 > run_start {
+                    emit(Segment::Output(&bytes[run_start..bytes.len() - 1]));
+                }
+                self.held_esc = true;
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    enum Piece {
+        Out(Vec<u8>),
+        Apc(Vec<u8>),
+    }
+
+    fn split(scanner: &mut ApcScanner, bytes: &[u8]) -> Vec<Piece> {
+        let mut pieces = Vec::new();
+        scanner.split(bytes, |segment| match segment {
+            Segment::Output(b) => pieces.push(Piece::Out(b.to_vec())),
+            Segment::Apc(b) => pieces.push(Piece::Apc(b.to_vec())),
+        });
+        pieces
+    }
+
+    #[test]
+    fn separates_apc_from_output() {
+        let mut s = ApcScanner::default();
+        assert_eq!(split(&mut s, b"ab\x1b_Gx=1;AAAA\x1b\\cd\x1b[1m"), vec![
+            Piece::Out(b"ab".to_vec()),
+            Piece::Apc(b"Gx=1;AAAA".to_vec()),
+            Piece::Out(b"cd\x1b[1m".to_vec()),
+        ]);
+    }
+
+    #[test]
+    fn apc_split_across_chunks() {
+        let mut s = ApcScanner::default();
+        assert_eq!(split(&mut s, b"a\x1b"), vec![Piece::Out(b"a".to_vec())]);
+        assert_eq!(split(&mut s, b"_Gab"), vec![]);
+        assert_eq!(split(&mut s, b"c\x1b"), vec![]);
+        assert_eq!(split(&mut s, b"\\z"), vec![Piece::Apc(b"Gabc".to_vec()), Piece::Out(b"z".to_vec())]);
+    }
+
+    #[test]
+    fn held_escape_is_released() {
+        let mut s = ApcScanner::default();
+        assert_eq!(split(&mut s, b"a\x1b"), vec![Piece::Out(b"a".to_vec())]);
+        assert_eq!(split(&mut s, b"[1m"), vec![Piece::Out(b"\x1b".to_vec()), Piece::Out(b"[1m".to_vec())]);
+    }
+
+    #[test]
+    fn other_escape_aborts_apc() {
+        let mut s = ApcScanner::default();
+        assert_eq!(split(&mut s, b"\x1b_Gabc\x1b[1mx"), vec![
+            Piece::Out(b"\x1b".to_vec()),
+            Piece::Out(b"[1mx".to_vec()),
+        ]);
+    }
+}
@@ -11,13 +11,27 @@ impl Default for Color {
     }
 }
 
+/// Underline drawn under a cell (SGR 4, 4:n and 21).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UnderlineStyle {
+    #[default]
+    None,
+    Single,
+    Double,
+    Curly,
+    Dotted,
+    Dashed,
+}
+
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub struct CellAttributes {
     pub fg: Color,
     pub bg: Color,
     pub bold: bool,
     pub italic: bool,
-    pub underline: bool,
+    pub underline: UnderlineStyle,
+    /// Underline color (SGR 58); `Default` follows the foreground.
+    pub underline_color: Color,
     pub strikethrough: bool,
     pub blink: bool,
     pub reverse: bool,
@@ -25,15 +39,35 @@ pub struct CellAttributes {
     pub dim: bool,
 }
 
+/// Shell integration zone a cell was printed in (OSC 133 / FinalTerm marks).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SemanticZone {
+    /// No marks seen, or printed after the command finished.
+    #[default]
+    None,
+    /// Between `A` (prompt start) and `B`.
+    Prompt,
+    /// Between `B` (command start) and `C`: what the user typed.
+    Input,
+    /// Between `C` (output start) and `D` (command finished).
+    Output,
+}
+
 #[derive(Debug, Clone, Copy, Default, PartialEq)]
 pub struct Cell {
     pub ch: char,
     pub attrs: CellAttributes,
+    /// OSC 8 hyperlink: index + 1 into `TerminalGrid::hyperlinks`, 0 for none.
+    pub link: u32,
+    pub zone: SemanticZone,
+    /// Shell command (OSC 133) the cell belongs to: index + 1 into
+    /// `TerminalGrid::commands`, 0 for none.
+    pub command: u32,
 }
 
 impl Cell {
     pub fn new(ch: char, attrs: CellAttributes) -> Self {
-        Self { ch, attrs }
+        Self { ch, attrs, link: 0, zone: SemanticZone::None, command: 0 }
     }
 
     pub fn is_empty(&self) -> bool {
@@ -57,7 +91,8 @@ mod tests {
         assert_eq!(attrs.bg, Color::Default);
         assert!(!attrs.bold);
         assert!(!attrs.italic);
-        assert!(!attrs.underline);
+        assert_eq!(attrs.underline, UnderlineStyle::None);
+        assert_eq!(attrs.underline_color, Color::Default);
         assert!(!attrs.strikethrough);
         assert!(!attrs.blink);
         assert!(!attrs.reverse);
@@ -0,0 +1,127 @@
+/// A 94-character set that can be designated into G0–G3.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Charset {
+    #[default]
+    Ascii,
+    /// United Kingdom: `#` is the pound sign.
+    Uk,
+    /// DEC Special Graphics: line drawing in place of `_` through `~`.
+    DecSpecialGraphics,
+}
+
+impl Charset {
+    /// The set named by the final byte of `ESC ( F` and friends.
+    pub fn from_designator(byte: u8) -> Option<Self> {
+        match byte {
+            b'B' => Some(Charset::Ascii),
+            b'A' => Some(Charset::Uk),
+            b'0' => Some(Charset::DecSpecialGraphics),
+            _ => None,
+        }
+    }
+
+    fn map(self, c: char) -> char {
+        match self {
+            Charset::Ascii => c,
+            Charset::Uk => if c == '#' { '£' } else { c },
+            Charset::DecSpecialGraphics => match c {
+                '_' => ' ',
+                '`' => '◆',
+                'a' => '▒',
+                'b' => '␉',
+                'c' => '␌',
+                'd' => '␍',
+                'e' => '␊',
+                'f' => '°',
+                'g' => '±',
+                'h' => '␤',
+                'i' => '␋',
+                'j' => '┘',
+                'k' => '┐',
+                'l' => '┌',
+                'm' => '└',
+                'n' => '┼',
+                'o' => '⎺',
+                'p' => '⎻',
+                'q' => '─',
+                'r' => '⎼',
+                's' => '⎽',
+                't' => '├',
+                'u' => '┤',
+                'v' => '┴',
+                'w' => '┬',
+                'x' => '│',
+                'y' => '≤',
+                'z' => '≥',
+                '{' => 'π',
+                '|' => '≠',
+                '}' => '£',
+                '~' => '·',
+                _ => c,
+            },
+        }
+    }
+}
+
+/// G0–G3 designations and which of them is invoked into GL.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Charsets {
+    slots: [Charset; 4],
+    /// Slot locked into GL (SI/SO, LS2/LS3)
+    active: usize,
+    /// Slot used for the next character only (SS2/SS3)
+    single_shift: Option<usize>,
+}
+
+impl Charsets {
+    /// Designate `charset` into G`slot`.
+    pub fn designate(&mut self, slot: usize, charset: Charset) {
+        self.slots[slot] = charset;
+    }
+
+    /// Invoke G`slot` into GL until further notice.
+    pub fn lock_shift(&mut self, slot: usize) {
+        self.active = slot;
+    }
+
+    /// Use G`slot` for the next printed character.
+    pub fn single_shift(&mut self, slot: usize) {
+        self.single_shift = Some(slot);
+    }
+
+    /// Translate a printed character through the invoked set.
+    pub fn map(&mut self, c: char) -> char {
+        let slot = self.single_shift.take().unwrap_or(self.active);
+        self.slots[slot].map(c)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn special_graphics_draws_boxes() {
+        let mut charsets = Charsets::default();
+        charsets.designate(0, Charset::DecSpecialGraphics);
+        let line: String = "lqqkx".chars().map(|c| charsets.map(c)).collect();
+        assert_eq!(line, "┌──┐│");
+        // Only lowercase letters and a few symbols are replaced
+        assert_eq!(charsets.map('A'), 'A');
+    }
+
+    #[test]
+    fn shifts_select_the_slot() {
+        let mut charsets = Charsets::default();
+        charsets.designate(1, Charset::DecSpecialGraphics);
+        charsets.designate(2, Charset::Uk);
+        assert_eq!(charsets.map('q'), 'q');
+        charsets.lock_shift(1);
+        assert_eq!(charsets.map('q'), '─');
+        charsets.single_shift(2);
+        assert_eq!(charsets.map('#'), '£');
+        assert_eq!(charsets.map('#'), '#');
+        charsets.lock_shift(0);
+        assert_eq!(charsets.map('q'), 'q');
+    }
+}
@@ -0,0 +1,115 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+
+/// An OSC 52 clipboard operation requested by the application. The parser
+/// only records these; the app owns the system clipboard and the access
+/// policy, and drains them each frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClipboardRequest {
+    /// Set the clipboard to the (still base64-encoded) payload.
+    Write { data: Vec<u8> },
+    /// Report the clipboard contents back to the application.
+    /// `selection` is echoed in the reply; `bell_terminated` picks its terminator.
+    Read { selection: String, bell_terminated: bool },
+}
+
+/// Default for the largest OSC 52 payload, in decoded bytes.
+pub const OSC52_MAX_BYTES: usize = 1024 * 1024;
+
+/// Parse the `Pc ; Pd` part of an OSC 52 sequence. Writes whose payload
+/// would decode to more than `max_bytes` are dropped here, before they are
+/// queued.
+pub fn parse_osc52(
+    selection: &[u8],
+    data: &[u8],
+    bell_terminated: bool,
+    max_bytes: usize,
+) -> Option<ClipboardRequest> {
+    let selection = std::str::from_utf8(selection).ok()?;
+    // An empty selection list means "s 0" in xterm; we only have one clipboard.
+    let selection = if selection.is_empty() { "c" } else { selection };
+    if data == b"?" {
+        Some(ClipboardRequest::Read { selection: selection.to_string(), bell_terminated })
+    } else if payload_fits(data, max_bytes) {
+        Some(ClipboardRequest::Write { data: data.to_vec() })
+    } else {
+        None
+    }
+}
+
+/// Whether base64 `data` can decode to at most `max_bytes`.
+fn payload_fits(data: &[u8], max_bytes: usize) -> bool {
+    data.len() / 4 * 3 <= max_bytes + 3
+}
+
+/// Decode an OSC 52 write payload. Returns `None` if it is not valid base64
+/// UTF-8 text or decodes to more than `max_bytes`.
+pub fn decode_osc52_payload(data: &[u8], max_bytes: usize) -> Option<String> {
+    // Reject oversized payloads before allocating for them
+    if !payload_fits(data, max_bytes) {
+        return None;
+    }
+    let decoded = STANDARD.decode(data).ok()?;
+    if decoded.len() > max_bytes {
+        return None;
+    }
+    String::from_utf8(decoded).ok()
+}
+
+/// Build the reply to an OSC 52 read request.
+pub fn encode_osc52_response(selection: &str, text: &str, bell_terminated: bool) -> Vec<u8> {
+    let terminator = if bell_terminated { "\x07" } else { "\x1b\\" };
+    format!("\x1b]52;{};{}{}", selection, STANDARD.encode(text), terminator).into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_write() {
+        assert_eq!(
+            parse_osc52(b"c", b"aGk=", false, 100),
+            Some(ClipboardRequest::Write { data: b"aGk=".to_vec() })
+        );
+    }
+
+    #[test]
+    fn parse_drops_oversized_write() {
+        let big = STANDARD.encode(vec![b'a'; 1000]);
+        assert_eq!(parse_osc52(b"c", big.as_bytes(), false, 10), None);
+        assert!(parse_osc52(b"c", big.as_bytes(), false, 1000).is_some());
+    }
+
+    #[test]
+    fn parse_read_defaults_selection() {
+        assert_eq!(
+            parse_osc52(b"", b"?", true, 0),
+            Some(ClipboardRequest::Read { selection: "c".to_string(), bell_terminated: true })
+        );
+    }
+
+    #[test]
+    fn decode_valid_payload() {
+        assert_eq!(decode_osc52_payload(b"aGVsbG8=", 100), Some("hello".to_string()));
+    }
+
+    #[test]
+    fn decode_rejects_invalid_base64() {
+        assert_eq!(decode_osc52_payload(b"not base64!", 100), None);
+    }
+
+    #[test]
+    fn decode_enforces_size_limit() {
+        assert_eq!(decode_osc52_payload(b"aGVsbG8=", 5), Some("hello".to_string()));
+        assert_eq!(decode_osc52_payload(b"aGVsbG8=", 4), None);
+        let big = STANDARD.encode(vec![b'a'; 1000]);
+        assert_eq!(decode_osc52_payload(big.as_bytes(), 10), None);
+    }
+
+    #[test]
+    fn response_encoding() {
+        assert_eq!(encode_osc52_response("c", "hi", false), b"\x1b]52;c;aGk=\x1b\\".to_vec());
+        assert_eq!(encode_osc52_response("p", "hi", true), b"\x1b]52;p;aGk=\x07".to_vec());
+    }
+}
@@ -0,0 +1,239 @@
+/// Configured colors a pane starts from (ANSI 0–15, default fg/bg, cursor).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Theme {
+    pub palette: [[f32; 4]; 16],
+    pub foreground: [f32; 4],
+    pub background: [f32; 4],
+    pub cursor: [f32; 4],
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        // xterm's default 16 colors
+        const XTERM: [[u8; 3]; 16] = [
+            [0x00, 0x00, 0x00], [0xcd, 0x00, 0x00], [0x00, 0xcd, 0x00], [0xcd, 0xcd, 0x00],
+            [0x00, 0x00, 0xee], [0xcd, 0x00, 0xcd], [0x00, 0xcd, 0xcd], [0xe5, 0xe5, 0xe5],
+            [0x7f, 0x7f, 0x7f], [0xff, 0x00, 0x00], [0x00, 0xff, 0x00], [0xff, 0xff, 0x00],
+            [0x5c, 0x5c, 0xff], [0xff, 0x00, 0xff], [0x00, 0xff, 0xff], [0xff, 0xff, 0xff],
+        ];
+        let mut palette = [[0.0; 4]; 16];
+        for (dst, [r, g, b]) in palette.iter_mut().zip(XTERM) {
+            *dst = rgb_to_rgba(r, g, b);
+        }
+        Self {
+            palette,
+            foreground: [1.0, 1.0, 1.0, 1.0],
+            background: [0.0, 0.0, 0.0, 1.0],
+            cursor: [1.0, 1.0, 1.0, 1.0],
+        }
+    }
+}
+
+/// A pane's live colors: the theme plus changes made at runtime with
+/// OSC 4/10/11/12 (undone by OSC 104/110/111/112).
+#[derive(Debug, Clone)]
+pub struct TerminalColors {
+    theme: Theme,
+    palette: [Option<[f32; 4]>; 256],
+    foreground: Option<[f32; 4]>,
+    background: Option<[f32; 4]>,
+    cursor: Option<[f32; 4]>,
+}
+
+impl Default for TerminalColors {
+    fn default() -> Self {
+        Self::new(Theme::default())
+    }
+}
+
+impl TerminalColors {
+    pub fn new(theme: Theme) -> Self {
+        Self { theme, palette: [None; 256], foreground: None, background: None, cursor: None }
+    }
+
+    /// Replace the underlying theme (e.g. on config reload). Runtime
+    /// overrides are kept.
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+    }
+
+    /// The full 256-color palette with overrides applied.
+    pub fn palette(&self) -> [[f32; 4]; 256] {
+        std::array::from_fn(|i| self.palette_color(i as u8))
+    }
+
+    pub fn palette_color(&self, index: u8) -> [f32; 4] {
+        let i = index as usize;
+        self.palette[i].unwrap_or_else(|| {
+            if i < 16 { self.theme.palette[i] } else { xterm256_to_rgba(index) }
+        })
+    }
+
+    /// Override a palette entry; `None` restores the theme color.
+    pub fn set_palette_color(&mut self, index: u8, color: Option<[f32; 4]>) {
+        self.palette[index as usize] = color;
+    }
+
+    pub fn reset_palette(&mut self) {
+        self.palette = [None; 256];
+    }
+
+    /// Undo every runtime change (RIS), going back to the theme.
+    pub fn reset_overrides(&mut self) {
+        *self = Self::new(self.theme);
+    }
+
+    pub fn foreground(&self) -> [f32; 4] {
+        self.foreground.unwrap_or(self.theme.foreground)
+    }
+
+    pub fn background(&self) -> [f32; 4] {
+        self.background.unwrap_or(self.theme.background)
+    }
+
+    pub fn cursor(&self) -> [f32; 4] {
+        // Keep the theme's alpha: OSC 12 only carries RGB
+        match self.cursor {
+            Some([r, g, b, _]) => [r, g, b, self.theme.cursor[3]],
+            None => self.theme.cursor,
+        }
+    }
+
+    pub fn set_foreground(&mut self, color: Option<[f32; 4]>) {
+        self.foreground = color;
+    }
+
+    pub fn set_background(&mut self, color: Option<[f32; 4]>) {
+        self.background = color;
+    }
+
+    pub fn set_cursor(&mut self, color: Option<[f32; 4]>) {
+        self.cursor = color;
+    }
+}
+
+fn rgb_to_rgba(r: u8, g: u8, b: u8) -> [f32; 4] {
+    [r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0, 1.0]
+}
+
+/// Standard xterm colors for indices 16–255 (6×6×6 cube, then grayscale).
+fn xterm256_to_rgba(i: u8) -> [f32; 4] {
+    if i < 16 {
+        return [1.0, 1.0, 1.0, 1.0];
+    }
+    if i >= 232 {
+        let gray = (i - 232) as f32 / 23.0;
+        return [gray, gray, gray, 1.0];
+    }
+    let i = i - 16;
+    let r = (i / 36) % 6;
+    let g = (i / 6) % 6;
+    let b = i % 6;
+    let scale = |v: u8| if v == 0 { 0.0 } else { (55.0 + v as f32 * 40.0) / 255.0 };
+    [scale(r), scale(g), scale(b), 1.0]
+}
+
+/// Parse an X11 color spec as used by OSC 4/10/11/12: `rgb:r/g/b` with 1–4
+/// hex digits per channel, or `#rgb`, `#rrggbb`, `#rrrgggbbb`, `#rrrrggggbbbb`.
+pub fn parse_color_spec(spec: &str) -> Option<[f32; 4]> {
+    let channel = |s: &str| -> Option<f32> {
+        if s.is_empty() || s.len() > 4 {
+            return None;
+        }
+        let v = u32::from_str_radix(s, 16).ok()?;
+        Some(v as f32 / ((1u32 << (4 * s.len())) - 1) as f32)
+    };
+    if let Some(rest) = spec.strip_prefix("rgb:") {
+        let mut parts = rest.split('/');
+        let r = channel(parts.next()?)?;
+        let g = channel(parts.next()?)?;
+        let b = channel(parts.next()?)?;
+        if parts.next().is_some() {
+            return None;
+        }
+        return Some([r, g, b, 1.0]);
+    }
+    let hex = spec.strip_prefix('#')?;
+    if hex.is_empty() || hex.len() % 3 != 0 || hex.len() > 12 || !hex.is_ascii() {
+        return None;
+    }
+    let n = hex.len() / 3;
+    Some([channel(&hex[..n])?, channel(&hex[n..2 * n])?, channel(&hex[2 * n..])?, 1.0])
+}
+
+/// Format a color the way xterm reports it: `rgb:rrrr/gggg/bbbb`.
+pub fn format_color_spec(color: [f32; 4]) -> String {
+    let channel = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u16 * 257;
+    format!("rgb:{:04x}/{:04x}/{:04x}", channel(color[0]), channel(color[1]), channel(color[2]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_rgb_spec() {
+        assert_eq!(parse_color_spec("rgb:ff/00/80"), Some([1.0, 0.0, 128.0 / 255.0, 1.0]));
+        assert_eq!(parse_color_spec("rgb:ffff/0000/0000"), Some([1.0, 0.0, 0.0, 1.0]));
+        assert_eq!(parse_color_spec("rgb:f/0/0"), Some([1.0, 0.0, 0.0, 1.0]));
+    }
+
+    #[test]
+    fn parse_hash_spec() {
+        assert_eq!(parse_color_spec("#ff0000"), Some([1.0, 0.0, 0.0, 1.0]));
+        assert_eq!(parse_color_spec("#f00"), Some([1.0, 0.0, 0.0, 1.0]));
+        assert_eq!(parse_color_spec("#ffff00000000"), Some([1.0, 0.0, 0.0, 1.0]));
+    }
+
+    #[test]
+    fn parse_invalid_spec() {
+        assert_eq!(parse_color_spec("red"), None);
+        assert_eq!(parse_color_spec("rgb:ff/00"), None);
+        assert_eq!(parse_color_spec("rgb:fffff/0/0"), None);
+        assert_eq!(parse_color_spec("#ff00"), None);
+    }
+
+    #[test]
+    fn format_spec_is_16_bit() {
+        assert_eq!(format_color_spec([1.0, 0.0, 128.0 / 255.0, 1.0]), "rgb:ffff/0000/8080");
+    }
+
+    #[test]
+    fn overrides_fall_back_to_theme() {
+        let mut theme = Theme::default();
+        theme.palette[1] = [0.5, 0.0, 0.0, 1.0];
+        let mut colors = TerminalColors::new(theme);
+        assert_eq!(colors.palette_color(1), [0.5, 0.0, 0.0, 1.0]);
+        colors.set_palette_color(1, Some([0.0, 1.0, 0.0, 1.0]));
+        assert_eq!(colors.palette_color(1), [0.0, 1.0, 0.0, 1.0]);
+        colors.reset_palette();
+        assert_eq!(colors.palette_color(1), [0.5, 0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn set_theme_keeps_overrides() {
+        let mut colors = TerminalColors::default();
+        colors.set_background(Some([0.2, 0.2, 0.2, 1.0]));
+        let theme = Theme { foreground: [0.0, 0.0, 1.0, 1.0], ..Theme::default() };
+        colors.set_theme(theme);
+        assert_eq!(colors.background(), [0.2, 0.2, 0.2, 1.0]);
+        assert_eq!(colors.foreground(), [0.0, 0.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn palette_extends_to_256() {
+        let colors = TerminalColors::default();
+        let palette = colors.palette();
+        assert_eq!(palette[16], [0.0, 0.0, 0.0, 1.0]);
+        assert_eq!(palette[231], [1.0, 1.0, 1.0, 1.0]);
+        assert_eq!(palette[255], [1.0, 1.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn cursor_override_keeps_theme_alpha() {
+        let theme = Theme { cursor: [1.0, 1.0, 1.0, 0.8], ..Theme::default() };
+        let mut colors = TerminalColors::new(theme);
+        colors.set_cursor(Some([1.0, 0.0, 0.0, 1.0]));
+        assert_eq!(colors.cursor(), [1.0, 0.0, 0.0, 0.8]);
+    }
+}
@@ -0,0 +1,192 @@
+use super::cell::{CellAttributes, Color, UnderlineStyle};
+use super::grid::TerminalGrid;
+
+/// Largest DCS payload we buffer. Longer strings are dropped unanswered.
+pub const DCS_MAX_BYTES: usize = 64 * 1024;
+
+/// A DCS string we know how to answer, identified from its introducer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DcsKind {
+    /// `DCS $ q Pt ST`: request a setting (DECRQSS).
+    Decrqss,
+    /// `DCS + q Pt ST`: request terminfo capabilities (XTGETTCAP).
+    Xtgettcap,
+}
+
+impl DcsKind {
+    pub fn from_hook(intermediates: &[u8], action: char) -> Option<Self> {
+        match (intermediates, action) {
+            (b"$", 'q') => Some(DcsKind::Decrqss),
+            (b"+", 'q') => Some(DcsKind::Xtgettcap),
+            _ => None,
+        }
+    }
+}
+
+/// Capabilities reported through XTGETTCAP, matching the xterm-256color
+/// terminfo we advertise in `TERM`. `None` marks a boolean capability.
+const CAPABILITIES: &[(&str, Option<&str>)] = &[
+    ("TN", Some("xterm-256color")),
+    ("name", Some("xterm-256color")),
+    ("Co", Some("256")),
+    ("colors", Some("256")),
+    ("RGB", Some("8/8/8")),
+    ("Tc", None),
+    ("setrgbf", Some("\x1b[38;2;%p1%d;%p2%d;%p3%dm")),
+    ("setrgbb", Some("\x1b[48;2;%p1%d;%p2%d;%p3%dm")),
+    ("Ms", Some("\x1b]52;%p1%s;%p2%s\x07")),
+    ("Ss", Some("\x1b[%p1%d q")),
+    ("Se", Some("\x1b[0 q")),
+    ("Smulx", Some("\x1b[4:%p1%dm")),
+    ("Setulc", Some("\x1b[58:2::%p1%{65536}%/%d:%p1%{256}%/%{255}%&%d:%p1%{255}%&%dm")),
+    ("bce", None),
+    ("km", None),
+];
+
+/// Answer a DECRQSS request. Only SGR (`m`) and the scroll margins (`r`,
+/// `s`) are reported; anything else gets the "invalid request" reply.
+pub fn decrqss_response(grid: &TerminalGrid, request: &[u8]) -> Vec<u8> {
+    let setting = match request {
+        b"m" => Some(format!("{}m", sgr_params(&grid.current_attrs))),
+        b"r" => Some(format!("{};{}r", grid.scroll_top + 1, grid.scroll_bottom + 1)),
+        b"s" => Some(format!("{};{}s", grid.scroll_left + 1, grid.scroll_right + 1)),
+        _ => None,
+    };
+    match setting {
+        Some(setting) => format!("\x1bP1$r{}\x1b\\", setting).into_bytes(),
+        None => b"\x1bP0$r\x1b\\".to_vec(),
+    }
+}
+
+/// Answer an XTGETTCAP request: `;`-separated hex-encoded capability names.
+/// Each name gets its own reply; unknown names get an error reply echoing
+/// the name, as xterm does.
+pub fn xtgettcap_responses(request: &[u8]) -> Vec<Vec<u8>> {
+    request
+        .split(|&b| b == b';')
+        .filter(|hex| !hex.is_empty())
+        .map(|hex| {
+            let name = hex_decode(hex);
+            let cap = name
+                .as_deref()
+                .and_then(|name| CAPABILITIES.iter().find(|(cap, _)| *cap == name));
+            let hex = String::from_utf8_lossy(hex);
+            match cap {
+                Some((_, Some(value))) => {
+                    format!("\x1bP1+r{}={}\x1b\\", hex, hex_encode(value.as_bytes())).into_bytes()
+                }
+                Some((_, None)) => format!("\x1bP1+r{}\x1b\\", hex).into_bytes(),
+                None => format!("\x1bP0+r{}\x1b\\", hex).into_bytes(),
+            }
+        })
+        .collect()
+}
+
+/// SGR parameters that reproduce `attrs` from a reset state.
+fn sgr_params(attrs: &CellAttributes) -> String {
+    let mut params = vec!["0".to_string()];
+    let flags = [
+        (attrs.bold, "1"),
+        (attrs.dim, "2"),
+        (attrs.italic, "3"),
+        (attrs.blink, "5"),
+        (attrs.reverse, "7"),
+        (attrs.invisible, "8"),
+        (attrs.strikethrough, "9"),
+    ];
+    params.extend(flags.iter().filter(|(on, _)| *on).map(|(_, p)| p.to_string()));
+    let underline = match attrs.underline {
+        UnderlineStyle::None => None,
+        UnderlineStyle::Single => Some("4"),
+        UnderlineStyle::Double => Some("4:2"),
+        UnderlineStyle::Curly => Some("4:3"),
+        UnderlineStyle::Dotted => Some("4:4"),
+        UnderlineStyle::Dashed => Some("4:5"),
+    };
+    params.extend(underline.map(str::to_string));
+    params.extend(color_params(attrs.fg, 30, 90, 38));
+    params.extend(color_params(attrs.bg, 40, 100, 48));
+    params.extend(match attrs.underline_color {
+        Color::Default => None,
+        Color::Indexed(i) => Some(format!("58:5:{}", i)),
+        Color::Rgb(r, g, b) => Some(format!("58:2::{}:{}:{}", r, g, b)),
+    });
+    params.join(";")
+}
+
+fn color_params(color: Color, base: u8, bright_base: u8, extended: u8) -> Option<String> {
+    match color {
+        Color::Default => None,
+        Color::Indexed(i) if i < 8 => Some((base + i).to_string()),
+        Color::Indexed(i) if i < 16 => Some((bright_base + i - 8).to_string()),
+        Color::Indexed(i) => Some(format!("{};5;{}", extended, i)),
+        Color::Rgb(r, g, b) => Some(format!("{};2;{};{};{}", extended, r, g, b)),
+    }
+}
+
+fn hex_decode(hex: &[u8]) -> Option<String> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    let bytes = hex
+        .chunks(2)
+        .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    String::from_utf8(bytes).ok()
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02X}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decrqss_sgr() {
+        let mut grid = TerminalGrid::new(10, 5);
+        assert_eq!(decrqss_response(&grid, b"m"), b"\x1bP1$r0m\x1b\\".to_vec());
+        grid.current_attrs.bold = true;
+        grid.current_attrs.fg = Color::Indexed(9);
+        grid.current_attrs.bg = Color::Rgb(1, 2, 3);
+        assert_eq!(decrqss_response(&grid, b"m"), b"\x1bP1$r0;1;91;48;2;1;2;3m\x1b\\".to_vec());
+        grid.current_attrs = CellAttributes {
+            underline: UnderlineStyle::Curly,
+            underline_color: Color::Rgb(4, 5, 6),
+            ..Default::default()
+        };
+        assert_eq!(decrqss_response(&grid, b"m"), b"\x1bP1$r0;4:3;58:2::4:5:6m\x1b\\".to_vec());
+    }
+
+    #[test]
+    fn decrqss_margins() {
+        let mut grid = TerminalGrid::new(10, 5);
+        grid.scroll_top = 1;
+        grid.scroll_bottom = 3;
+        assert_eq!(decrqss_response(&grid, b"r"), b"\x1bP1$r2;4r\x1b\\".to_vec());
+    }
+
+    #[test]
+    fn decrqss_unsupported() {
+        let grid = TerminalGrid::new(10, 5);
+        assert_eq!(decrqss_response(&grid, b"\"p"), b"\x1bP0$r\x1b\\".to_vec());
+    }
+
+    #[test]
+    fn xtgettcap_known_and_unknown() {
+        // "TN" = 544E, "Tc" = 5463, "xx" = 7878
+        let replies = xtgettcap_responses(b"544E;5463;7878");
+        assert_eq!(replies, vec![
+            format!("\x1bP1+r544E={}\x1b\\", hex_encode(b"xterm-256color")).into_bytes(),
+            b"\x1bP1+r5463\x1b\\".to_vec(),
+            b"\x1bP0+r7878\x1b\\".to_vec(),
+        ]);
+    }
+
+    #[test]
+    fn xtgettcap_lowercase_hex_and_garbage() {
+        assert_eq!(xtgettcap_responses(b"436f"), vec![format!("\x1bP1+r436f={}\x1b\\", hex_encode(b"256")).into_bytes()]);
+        assert_eq!(xtgettcap_responses(b"zz"), vec![b"\x1bP0+rzz\x1b\\".to_vec()]);
+    }
+}
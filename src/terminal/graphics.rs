@@ -0,0 +1,80 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Decoded pixels of an inline image (sixel, kitty, iTerm2). Shared by
+/// every placement that shows it; `id` keys the renderer's texture cache.
+#[derive(Debug)]
+pub struct ImageData {
+    pub id: u64,
+    pub width: u32,
+    pub height: u32,
+    /// Straight (non-premultiplied) RGBA, `width * height * 4` bytes.
+    pub rgba: Vec<u8>,
+}
+
+impl ImageData {
+    pub fn new(width: u32, height: u32, rgba: Vec<u8>) -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+        debug_assert_eq!(rgba.len(), width as usize * height as usize * 4);
+        Self { id: NEXT_ID.fetch_add(1, Ordering::Relaxed), width, height, rgba }
+    }
+
+    pub fn byte_size(&self) -> usize {
+        self.rgba.len()
+    }
+}
+
+/// An image shown on the grid, anchored to the cell at its top-left corner.
+#[derive(Debug, Clone)]
+pub struct ImagePlacement {
+    pub image: Arc<ImageData>,
+    /// Stable line number of the top row (see `TerminalGrid::line_of_abs_row`),
+    /// so the image scrolls with the text into scrollback.
+    pub line: u64,
+    pub col: usize,
+    /// Cells covered on screen.
+    pub cols: usize,
+    pub rows: usize,
+    /// Offset of the image within its top-left cell, in pixels.
+    pub offset: (u32, u32),
+    /// Part of the image shown, in image pixels: (x, y, width, height).
+    pub source: (u32, u32, u32, u32),
+    /// Displayed size in pixels; differs from `source` when scaled.
+    pub size: (f32, f32),
+    /// Stacking order: negative values are drawn below text.
+    pub z_index: i32,
+    /// Kitty graphics image id and placement id (0 for other protocols).
+    pub kitty_id: u32,
+    pub kitty_placement: u32,
+}
+
+impl ImagePlacement {
+    /// Place the whole image at its natural size. Such images sit below
+    /// the text, so anything printed over them stays readable.
+    pub fn new(image: Arc<ImageData>, line: u64, col: usize, cols: usize, rows: usize) -> Self {
+        let (width, height) = (image.width, image.height);
+        Self {
+            image,
+            line,
+            col,
+            cols,
+            rows,
+            offset: (0, 0),
+            source: (0, 0, width, height),
+            size: (width as f32, height as f32),
+            z_index: -1,
+            kitty_id: 0,
+            kitty_placement: 0,
+        }
+    }
+
+    /// Whether the image covers the cell at (`line`, `col`).
+    pub fn covers(&self, line: u64, col: usize) -> bool {
+        (self.line..self.end_line()).contains(&line) && (self.col..self.col + self.cols).contains(&col)
+    }
+
+    /// Line number just below the image.
+    pub fn end_line(&self) -> u64 {
+        self.line + self.rows as u64
+    }
+}
@@ -1,19 +1,152 @@
-use super::cell::{Cell, CellAttributes};
-use std::collections::VecDeque;
+use super::cell::{Cell, CellAttributes, SemanticZone};
+use super::clipboard::{ClipboardRequest, OSC52_MAX_BYTES};
+use super::notification::Notification;
+use super::colors::TerminalColors;
+use super::graphics::{ImageData, ImagePlacement};
+use super::kitty::KittyImageStore;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Pixel budget for inline images kept by one grid (both screens and
+/// scrollback). The oldest placements are dropped beyond it.
+const IMAGE_MEMORY_LIMIT: usize = 256 * 1024 * 1024;
+
+/// Which mouse events the application asked to receive (DEC modes 1000/1002/1003).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MouseTracking {
+    /// No reporting; the mouse drives local selection and scrolling.
+    #[default]
+    Off,
+    /// Mode 1000: button presses, releases and wheel.
+    Click,
+    /// Mode 1002: as `Click`, plus motion while a button is held.
+    ButtonMotion,
+    /// Mode 1003: as `ButtonMotion`, plus motion with no button held.
+    AnyMotion,
+}
+
+impl MouseTracking {
+    /// Whether a motion event should be reported given the current button state.
+    pub fn reports_motion(self, button_held: bool) -> bool {
+        match self {
+            MouseTracking::Off | MouseTracking::Click => false,
+            MouseTracking::ButtonMotion => button_held,
+            MouseTracking::AnyMotion => true,
+        }
+    }
+}
+
+/// Target of an OSC 8 hyperlink. Cells with the same `id` and `uri` belong
+/// to the same link even when printed in separate pieces.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Hyperlink {
+    pub id: Option<String>,
+    pub uri: String,
+}
+
+/// A shell command delimited by OSC 133 marks, from its prompt (`A`) to
+/// its completion (`D`).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShellCommand {
+    /// Exit status reported with `D`, if the shell sent one.
+    pub exit_code: Option<i32>,
+}
+
+/// Wire format for mouse reports (DEC modes 1006/1015).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MouseEncoding {
+    /// `ESC [ M Cb Cx Cy` with each value offset by 32 and limited to 223.
+    #[default]
+    Default,
+    /// Mode 1006: `ESC [ < b ; x ; y M/m`.
+    Sgr,
+    /// Mode 1015: `ESC [ b ; x ; y M` with decimal values.
+    Urxvt,
+}
+
+/// Cursor shape, picked by the config or by the application with DECSCUSR.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CursorShape {
+    #[default]
+    Block,
+    Underline,
+    Bar,
+}
+
+/// Size of a whole line, set with DECDWL/DECDHL (`ESC # 3`–`ESC # 6`).
+/// Wide and tall lines show half as many columns as the screen has.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineSize {
+    #[default]
+    Single,
+    DoubleWidth,
+    /// Top half of a double-height line
+    DoubleHeightTop,
+    /// Bottom half of a double-height line
+    DoubleHeightBottom,
+}
+
+/// Kitty keyboard protocol progressive enhancement flags (`CSI > flags u`).
+/// Bit 1 (disambiguate escape codes) is implied by any other flag, so any
+/// non-empty set switches the key encoder over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct KeyboardFlags(pub u8);
+
+impl KeyboardFlags {
+    pub const REPORT_EVENT_TYPES: Self = Self(2);
+    pub const REPORT_ALTERNATE_KEYS: Self = Self(4);
+    pub const REPORT_ALL_KEYS: Self = Self(8);
+    pub const REPORT_TEXT: Self = Self(16);
+    const ALL: u8 = 31;
+
+    pub fn contains(self, flag: Self) -> bool {
+        self.0 & flag.0 == flag.0
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+}
+
+/// Most flag sets an application can push; the oldest are dropped beyond it.
+const KEYBOARD_STACK_LIMIT: usize = 64;
+
+/// Most titles saved with `CSI 22 t`, as in xterm.
+const TITLE_STACK_LIMIT: usize = 10;
+
+/// Most OSC 8 targets kept at once. When the table fills, targets no cell
+/// refers to any more are dropped; new links beyond it print as plain text.
+const HYPERLINK_LIMIT: usize = 4096;
+
+/// Most OSC 133 commands kept at once. When the table fills, commands no
+/// cell belongs to any more are dropped; beyond it new commands go unmarked.
+const COMMAND_LIMIT: usize = 16384;
 
 #[derive(Debug, Clone)]
 pub struct TerminalGrid {
     pub cols: usize,
     pub rows: usize,
     pub cells: Vec<Vec<Cell>>,
+    /// Size of each row in `cells`. Rows pushed to scrollback go back to
+    /// single size.
+    pub line_sizes: Vec<LineSize>,
     pub cursor_col: usize,
     pub cursor_row: usize,
     pub scroll_top: usize,
     pub scroll_bottom: usize,
+    /// Left and right margins (DECSLRM); the full width unless the
+    /// application set them with left/right margin mode (DECLRMM) on.
+    pub scroll_left: usize,
+    pub scroll_right: usize,
     pub scrollback: VecDeque<Vec<Cell>>,
     pub scrollback_limit: usize,
     pub current_attrs: CellAttributes,
     pub title: String,
+    /// Titles saved with `CSI 22 t`, restored by `CSI 23 t`.
+    title_stack: Vec<String>,
     /// Pending line wrap: next char goes to start of next line
     pub pending_wrap: bool,
     /// Incremented on every visible cell change.  The renderer compares this
@@ -31,12 +164,82 @@ pub struct TerminalGrid {
     /// the terminal cursor and draw their own; we must respect this so our
     /// animated cursor doesn't render in the wrong place.
     pub cursor_visible: bool,
+    /// Cursor shape set with DECSCUSR (`CSI Ps SP q`); `None` uses the
+    /// configured shape.
+    pub cursor_shape: Option<CursorShape>,
+    /// Cursor blinking set with DECSCUSR or DEC mode 12; `None` uses the
+    /// configured behavior.
+    pub cursor_blinking: Option<bool>,
     /// Reverse-video cursor position detected by scanning the grid.
     /// TUI apps like Claude Code hide the terminal cursor permanently and
     /// draw their own cursor as a single reverse-video (`ESC[7m`) character.
     /// Each frame we scan the visible cells to find that character and report
     /// its position so the GPU-animated cursor can track it.
     pub reverse_cursor: Option<(usize, usize)>,
+    /// Mouse events the application wants reported (DEC modes 1000/1002/1003).
+    pub mouse_tracking: MouseTracking,
+    /// How mouse reports are encoded (DEC modes 1006/1015).
+    pub mouse_encoding: MouseEncoding,
+    /// Reverse video for the whole screen (DECSCNM / DEC mode 5): default
+    /// foreground and background trade places.
+    pub reverse_video: bool,
+    /// Whether the alternate screen (DEC modes 47/1047/1049) is active.
+    /// `cells` always holds the active screen; the other one is parked in
+    /// `inactive_cells`. Lines scrolled off the alternate screen are
+    /// discarded rather than pushed to scrollback.
+    pub alt_screen: bool,
+    inactive_cells: Vec<Vec<Cell>>,
+    inactive_line_sizes: Vec<LineSize>,
+    /// Interned OSC 8 hyperlinks; `Cell::link` is an index + 1 into this table.
+    pub hyperlinks: Vec<Hyperlink>,
+    hyperlink_ids: HashMap<Hyperlink, u32>,
+    /// `scrollback_dropped` when the hyperlink table was last compacted.
+    hyperlink_gc_dropped: Option<u64>,
+    /// Hyperlink applied to newly printed cells (0 = none).
+    pub current_link: u32,
+    /// OSC 52 clipboard requests, drained each frame by the app.
+    pub clipboard_requests: Vec<ClipboardRequest>,
+    /// Largest OSC 52 write (decoded bytes) queued; larger ones are
+    /// dropped by the parser. Set by the app from the config.
+    pub clipboard_max_bytes: usize,
+    /// OSC 9 / OSC 777 desktop notifications, drained each frame by the app.
+    pub notifications: Vec<Notification>,
+    /// Set by BEL; taken each frame by the app.
+    pub bell: bool,
+    /// Palette and default colors, including OSC 4/10/11/12 changes.
+    pub colors: TerminalColors,
+    /// Working directory last reported by the shell with OSC 7. May be a
+    /// path on a remote host when the shell is inside ssh.
+    pub cwd: Option<PathBuf>,
+    /// Commands seen through OSC 133 marks; `Cell::command` is an index + 1
+    /// into this table.
+    pub commands: Vec<ShellCommand>,
+    /// `scrollback_dropped` when the command table was last compacted.
+    command_gc_dropped: Option<u64>,
+    /// Command applied to newly printed cells (0 = none).
+    pub current_command: u32,
+    /// Zone applied to newly printed cells.
+    pub current_zone: SemanticZone,
+    /// Horizontal tab stops, one flag per column (HTS / TBC).
+    tab_stops: Vec<bool>,
+    /// Inline images on the active screen (and its scrollback).
+    pub images: Vec<ImagePlacement>,
+    inactive_images: Vec<ImagePlacement>,
+    /// Rows dropped from the front of scrollback (or cleared with it) so
+    /// far. Together with the absolute row this gives each line a stable
+    /// number that images anchor to.
+    scrollback_dropped: u64,
+    /// Size of a cell in pixels, used to size images. Set by the app.
+    pub cell_size: (f32, f32),
+    /// Images transmitted with the kitty graphics protocol.
+    pub kitty: KittyImageStore,
+    /// xterm modifyOtherKeys level (`CSI > 4 ; level m`): 0 is off, 1
+    /// encodes modified keys that have no legacy sequence, 2 all of them.
+    pub modify_other_keys: u8,
+    /// Kitty keyboard protocol flag stack; the top entry is in effect.
+    /// Each screen has its own stack.
+    keyboard_flags: Vec<KeyboardFlags>,
+    inactive_keyboard_flags: Vec<KeyboardFlags>,
 }
 
 impl TerminalGrid {
@@ -46,21 +249,56 @@ impl TerminalGrid {
             cols,
             rows,
             cells,
+            line_sizes: vec![LineSize::Single; rows],
             cursor_col: 0,
             cursor_row: 0,
             scroll_top: 0,
             scroll_bottom: rows.saturating_sub(1),
+            scroll_left: 0,
+            scroll_right: cols.saturating_sub(1),
             scrollback: VecDeque::new(),
             scrollback_limit: 10000,
             current_attrs: CellAttributes::default(),
             title: String::new(),
+            title_stack: Vec::new(),
             pending_wrap: false,
             generation: 0,
             bracketed_paste: false,
             application_cursor_keys: false,
             response_queue: Vec::new(),
             cursor_visible: true,
+            cursor_shape: None,
+            cursor_blinking: None,
             reverse_cursor: None,
+            mouse_tracking: MouseTracking::Off,
+            mouse_encoding: MouseEncoding::Default,
+            reverse_video: false,
+            alt_screen: false,
+            inactive_cells: vec![vec![Cell::default(); cols]; rows],
+            inactive_line_sizes: vec![LineSize::Single; rows],
+            hyperlinks: Vec::new(),
+            hyperlink_ids: HashMap::new(),
+            hyperlink_gc_dropped: None,
+            current_link: 0,
+            clipboard_requests: Vec::new(),
+            clipboard_max_bytes: OSC52_MAX_BYTES,
+            notifications: Vec::new(),
+            bell: false,
+            colors: TerminalColors::default(),
+            cwd: None,
+            commands: Vec::new(),
+            command_gc_dropped: None,
+            current_command: 0,
+            current_zone: SemanticZone::None,
+            tab_stops: default_tab_stops(cols),
+            images: Vec::new(),
+            inactive_images: Vec::new(),
+            scrollback_dropped: 0,
+            cell_size: (8.0, 16.0),
+            kitty: KittyImageStore::default(),
+            modify_other_keys: 0,
+            keyboard_flags: Vec::new(),
+            inactive_keyboard_flags: Vec::new(),
         }
     }
 
@@ -69,27 +307,123 @@ impl TerminalGrid {
             return;
         }
         self.generation = self.generation.wrapping_add(1);
-        let mut new_cells = vec![vec![Cell::default(); cols]; rows];
-        let copy_rows = self.rows.min(rows);
-        let copy_cols = self.cols.min(cols);
-        for r in 0..copy_rows {
-            for c in 0..copy_cols {
-                new_cells[r][c] = self.cells[r][c];
-            }
-        }
+        self.cells = resized_cells(&self.cells, cols, rows);
+        self.inactive_cells = resized_cells(&self.inactive_cells, cols, rows);
+        self.line_sizes.resize(rows, LineSize::Single);
+        self.inactive_line_sizes.resize(rows, LineSize::Single);
+        // Keep stops the application set; new columns get the default ones
+        let mut tab_stops = default_tab_stops(cols);
+        let keep = cols.min(self.cols);
+        tab_stops[..keep].copy_from_slice(&self.tab_stops[..keep]);
+        self.tab_stops = tab_stops;
         self.cols = cols;
         self.rows = rows;
-        self.cells = new_cells;
         self.cursor_col = self.cursor_col.min(cols.saturating_sub(1));
         self.cursor_row = self.cursor_row.min(rows.saturating_sub(1));
         self.scroll_top = 0;
         self.scroll_bottom = rows.saturating_sub(1);
+        self.scroll_left = 0;
+        self.scroll_right = cols.saturating_sub(1);
         self.pending_wrap = false;
     }
 
+    /// Switch to the alternate screen, parking the primary screen's cells.
+    /// The alternate screen keeps whatever it last held; callers clear it
+    /// as the mode requires.
+    pub fn enter_alt_screen(&mut self) {
+        if self.alt_screen {
+            return;
+        }
+        std::mem::swap(&mut self.cells, &mut self.inactive_cells);
+        std::mem::swap(&mut self.line_sizes, &mut self.inactive_line_sizes);
+        std::mem::swap(&mut self.images, &mut self.inactive_images);
+        std::mem::swap(&mut self.keyboard_flags, &mut self.inactive_keyboard_flags);
+        self.alt_screen = true;
+        self.pending_wrap = false;
+        self.generation = self.generation.wrapping_add(1);
+    }
+
+    /// Switch back to the primary screen, restoring its cells.
+    pub fn exit_alt_screen(&mut self) {
+        if !self.alt_screen {
+            return;
+        }
+        std::mem::swap(&mut self.cells, &mut self.inactive_cells);
+        std::mem::swap(&mut self.line_sizes, &mut self.inactive_line_sizes);
+        std::mem::swap(&mut self.images, &mut self.inactive_images);
+        std::mem::swap(&mut self.keyboard_flags, &mut self.inactive_keyboard_flags);
+        self.alt_screen = false;
+        self.pending_wrap = false;
+        self.generation = self.generation.wrapping_add(1);
+    }
+
+    /// Default foreground and background, swapped in reverse video.
+    pub fn default_colors(&self) -> ([f32; 4], [f32; 4]) {
+        let (fg, bg) = (self.colors.foreground(), self.colors.background());
+        if self.reverse_video { (bg, fg) } else { (fg, bg) }
+    }
+
+    /// Full reset (RIS): back to the state of a new terminal. The scrollback
+    /// and what its cells refer to, the title and the theme are kept.
+    pub fn reset(&mut self) {
+        self.exit_alt_screen();
+        self.clear_visible_images();
+        let old = std::mem::replace(self, TerminalGrid::new(self.cols, self.rows));
+        self.scrollback = old.scrollback;
+        self.scrollback_limit = old.scrollback_limit;
+        self.scrollback_dropped = old.scrollback_dropped;
+        self.images = old.images;
+        self.hyperlinks = old.hyperlinks;
+        self.hyperlink_ids = old.hyperlink_ids;
+        self.hyperlink_gc_dropped = old.hyperlink_gc_dropped;
+        self.commands = old.commands;
+        self.command_gc_dropped = old.command_gc_dropped;
+        self.title = old.title;
+        self.cwd = old.cwd;
+        self.response_queue = old.response_queue;
+        self.clipboard_requests = old.clipboard_requests;
+        self.clipboard_max_bytes = old.clipboard_max_bytes;
+        self.notifications = old.notifications;
+        self.cell_size = old.cell_size;
+        self.colors = old.colors;
+        self.colors.reset_overrides();
+        self.generation = old.generation.wrapping_add(1);
+    }
+
+    /// Soft reset (DECSTR): attributes, margins, tab stops, palette
+    /// overrides and the cursor, mouse and keyboard modes go back to their
+    /// defaults. The screen is left alone.
+    pub fn soft_reset(&mut self) {
+        self.current_attrs = CellAttributes::default();
+        self.current_link = 0;
+        self.scroll_top = 0;
+        self.scroll_bottom = self.rows - 1;
+        self.scroll_left = 0;
+        self.scroll_right = self.cols - 1;
+        self.tab_stops = default_tab_stops(self.cols);
+        self.colors.reset_overrides();
+        self.reverse_video = false;
+        self.cursor_visible = true;
+        self.cursor_shape = None;
+        self.cursor_blinking = None;
+        self.application_cursor_keys = false;
+        self.bracketed_paste = false;
+        self.mouse_tracking = MouseTracking::Off;
+        self.mouse_encoding = MouseEncoding::Default;
+        self.modify_other_keys = 0;
+        self.keyboard_flags.clear();
+        self.inactive_keyboard_flags.clear();
+        self.pending_wrap = false;
+        self.generation = self.generation.wrapping_add(1);
+    }
+
     pub fn set_cell(&mut self, col: usize, row: usize, ch: char) {
         if row < self.rows && col < self.cols {
-            self.cells[row][col] = Cell::new(ch, self.current_attrs);
+            let mut cell = Cell::new(ch, self.current_attrs);
+            cell.link = self.current_link;
+            cell.zone = self.current_zone;
+            cell.command = self.current_command;
+            self.cells[row][col] = cell;
             self.generation = self.generation.wrapping_add(1);
         }
     }
@@ -113,6 +447,53 @@ impl TerminalGrid {
         }
     }
 
+    /// Clear whole rows as erase-in-display does, which also returns them
+    /// to single size.
+    pub fn erase_rows(&mut self, rows: std::ops::Range<usize>) {
+        for row in rows.start..rows.end.min(self.rows) {
+            self.clear_line(row);
+            self.line_sizes[row] = LineSize::Single;
+        }
+    }
+
+    /// Number of columns shown on `row`: half the screen on wide and tall lines.
+    pub fn line_cols(&self, row: usize) -> usize {
+        match self.line_sizes.get(row) {
+            Some(LineSize::Single) | None => self.cols,
+            Some(_) => (self.cols / 2).max(1),
+        }
+    }
+
+    /// Set the size of the cursor row (DECSWL/DECDWL/DECDHL), keeping the
+    /// cursor on the columns the line still shows.
+    pub fn set_line_size(&mut self, size: LineSize) {
+        let row = self.cursor_row;
+        if self.line_sizes[row] != size {
+            self.line_sizes[row] = size;
+            self.generation = self.generation.wrapping_add(1);
+        }
+        self.cursor_col = self.cursor_col.min(self.line_cols(row) - 1);
+        self.pending_wrap = false;
+    }
+
+    /// Screen alignment pattern (DECALN): fill the screen with `E`, reset
+    /// margins and line sizes, and home the cursor.
+    pub fn fill_alignment_pattern(&mut self) {
+        let fill = Cell { ch: 'E', ..Cell::default() };
+        for row in &mut self.cells {
+            row.fill(fill);
+        }
+        self.line_sizes.fill(LineSize::Single);
+        self.scroll_top = 0;
+        self.scroll_bottom = self.rows - 1;
+        self.scroll_left = 0;
+        self.scroll_right = self.cols - 1;
+        self.cursor_row = 0;
+        self.cursor_col = 0;
+        self.pending_wrap = false;
+        self.generation = self.generation.wrapping_add(1);
+    }
+
     #[allow(dead_code)]
     pub fn clear_screen(&mut self) {
         for row in 0..self.rows {
@@ -133,51 +514,77 @@ impl TerminalGrid {
         }
         let region_height = bottom - top + 1;
         let count = count.min(region_height);
+        if self.has_side_margins() {
+            self.shift_columns(top, bottom, count as isize);
+            return;
+        }
 
-        // Move scrolled-out rows to scrollback (swap in blank row, avoid clone)
+        // Move scrolled-out rows to scrollback (swap in blank row, avoid clone).
+        // The alternate screen has no scrollback: its rows are just cleared below.
         for i in 0..count {
             let row_idx = top + i;
-            if row_idx < self.rows {
+            if row_idx < self.rows && !self.alt_screen {
                 let blank = vec![Cell::default(); self.cols];
                 let row = std::mem::replace(&mut self.cells[row_idx], blank);
                 self.scrollback.push_back(row);
                 if self.scrollback.len() > self.scrollback_limit {
                     self.scrollback.pop_front();
+                    self.scrollback_dropped += 1;
                 }
             }
         }
+        let first_line = self.scrollback_dropped;
+        self.images.retain(|p| p.end_line() > first_line);
+        if self.alt_screen {
+            // Lines keep their numbers when pushed to scrollback, so only
+            // images on the alternate screen need moving
+            self.shift_images(top, bottom, -(count as i64));
+        }
 
         // Shift rows up using swap (avoids Vec allocation per row)
         for r in top..(bottom + 1 - count) {
             let src = r + count;
             if src <= bottom && src < self.rows {
                 self.cells.swap(r, src);
+                self.line_sizes.swap(r, src);
             }
         }
         // Clear newly exposed rows at bottom
         for r in (bottom + 1 - count)..(bottom + 1) {
             if r < self.rows {
                 self.clear_line(r);
+                self.line_sizes[r] = LineSize::Single;
             }
         }
     }
 
     /// Scroll down region [scroll_top..=scroll_bottom] by `count` lines
     pub fn scroll_down_region(&mut self, count: usize) {
-        self.generation = self.generation.wrapping_add(1);
         let top = self.scroll_top;
+        self.scroll_down_from(top, count);
+    }
+
+    /// Scroll rows `top..=scroll_bottom` down by `count` lines.
+    fn scroll_down_from(&mut self, top: usize, count: usize) {
+        self.generation = self.generation.wrapping_add(1);
         let bottom = self.scroll_bottom.min(self.rows - 1);
         if top > bottom {
             return;
         }
         let region_height = bottom - top + 1;
         let count = count.min(region_height);
+        if self.has_side_margins() {
+            self.shift_columns(top, bottom, -(count as isize));
+            return;
+        }
+        self.shift_images(top, bottom, count as i64);
 
         for r in (top..bottom + 1).rev() {
             let dst = r;
             let src = r.wrapping_sub(count);
             if src >= top && src <= bottom && dst < self.rows {
                 self.cells.swap(dst, src);
+                self.line_sizes.swap(dst, src);
             } else if dst >= top && dst < top + count && dst < self.rows {
                 self.clear_line(dst);
             }
@@ -186,8 +593,206 @@ impl TerminalGrid {
         for r in top..(top + count).min(bottom + 1) {
             if r < self.rows {
                 self.clear_line(r);
+                self.line_sizes[r] = LineSize::Single;
+            }
+        }
+    }
+
+    /// Whether left/right margins narrower than the screen are set.
+    fn has_side_margins(&self) -> bool {
+        self.scroll_left > 0 || self.scroll_right + 1 < self.cols
+    }
+
+    /// Scroll the cells of rows `top..=bottom` between the left and right
+    /// margins: up by `count` rows if positive, down if negative. Cells
+    /// outside the margins stay put, and nothing goes to scrollback.
+    fn shift_columns(&mut self, top: usize, bottom: usize, count: isize) {
+        let columns = self.scroll_left..self.scroll_right.min(self.cols - 1) + 1;
+        let height = (bottom - top + 1) as isize;
+        let rows: Vec<usize> = if count > 0 { (top..=bottom).collect() } else { (top..=bottom).rev().collect() };
+        for row in rows {
+            let src = row as isize + count;
+            if src >= top as isize && src < top as isize + height {
+                let (src, dst) = (src as usize, row);
+                let moved = self.cells[src][columns.clone()].to_vec();
+                self.cells[dst][columns.clone()].copy_from_slice(&moved);
+            } else {
+                self.cells[row][columns.clone()].fill(Cell::default());
             }
         }
+        self.generation = self.generation.wrapping_add(1);
+    }
+
+    /// Whether the cursor is inside the scroll region and margins, where
+    /// line and character insertion and deletion take effect.
+    fn cursor_in_margins(&self) -> bool {
+        (self.scroll_top..=self.scroll_bottom).contains(&self.cursor_row)
+            && (self.scroll_left..=self.scroll_right).contains(&self.cursor_col)
+    }
+
+    /// Insert `count` blank lines at the cursor row, pushing the rows
+    /// below it down within the scroll region (IL).
+    pub fn insert_lines(&mut self, count: usize) {
+        if self.cursor_in_margins() {
+            self.scroll_down_from(self.cursor_row, count);
+            self.cursor_col = self.scroll_left;
+            self.pending_wrap = false;
+        }
+    }
+
+    /// Delete `count` lines at the cursor row, pulling the rows below it
+    /// up within the scroll region (DL). Deleted lines are discarded.
+    pub fn delete_lines(&mut self, count: usize) {
+        if !self.cursor_in_margins() {
+            return;
+        }
+        let (top, bottom) = (self.cursor_row, self.scroll_bottom.min(self.rows - 1));
+        let count = count.min(bottom - top + 1);
+        if self.has_side_margins() {
+            self.shift_columns(top, bottom, count as isize);
+        } else {
+            self.shift_images(top, bottom, -(count as i64));
+            self.cells[top..=bottom].rotate_left(count);
+            self.line_sizes[top..=bottom].rotate_left(count);
+            for row in bottom + 1 - count..=bottom {
+                self.clear_line(row);
+                self.line_sizes[row] = LineSize::Single;
+            }
+        }
+        self.cursor_col = self.scroll_left;
+        self.pending_wrap = false;
+        self.generation = self.generation.wrapping_add(1);
+    }
+
+    /// Insert `count` blank cells at the cursor, shifting the rest of the
+    /// line up to the right margin over (ICH).
+    pub fn insert_chars(&mut self, count: usize) {
+        if !(self.scroll_left..=self.scroll_right).contains(&self.cursor_col) {
+            return;
+        }
+        let (col, end) = (self.cursor_col, self.scroll_right.min(self.cols - 1) + 1);
+        let count = count.min(end - col);
+        let row = &mut self.cells[self.cursor_row];
+        row[col..end].rotate_right(count);
+        row[col..col + count].fill(Cell::default());
+        self.pending_wrap = false;
+        self.generation = self.generation.wrapping_add(1);
+    }
+
+    /// Delete `count` cells at the cursor, pulling the rest of the line up
+    /// to the right margin back (DCH).
+    pub fn delete_chars(&mut self, count: usize) {
+        if !(self.scroll_left..=self.scroll_right).contains(&self.cursor_col) {
+            return;
+        }
+        let (col, end) = (self.cursor_col, self.scroll_right.min(self.cols - 1) + 1);
+        let count = count.min(end - col);
+        let row = &mut self.cells[self.cursor_row];
+        row[col..end].rotate_left(count);
+        row[end - count..end].fill(Cell::default());
+        self.pending_wrap = false;
+        self.generation = self.generation.wrapping_add(1);
+    }
+
+    /// Move images whose top row lies in visible rows `top..=bottom` by
+    /// `delta` rows, dropping those pushed out of that range.
+    fn shift_images(&mut self, top: usize, bottom: usize, delta: i64) {
+        let top_line = self.line_of_abs_row(self.scrollback.len() + top) as i64;
+        let bottom_line = self.line_of_abs_row(self.scrollback.len() + bottom) as i64;
+        self.images.retain_mut(|p| {
+            let line = p.line as i64;
+            if line < top_line || line > bottom_line {
+                return true;
+            }
+            let moved = line + delta;
+            p.line = moved.max(0) as u64;
+            (top_line..=bottom_line).contains(&moved)
+        });
+    }
+
+    /// Stable number of the line at `abs_row`; unlike the absolute row it
+    /// does not change when old scrollback is dropped.
+    pub fn line_of_abs_row(&self, abs_row: usize) -> u64 {
+        self.scrollback_dropped + abs_row as u64
+    }
+
+    /// Absolute row of a line number, or `None` if it has left scrollback.
+    pub fn abs_row_of_line(&self, line: u64) -> Option<usize> {
+        line.checked_sub(self.scrollback_dropped).map(|row| row as usize)
+    }
+
+    /// Number of cells an image shown at the given pixel size covers.
+    pub fn image_cell_extent(&self, width: f32, height: f32) -> (usize, usize) {
+        let (cell_w, cell_h) = self.cell_size;
+        let cols = (width / cell_w.max(1.0)).ceil() as usize;
+        let rows = (height / cell_h.max(1.0)).ceil() as usize;
+        (cols.max(1), rows.max(1))
+    }
+
+    /// Line number of the cursor row.
+    pub fn cursor_line(&self) -> u64 {
+        self.line_of_abs_row(self.scrollback.len() + self.cursor_row)
+    }
+
+    /// Place an image, shown at `size` pixels, with its top-left corner at
+    /// the cursor. Images it completely covers are removed. The cursor does
+    /// not move. Returns the cells the image covers.
+    pub fn place_image(&mut self, image: Arc<ImageData>, size: (f32, f32)) -> (usize, usize) {
+        let (cols, rows) = self.image_cell_extent(size.0, size.1);
+        let mut placement = ImagePlacement::new(image, self.cursor_line(), self.cursor_col, cols, rows);
+        placement.size = size;
+        self.images.retain(|p| {
+            !(p.line >= placement.line
+                && p.end_line() <= placement.end_line()
+                && p.col >= placement.col
+                && p.col + p.cols <= placement.col + placement.cols)
+        });
+        self.add_image(placement);
+        (cols, rows)
+    }
+
+    /// Add a placement as is, keeping within the image memory budget.
+    pub fn add_image(&mut self, placement: ImagePlacement) {
+        self.images.push(placement);
+
+        // Enforce the memory budget, oldest first
+        let mut total: usize = self.images.iter().chain(&self.inactive_images).map(|p| p.image.byte_size()).sum();
+        while total > IMAGE_MEMORY_LIMIT && self.images.len() > 1 {
+            total -= self.images.remove(0).image.byte_size();
+        }
+        self.generation = self.generation.wrapping_add(1);
+    }
+
+    /// Remove the placements on the active screen that match `remove`,
+    /// returning them.
+    pub fn remove_images(&mut self, mut remove: impl FnMut(&ImagePlacement) -> bool) -> Vec<ImagePlacement> {
+        let (removed, kept) = std::mem::take(&mut self.images).into_iter().partition(|p| remove(p));
+        self.images = kept;
+        if !removed.is_empty() {
+            self.generation = self.generation.wrapping_add(1);
+        }
+        removed
+    }
+
+    /// Whether a placement on either screen shows the kitty image `id`.
+    pub fn kitty_image_in_use(&self, id: u32) -> bool {
+        self.images.iter().chain(&self.inactive_images).any(|p| p.kitty_id == id)
+    }
+
+    /// Remove images whose top row is on the visible screen (ED 2).
+    pub fn clear_visible_images(&mut self) {
+        let first_visible = self.line_of_abs_row(self.scrollback.len());
+        self.images.retain(|p| p.line < first_visible);
+        self.generation = self.generation.wrapping_add(1);
+    }
+
+    /// Drop all scrollback rows and the images anchored in them (ED 3).
+    pub fn clear_scrollback(&mut self) {
+        self.scrollback_dropped += self.scrollback.len() as u64;
+        self.scrollback.clear();
+        let first_line = self.scrollback_dropped;
+        self.images.retain(|p| p.line >= first_line);
+        self.generation = self.generation.wrapping_add(1);
     }
 
     pub fn newline(&mut self) {
@@ -199,8 +804,9 @@ impl TerminalGrid {
         }
     }
 
+    /// Move to the left margin, or to column 0 from left of it.
     pub fn carriage_return(&mut self) {
-        self.cursor_col = 0;
+        self.cursor_col = if self.cursor_col >= self.scroll_left { self.scroll_left } else { 0 };
         self.pending_wrap = false;
     }
 
@@ -211,7 +817,9 @@ impl TerminalGrid {
     /// Advance the cursor by `width` columns (1 for normal chars, 2 for wide chars).
     pub fn advance_cursor_by_width(&mut self, width: usize) {
         let next_col = self.cursor_col + width;
-        if next_col < self.cols {
+        // Wrap at the right margin, unless the cursor is already past it
+        let edge = if self.cursor_col <= self.scroll_right { self.scroll_right + 1 } else { self.cols };
+        if next_col < edge.min(self.line_cols(self.cursor_row)) {
             self.cursor_col = next_col;
             self.pending_wrap = false;
         } else {
@@ -220,6 +828,100 @@ impl TerminalGrid {
         }
     }
 
+    /// Save the title (XTWINOPS 22). The oldest is dropped past the limit.
+    pub fn push_title(&mut self) {
+        if self.title_stack.len() == TITLE_STACK_LIMIT {
+            self.title_stack.remove(0);
+        }
+        self.title_stack.push(self.title.clone());
+    }
+
+    /// Restore the last saved title (XTWINOPS 23), if any.
+    pub fn pop_title(&mut self) {
+        if let Some(title) = self.title_stack.pop() {
+            self.title = title;
+        }
+    }
+
+    /// Set a tab stop at the cursor column (HTS).
+    pub fn set_tab_stop(&mut self) {
+        if let Some(stop) = self.tab_stops.get_mut(self.cursor_col) {
+            *stop = true;
+        }
+    }
+
+    /// Clear the tab stop at the cursor column (TBC 0).
+    pub fn clear_tab_stop(&mut self) {
+        if let Some(stop) = self.tab_stops.get_mut(self.cursor_col) {
+            *stop = false;
+        }
+    }
+
+    /// Clear every tab stop (TBC 3).
+    pub fn clear_all_tab_stops(&mut self) {
+        self.tab_stops.fill(false);
+    }
+
+    /// Move the cursor forward `count` tab stops (HT / CHT), stopping at
+    /// the right margin when there are no more.
+    pub fn tab_forward(&mut self, count: usize) {
+        let last = if self.cursor_col <= self.scroll_right { self.scroll_right } else { self.cols.saturating_sub(1) };
+        for _ in 0..count {
+            self.cursor_col = (self.cursor_col + 1..last)
+                .find(|&c| self.tab_stops[c])
+                .unwrap_or(last);
+        }
+        self.pending_wrap = false;
+    }
+
+    /// Move the cursor back `count` tab stops (CBT), stopping at column 0.
+    pub fn tab_backward(&mut self, count: usize) {
+        for _ in 0..count {
+            self.cursor_col = (1..self.cursor_col)
+                .rev()
+                .find(|&c| self.tab_stops[c])
+                .unwrap_or(0);
+        }
+        self.pending_wrap = false;
+    }
+
+    /// Kitty keyboard flags in effect for the active screen.
+    pub fn keyboard_flags(&self) -> KeyboardFlags {
+        self.keyboard_flags.last().copied().unwrap_or_default()
+    }
+
+    /// `CSI > flags u`: push a flag set.
+    pub fn push_keyboard_flags(&mut self, flags: u8) {
+        if self.keyboard_flags.len() >= KEYBOARD_STACK_LIMIT {
+            self.keyboard_flags.remove(0);
+        }
+        self.keyboard_flags.push(KeyboardFlags(flags & KeyboardFlags::ALL));
+    }
+
+    /// `CSI < n u`: pop `n` flag sets. Popping more than were pushed
+    /// empties the stack, which turns the protocol off.
+    pub fn pop_keyboard_flags(&mut self, count: usize) {
+        let keep = self.keyboard_flags.len().saturating_sub(count);
+        self.keyboard_flags.truncate(keep);
+    }
+
+    /// `CSI = flags ; mode u`: change the flags in effect. Mode 1 replaces
+    /// them, 2 sets the given bits and 3 clears them.
+    pub fn set_keyboard_flags(&mut self, flags: u8, mode: u16) {
+        let flags = flags & KeyboardFlags::ALL;
+        let current = self.keyboard_flags().0;
+        let new = match mode {
+            1 => flags,
+            2 => current | flags,
+            3 => current & !flags,
+            _ => return,
+        };
+        match self.keyboard_flags.last_mut() {
+            Some(top) => *top = KeyboardFlags(new),
+            None => self.keyboard_flags.push(KeyboardFlags(new)),
+        }
+    }
+
     #[allow(dead_code)]
     pub fn total_rows(&self) -> usize {
         self.scrollback.len() + self.rows
@@ -293,9 +995,14 @@ impl TerminalGrid {
     }
 
     /// Return the range of user input around the cursor, spanning multiple lines
-    /// if needed. Scans upward from the cursor row to find the prompt marker,
-    /// then selects from prompt end to the last non-empty column on the cursor row.
+    /// if needed. When the shell marks its input with OSC 133 the marked cells
+    /// are used; otherwise scans upward from the cursor row to find the prompt
+    /// marker, then selects from prompt end to the last non-empty column on the
+    /// cursor row.
     pub fn cursor_line_input_range(&self) -> Option<(usize, usize, usize, usize)> {
+        if let Some(command) = self.cursor_row_command() {
+            return self.marked_input_range(command);
+        }
         let slen = self.scrollback.len();
         // Find the prompt row by scanning upward from cursor_row
         let mut prompt_row = self.cursor_row;
@@ -318,6 +1025,109 @@ impl TerminalGrid {
         Some((slen + prompt_row, prompt_col, slen + self.cursor_row, end_col))
     }
 
+    /// Command the cursor row belongs to according to OSC 133 marks,
+    /// preferring the cells up to the cursor.
+    fn cursor_row_command(&self) -> Option<u32> {
+        let row = &self.cells[self.cursor_row];
+        let split = (self.cursor_col + 1).min(row.len());
+        let (before, after) = row.split_at(split);
+        before
+            .iter()
+            .rev()
+            .chain(after)
+            .find(|c| c.zone != SemanticZone::None && c.command != 0)
+            .map(|c| c.command)
+    }
+
+    /// Range of the non-blank input cells of `command`. Scans upward from
+    /// the cursor until the rows stop belonging to the command, so input
+    /// wrapped into scrollback is included.
+    fn marked_input_range(&self, command: u32) -> Option<(usize, usize, usize, usize)> {
+        let mut start: Option<(usize, usize)> = None;
+        let mut end: Option<(usize, usize)> = None;
+        let mut seen = false;
+        for abs_row in (0..=self.scrollback.len() + self.cursor_row).rev() {
+            let Some(row) = self.abs_row_cells(abs_row) else { continue };
+            if !row.iter().any(|c| c.command == command) {
+                if seen {
+                    break;
+                }
+                continue;
+            }
+            seen = true;
+            let mut input = row
+                .iter()
+                .enumerate()
+                .filter(|(_, c)| c.command == command && c.zone == SemanticZone::Input && !c.is_empty())
+                .map(|(col, _)| col);
+            if let Some(first) = input.next() {
+                start = Some((abs_row, first));
+                end = end.or(Some((abs_row, input.next_back().unwrap_or(first))));
+            }
+        }
+        let (start, end) = (start?, end?);
+        Some((start.0, start.1, end.0, end.1))
+    }
+
+    /// Start a new shell command at an OSC 133 `A` mark.
+    pub fn begin_shell_command(&mut self) {
+        self.current_zone = SemanticZone::Prompt;
+        if self.commands.len() >= COMMAND_LIMIT {
+            // Scanning again only helps once rows have left scrollback
+            if self.command_gc_dropped != Some(self.scrollback_dropped) {
+                self.compact_commands();
+            }
+            if self.commands.len() >= COMMAND_LIMIT {
+                self.current_command = 0;
+                return;
+            }
+        }
+        self.commands.push(ShellCommand::default());
+        self.current_command = self.commands.len() as u32;
+    }
+
+    /// Drop commands no cell belongs to and renumber the rest, rewriting
+    /// `Cell::command` on both screens and in scrollback.
+    fn compact_commands(&mut self) {
+        self.command_gc_dropped = Some(self.scrollback_dropped);
+        let mut used = vec![false; self.commands.len() + 1];
+        used[self.current_command as usize] = true;
+        let rows = self.scrollback.iter().chain(&self.cells).chain(&self.inactive_cells);
+        for cell in rows.flatten() {
+            used[cell.command as usize] = true;
+        }
+
+        let mut remap = vec![0u32; used.len()];
+        let old = std::mem::take(&mut self.commands);
+        for (i, command) in old.into_iter().enumerate() {
+            if used[i + 1] {
+                self.commands.push(command);
+                remap[i + 1] = self.commands.len() as u32;
+            }
+        }
+        if self.commands.len() == remap.len() - 1 {
+            return;
+        }
+
+        let rows = self.scrollback.iter_mut().chain(&mut self.cells).chain(&mut self.inactive_cells);
+        for cell in rows.flatten() {
+            cell.command = remap[cell.command as usize];
+        }
+        self.current_command = remap[self.current_command as usize];
+        self.generation = self.generation.wrapping_add(1);
+    }
+
+    /// Finish the current shell command at an OSC 133 `D` mark.
+    pub fn finish_shell_command(&mut self, exit_code: Option<i32>) {
+        if let Some(command) = (self.current_command as usize)
+            .checked_sub(1)
+            .and_then(|i| self.commands.get_mut(i))
+        {
+            command.exit_code = exit_code;
+        }
+        self.current_zone = SemanticZone::None;
+    }
+
     /// Detect prompt end column on a specific visible row.
     fn prompt_end_col_for_row(&self, row_idx: usize) -> Option<usize> {
         let row = &self.cells[row_idx];
@@ -370,6 +1180,119 @@ impl TerminalGrid {
         Some((first, 0, last, end_col))
     }
 
+    /// Return the link value for an OSC 8 hyperlink, reusing the existing
+    /// entry when the same target was seen before.
+    pub fn intern_hyperlink(&mut self, id: Option<&str>, uri: &str) -> u32 {
+        let link = Hyperlink { id: id.map(str::to_string), uri: uri.to_string() };
+        if let Some(&value) = self.hyperlink_ids.get(&link) {
+            return value;
+        }
+        if self.hyperlinks.len() >= HYPERLINK_LIMIT {
+            // Scanning again only helps once rows have left scrollback
+            if self.hyperlink_gc_dropped != Some(self.scrollback_dropped) {
+                self.compact_hyperlinks();
+            }
+            if self.hyperlinks.len() >= HYPERLINK_LIMIT {
+                return 0;
+            }
+        }
+        self.hyperlinks.push(link.clone());
+        let value = self.hyperlinks.len() as u32;
+        self.hyperlink_ids.insert(link, value);
+        value
+    }
+
+    /// Drop hyperlinks no cell refers to and renumber the rest, rewriting
+    /// `Cell::link` on both screens and in scrollback.
+    fn compact_hyperlinks(&mut self) {
+        self.hyperlink_gc_dropped = Some(self.scrollback_dropped);
+        let mut used = vec![false; self.hyperlinks.len() + 1];
+        used[self.current_link as usize] = true;
+        let rows = self.scrollback.iter().chain(&self.cells).chain(&self.inactive_cells);
+        for cell in rows.flatten() {
+            used[cell.link as usize] = true;
+        }
+
+        let mut remap = vec![0u32; used.len()];
+        let old = std::mem::take(&mut self.hyperlinks);
+        self.hyperlink_ids.clear();
+        for (i, link) in old.into_iter().enumerate() {
+            if used[i + 1] {
+                self.hyperlinks.push(link.clone());
+                remap[i + 1] = self.hyperlinks.len() as u32;
+                self.hyperlink_ids.insert(link, remap[i + 1]);
+            }
+        }
+        if self.hyperlinks.len() == remap.len() - 1 {
+            return;
+        }
+
+        let rows = self.scrollback.iter_mut().chain(&mut self.cells).chain(&mut self.inactive_cells);
+        for cell in rows.flatten() {
+            cell.link = remap[cell.link as usize];
+        }
+        self.current_link = remap[self.current_link as usize];
+        self.generation = self.generation.wrapping_add(1);
+    }
+
+    /// DECDWL/DECDHL size of a row in absolute row space. Scrollback keeps
+    /// no line sizes, so its rows are single.
+    pub fn abs_row_line_size(&self, abs_row: usize) -> LineSize {
+        abs_row
+            .checked_sub(self.scrollback.len())
+            .and_then(|row| self.line_sizes.get(row).copied())
+            .unwrap_or(LineSize::Single)
+    }
+
+    /// Cells of a row in absolute row space (scrollback, then visible rows).
+    pub fn abs_row_cells(&self, abs_row: usize) -> Option<&[Cell]> {
+        let slen = self.scrollback.len();
+        if abs_row < slen {
+            Some(&self.scrollback[abs_row])
+        } else {
+            self.cells.get(abs_row - slen).map(|r| r.as_slice())
+        }
+    }
+
+    /// Return the extent of the OSC 8 hyperlink under (abs_row, col) as
+    /// (start_row, start_col, end_row, end_col) with an inclusive end, plus
+    /// its URI. The range follows the link across wrapped rows.
+    pub fn hyperlink_range(&self, abs_row: usize, col: usize) -> Option<(usize, usize, usize, usize, &str)> {
+        let link = self.abs_row_cells(abs_row)?.get(col)?.link;
+        let uri = self.hyperlinks.get((link as usize).checked_sub(1)?)?.uri.as_str();
+        let link_at = |r: usize, c: usize| self.abs_row_cells(r).and_then(|row| row.get(c)).map(|cell| cell.link);
+
+        let (mut start_row, mut start_col) = (abs_row, col);
+        loop {
+            let prev = if start_col > 0 {
+                Some((start_row, start_col - 1))
+            } else if start_row > 0 {
+                self.abs_row_cells(start_row - 1)
+                    .and_then(|row| row.len().checked_sub(1))
+                    .map(|c| (start_row - 1, c))
+            } else {
+                None
+            };
+            match prev {
+                Some((r, c)) if link_at(r, c) == Some(link) => (start_row, start_col) = (r, c),
+                _ => break,
+            }
+        }
+
+        let (mut end_row, mut end_col) = (abs_row, col);
+        loop {
+            let row_len = self.abs_row_cells(end_row).map_or(0, |row| row.len());
+            let next = if end_col + 1 < row_len { (end_row, end_col + 1) } else { (end_row + 1, 0) };
+            if link_at(next.0, next.1) == Some(link) {
+                (end_row, end_col) = next;
+            } else {
+                break;
+            }
+        }
+
+        Some((start_row, start_col, end_row, end_col, uri))
+    }
+
     /// Extract text for a selection range.
     /// Coordinates use absolute row indexing:
     ///   abs_row 0..scrollback.len()         → scrollback rows
@@ -408,6 +1331,21 @@ impl TerminalGrid {
     }
 }
 
+fn default_tab_stops(cols: usize) -> Vec<bool> {
+    (0..cols).map(|c| c > 0 && c % 8 == 0).collect()
+}
+
+/// Copy `cells` into a new `cols` x `rows` buffer, truncating or padding
+/// with blank cells.
+fn resized_cells(cells: &[Vec<Cell>], cols: usize, rows: usize) -> Vec<Vec<Cell>> {
+    let mut new_cells = vec![vec![Cell::default(); cols]; rows];
+    for (new_row, old_row) in new_cells.iter_mut().zip(cells) {
+        let copy_cols = cols.min(old_row.len());
+        new_row[..copy_cols].copy_from_slice(&old_row[..copy_cols]);
+    }
+    new_cells
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -827,4 +1765,267 @@ mod tests {
         // Should start at row 0 col 4 (after "% "), end at row 1 col 6 ("world" ends at 6)
         assert_eq!(range, Some((0, 4, 1, 6)));
     }
+
+    #[test]
+    fn intern_hyperlink_reuses_entries() {
+        let mut g = TerminalGrid::new(10, 2);
+        let a = g.intern_hyperlink(None, "https://a.example");
+        let b = g.intern_hyperlink(Some("x"), "https://a.example");
+        assert_ne!(a, 0);
+        assert_ne!(a, b);
+        assert_eq!(g.intern_hyperlink(None, "https://a.example"), a);
+        assert_eq!(g.hyperlinks.len(), 2);
+    }
+
+    #[test]
+    fn hyperlinks_dropped_with_old_scrollback() {
+        let mut g = TerminalGrid::new(4, 2);
+        g.scrollback_limit = 2;
+        // Parked on the alternate screen so scrolling leaves it alone
+        g.current_link = g.intern_hyperlink(None, "https://kept.example");
+        g.enter_alt_screen();
+        g.set_cell(0, 0, 'k');
+        g.exit_alt_screen();
+        for i in 1..HYPERLINK_LIMIT {
+            g.current_link = g.intern_hyperlink(None, &format!("https://{i}.example"));
+            g.set_cell(0, 0, 'x');
+            g.scroll_up_region(1);
+        }
+        g.current_link = 0;
+        assert_eq!(g.hyperlinks.len(), HYPERLINK_LIMIT);
+
+        let link = g.intern_hyperlink(None, "https://new.example");
+        assert!(g.hyperlinks.len() < HYPERLINK_LIMIT);
+        assert_eq!(g.hyperlinks[link as usize - 1].uri, "https://new.example");
+        g.enter_alt_screen();
+        let kept = g.cells[0][0].link;
+        assert_eq!(g.hyperlinks[kept as usize - 1].uri, "https://kept.example");
+    }
+
+    #[test]
+    fn hyperlink_range_single_row() {
+        let mut g = TerminalGrid::new(10, 2);
+        g.current_link = g.intern_hyperlink(None, "file:///tmp");
+        for (i, ch) in "tmp".chars().enumerate() {
+            g.set_cell(2 + i, 0, ch);
+        }
+        assert_eq!(g.hyperlink_range(0, 3), Some((0, 2, 0, 4, "file:///tmp")));
+        assert_eq!(g.hyperlink_range(0, 5), None);
+    }
+
+    #[test]
+    fn hyperlink_range_follows_wrapped_rows() {
+        let mut g = TerminalGrid::new(4, 3);
+        g.current_link = g.intern_hyperlink(None, "https://example.com");
+        g.set_cell(2, 0, 'a');
+        g.set_cell(3, 0, 'b');
+        g.set_cell(0, 1, 'c');
+        g.current_link = 0;
+        g.set_cell(1, 1, ' ');
+        assert_eq!(g.hyperlink_range(1, 0), Some((0, 2, 1, 0, "https://example.com")));
+        assert_eq!(g.hyperlink_range(0, 2), Some((0, 2, 1, 0, "https://example.com")));
+    }
+
+    #[test]
+    fn hyperlink_range_in_scrollback() {
+        let mut g = TerminalGrid::new(4, 1);
+        g.current_link = g.intern_hyperlink(None, "https://example.com");
+        g.set_cell(0, 0, 'a');
+        g.scroll_up_region(1);
+        assert_eq!(g.hyperlink_range(0, 0), Some((0, 0, 0, 0, "https://example.com")));
+        assert_eq!(g.hyperlink_range(1, 0), None);
+    }
+
+    fn print(g: &mut TerminalGrid, text: &str) {
+        for ch in text.chars() {
+            if g.cursor_col == g.cols {
+                g.cursor_col = 0;
+                g.newline();
+            }
+            g.set_cell(g.cursor_col, g.cursor_row, ch);
+            g.cursor_col += 1;
+        }
+    }
+
+    #[test]
+    fn marked_input_range_spans_wrapped_rows_into_scrollback() {
+        let mut g = TerminalGrid::new(6, 2);
+        g.begin_shell_command();
+        print(&mut g, "> ");
+        g.current_zone = SemanticZone::Input;
+        print(&mut g, "echo hello x");
+        // "> echo" has scrolled off into scrollback
+        assert_eq!(g.scrollback.len(), 1);
+        assert_eq!(g.cursor_line_input_range(), Some((0, 2, 2, 1)));
+    }
+
+    #[test]
+    fn marked_input_range_empty_input() {
+        let mut g = TerminalGrid::new(10, 2);
+        g.begin_shell_command();
+        print(&mut g, "> ");
+        g.current_zone = SemanticZone::Input;
+        assert_eq!(g.cursor_line_input_range(), None);
+    }
+
+    #[test]
+    fn marked_input_range_skips_previous_command() {
+        let mut g = TerminalGrid::new(10, 3);
+        g.begin_shell_command();
+        print(&mut g, "> ");
+        g.current_zone = SemanticZone::Input;
+        print(&mut g, "ls");
+        g.finish_shell_command(Some(0));
+        g.cursor_col = 0;
+        g.cursor_row = 1;
+        g.begin_shell_command();
+        print(&mut g, "> ");
+        g.current_zone = SemanticZone::Input;
+        print(&mut g, "pwd");
+        assert_eq!(g.cursor_line_input_range(), Some((1, 2, 1, 4)));
+        assert_eq!(g.commands[0].exit_code, Some(0));
+    }
+
+    #[test]
+    fn marked_prompt_row_has_no_input() {
+        // The heuristic would take "ls" after "% " for input
+        let mut g = TerminalGrid::new(10, 2);
+        g.begin_shell_command();
+        print(&mut g, "~ % ls");
+        assert_eq!(g.cursor_line_input_range(), None);
+    }
+
+    #[test]
+    fn marked_output_row_uses_its_command_input() {
+        let mut g = TerminalGrid::new(10, 3);
+        g.begin_shell_command();
+        print(&mut g, "> ");
+        g.current_zone = SemanticZone::Input;
+        print(&mut g, "ls");
+        g.newline();
+        g.cursor_col = 0;
+        g.current_zone = SemanticZone::Output;
+        print(&mut g, "% x");
+        assert_eq!(g.cursor_line_input_range(), Some((0, 2, 0, 3)));
+    }
+
+    #[test]
+    fn unreferenced_commands_dropped_when_table_fills() {
+        let mut g = TerminalGrid::new(10, 2);
+        g.begin_shell_command();
+        print(&mut g, "> ");
+        for _ in 1..COMMAND_LIMIT {
+            g.begin_shell_command();
+        }
+        assert_eq!(g.commands.len(), COMMAND_LIMIT);
+        g.begin_shell_command();
+        // The first command (still on screen) and the current one remain
+        assert_eq!(g.commands.len(), 3);
+        assert_eq!(g.cells[0][0].command, 1);
+        assert_eq!(g.current_command, 3);
+    }
+
+    #[test]
+    fn tab_stops_survive_resize() {
+        let mut g = TerminalGrid::new(20, 2);
+        g.clear_all_tab_stops();
+        g.cursor_col = 5;
+        g.set_tab_stop();
+        g.resize(10, 2);
+        g.resize(30, 2);
+        g.cursor_col = 0;
+        g.tab_forward(1);
+        assert_eq!(g.cursor_col, 5);
+        // Columns added by the resize get the default stops
+        g.tab_forward(1);
+        assert_eq!(g.cursor_col, 16);
+    }
+
+    fn test_image(width: u32, height: u32) -> Arc<ImageData> {
+        Arc::new(ImageData::new(width, height, vec![0; (width * height * 4) as usize]))
+    }
+
+    #[test]
+    fn images_dropped_with_old_scrollback() {
+        let mut g = TerminalGrid::new(4, 2);
+        g.scrollback_limit = 2;
+        g.place_image(test_image(1, 1), (1.0, 1.0));
+        for _ in 0..2 {
+            g.scroll_up_region(1);
+        }
+        assert_eq!(g.abs_row_of_line(g.images[0].line), Some(0));
+        g.scroll_up_region(1);
+        assert!(g.images.is_empty());
+    }
+
+    #[test]
+    fn alt_screen_images_scroll_without_scrollback() {
+        let mut g = TerminalGrid::new(4, 3);
+        g.place_image(test_image(1, 1), (1.0, 1.0));
+        g.enter_alt_screen();
+        assert!(g.images.is_empty());
+        g.cursor_row = 2;
+        g.place_image(test_image(1, 1), (1.0, 1.0));
+        g.scroll_up_region(1);
+        assert_eq!(g.abs_row_of_line(g.images[0].line), Some(1));
+        g.scroll_up_region(2);
+        assert!(g.images.is_empty());
+        g.exit_alt_screen();
+        assert_eq!(g.images.len(), 1);
+    }
+
+    #[test]
+    fn scroll_down_moves_images() {
+        let mut g = TerminalGrid::new(4, 3);
+        g.place_image(test_image(1, 1), (1.0, 1.0));
+        g.scroll_down_region(1);
+        assert_eq!(g.abs_row_of_line(g.images[0].line), Some(1));
+    }
+
+    #[test]
+    fn clear_scrollback_keeps_visible_lines_stable() {
+        let mut g = TerminalGrid::new(4, 2);
+        g.scroll_up_region(1);
+        g.cursor_row = 1;
+        g.place_image(test_image(1, 1), (1.0, 1.0));
+        g.clear_scrollback();
+        assert_eq!(g.abs_row_of_line(g.images[0].line), Some(1));
+    }
+
+    #[test]
+    fn new_image_replaces_covered_one() {
+        let mut g = TerminalGrid::new(8, 4);
+        g.place_image(test_image(1, 1), (1.0, 1.0));
+        g.place_image(test_image(4, 4), (16.0, 32.0));
+        assert_eq!(g.images.len(), 1);
+        assert_eq!(g.images[0].cols, 2);
+    }
+
+    #[test]
+    fn keyboard_flag_stack() {
+        let mut g = TerminalGrid::new(4, 2);
+        assert!(g.keyboard_flags().is_empty());
+        g.push_keyboard_flags(1);
+        g.push_keyboard_flags(0xff);
+        assert_eq!(g.keyboard_flags(), KeyboardFlags(31));
+        g.set_keyboard_flags(8, 3);
+        assert_eq!(g.keyboard_flags(), KeyboardFlags(23));
+        g.pop_keyboard_flags(1);
+        assert_eq!(g.keyboard_flags(), KeyboardFlags(1));
+        g.pop_keyboard_flags(5);
+        assert!(g.keyboard_flags().is_empty());
+    }
+
+    #[test]
+    fn keyboard_flags_are_per_screen() {
+        let mut g = TerminalGrid::new(4, 2);
+        g.push_keyboard_flags(1);
+        g.enter_alt_screen();
+        assert!(g.keyboard_flags().is_empty());
+        g.set_keyboard_flags(3, 1);
+        g.exit_alt_screen();
+        assert_eq!(g.keyboard_flags(), KeyboardFlags(1));
+        g.enter_alt_screen();
+        assert_eq!(g.keyboard_flags(), KeyboardFlags(3));
+    }
 }
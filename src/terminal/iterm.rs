@@ -0,0 +1,219 @@
+use super::graphics::ImageData;
+use super::grid::TerminalGrid;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use std::io::Cursor;
+use std::sync::Arc;
+
+/// Largest image side in pixels, as for sixel.
+const MAX_IMAGE_DIMENSION: u32 = 4096;
+
+/// Most memory decoding one image may take, with room for 16-bit
+/// channels. Also bounds the size of the file itself.
+const MAX_DECODE_BYTES: usize = MAX_IMAGE_DIMENSION as usize * MAX_IMAGE_DIMENSION as usize * 8;
+
+/// Width or height argument of an inline image.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Dimension {
+    Auto,
+    Cells(f32),
+    Pixels(f32),
+    /// Percent of the terminal's width or height
+    Percent(f32),
+}
+
+impl Dimension {
+    fn parse(value: &str) -> Self {
+        let number = |v: &str| v.parse::<f32>().ok().filter(|n| n.is_finite() && *n > 0.0);
+        if let Some(px) = value.strip_suffix("px") {
+            number(px).map_or(Dimension::Auto, Dimension::Pixels)
+        } else if let Some(percent) = value.strip_suffix('%') {
+            number(percent).map_or(Dimension::Auto, Dimension::Percent)
+        } else {
+            number(value).map_or(Dimension::Auto, Dimension::Cells)
+        }
+    }
+
+    /// Size in pixels, given the cell size and the terminal size along the
+    /// same axis. `None` for `auto`.
+    fn to_pixels(self, cell: f32, total: f32) -> Option<f32> {
+        match self {
+            Dimension::Auto => None,
+            Dimension::Cells(n) => Some(n * cell),
+            Dimension::Pixels(n) => Some(n),
+            Dimension::Percent(p) => Some(total * p / 100.0),
+        }
+    }
+}
+
+/// An image from `OSC 1337 ; File=[args]:base64 ST`.
+#[derive(Debug)]
+struct InlineImage {
+    image: ImageData,
+    width: Dimension,
+    height: Dimension,
+    preserve_aspect_ratio: bool,
+}
+
+/// Parse the text after `File=`. Returns `None` for files that are not
+/// meant to be shown inline (downloads) or can't be decoded.
+fn parse_file(data: &[u8]) -> Option<InlineImage> {
+    let colon = data.iter().position(|&b| b == b':')?;
+    let (args, payload) = (std::str::from_utf8(&data[..colon]).ok()?, &data[colon + 1..]);
+
+    let mut inline = false;
+    let mut width = Dimension::Auto;
+    let mut height = Dimension::Auto;
+    let mut preserve_aspect_ratio = true;
+    for arg in args.split(';') {
+        let Some((key, value)) = arg.split_once('=') else {
+            continue;
+        };
+        match key {
+            "inline" => inline = value == "1",
+            "width" => width = Dimension::parse(value),
+            "height" => height = Dimension::parse(value),
+            "preserveAspectRatio" => preserve_aspect_ratio = value != "0",
+            _ => {}
+        }
+    }
+    if !inline {
+        return None;
+    }
+
+    let payload = payload.trim_ascii();
+    if payload.len() / 4 * 3 > MAX_DECODE_BYTES {
+        return None;
+    }
+    let bytes = STANDARD.decode(payload).ok()?;
+    // The limits are checked against the header, before decoding
+    let mut limits = image::Limits::default();
+    limits.max_image_width = Some(MAX_IMAGE_DIMENSION);
+    limits.max_image_height = Some(MAX_IMAGE_DIMENSION);
+    limits.max_alloc = Some(MAX_DECODE_BYTES as u64);
+    let mut reader = image::ImageReader::new(Cursor::new(&bytes)).with_guessed_format().ok()?;
+    reader.limits(limits);
+    let decoded = reader.decode().ok()?.to_rgba8();
+    let image = ImageData::new(decoded.width(), decoded.height(), decoded.into_raw());
+    Some(InlineImage { image, width, height, preserve_aspect_ratio })
+}
+
+impl InlineImage {
+    /// Size to show the image at, in pixels. Images without an explicit
+    /// width are shrunk to fit the terminal width.
+    fn display_size(&self, cell_size: (f32, f32), screen: (f32, f32)) -> (f32, f32) {
+        let natural = (self.image.width as f32, self.image.height as f32);
+        let width = self.width.to_pixels(cell_size.0, screen.0);
+        let height = self.height.to_pixels(cell_size.1, screen.1);
+        let keep = self.preserve_aspect_ratio;
+        let (w, h) = match (width, height) {
+            (None, None) => {
+                let scale = (screen.0 / natural.0).min(1.0);
+                (natural.0 * scale, natural.1 * scale)
+            }
+            (Some(w), None) if keep => (w, w * natural.1 / natural.0),
+            (None, Some(h)) if keep => (h * natural.0 / natural.1, h),
+            // Fit inside the box, keeping the aspect ratio
+            (Some(w), Some(h)) if keep => {
+                let scale = (w / natural.0).min(h / natural.1);
+                (natural.0 * scale, natural.1 * scale)
+            }
+            (w, h) => (w.unwrap_or(natural.0), h.unwrap_or(natural.1)),
+        };
+        (w.max(1.0), h.max(1.0))
+    }
+}
+
+/// Handle the argument of `OSC 1337 ; File=...`: place the image at the
+/// cursor and leave the cursor after it, on its last row.
+pub fn place_file(grid: &mut TerminalGrid, data: &[u8]) {
+    let Some(inline) = parse_file(data) else {
+        return;
+    };
+    let (cell_w, cell_h) = grid.cell_size;
+    let screen = (grid.cols as f32 * cell_w, grid.rows as f32 * cell_h);
+    let size = inline.display_size(grid.cell_size, screen);
+
+    let col = grid.cursor_col;
+    let (cols, rows) = grid.place_image(Arc::new(inline.image), size);
+    for _ in 1..rows {
+        grid.newline();
+    }
+    grid.cursor_col = (col + cols).min(grid.cols.saturating_sub(1));
+    grid.pending_wrap = false;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn png_base64(width: u32, height: u32) -> String {
+        let image = image::RgbaImage::from_pixel(width, height, image::Rgba([0, 0, 255, 255]));
+        let mut bytes = Vec::new();
+        image.write_to(&mut std::io::Cursor::new(&mut bytes), image::ImageFormat::Png).unwrap();
+        STANDARD.encode(bytes)
+    }
+
+    fn image(args: &str, width: u32, height: u32) -> InlineImage {
+        parse_file(format!("{}:{}", args, png_base64(width, height)).as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn parses_dimensions() {
+        assert_eq!(Dimension::parse("10"), Dimension::Cells(10.0));
+        assert_eq!(Dimension::parse("120px"), Dimension::Pixels(120.0));
+        assert_eq!(Dimension::parse("50%"), Dimension::Percent(50.0));
+        assert_eq!(Dimension::parse("auto"), Dimension::Auto);
+        assert_eq!(Dimension::parse("-3"), Dimension::Auto);
+    }
+
+    #[test]
+    fn only_inline_files_are_shown() {
+        let data = png_base64(2, 2);
+        assert!(parse_file(format!("name=eC5wbmc=;size=10:{}", data).as_bytes()).is_none());
+        assert!(parse_file(format!("inline=1:{}", data).as_bytes()).is_some());
+        assert!(parse_file(b"inline=1:not an image").is_none());
+    }
+
+    #[test]
+    fn oversized_images_are_rejected() {
+        let too_wide = png_base64(MAX_IMAGE_DIMENSION + 1, 1);
+        assert!(parse_file(format!("inline=1:{}", too_wide).as_bytes()).is_none());
+        let widest = png_base64(MAX_IMAGE_DIMENSION, 1);
+        assert!(parse_file(format!("inline=1:{}", widest).as_bytes()).is_some());
+
+        let mut g = TerminalGrid::new(20, 10);
+        place_file(&mut g, format!("inline=1:{}", too_wide).as_bytes());
+        assert!(g.images.is_empty());
+    }
+
+    #[test]
+    fn display_size_from_arguments() {
+        let cell = (10.0, 20.0);
+        let screen = (800.0, 480.0);
+        // Natural size, shrunk to the screen width
+        assert_eq!(image("inline=1", 40, 20).display_size(cell, screen), (40.0, 20.0));
+        assert_eq!(image("inline=1", 1600, 100).display_size(cell, screen), (800.0, 50.0));
+        // One side given: the other follows the aspect ratio
+        assert_eq!(image("inline=1;width=8", 40, 20).display_size(cell, screen), (80.0, 40.0));
+        assert_eq!(image("inline=1;height=50%", 40, 20).display_size(cell, screen), (480.0, 240.0));
+        // Both given: fit inside the box, or stretch without preserveAspectRatio
+        assert_eq!(image("inline=1;width=100px;height=100px", 40, 20).display_size(cell, screen), (100.0, 50.0));
+        assert_eq!(
+            image("inline=1;width=100px;height=100px;preserveAspectRatio=0", 40, 20).display_size(cell, screen),
+            (100.0, 100.0)
+        );
+    }
+
+    #[test]
+    fn place_file_reserves_rows() {
+        let mut g = TerminalGrid::new(20, 10);
+        g.cell_size = (10.0, 20.0);
+        g.cursor_col = 2;
+        let data = format!("inline=1;width=3;height=2;preserveAspectRatio=0:{}", png_base64(4, 4));
+        place_file(&mut g, data.as_bytes());
+        let p = &g.images[0];
+        assert_eq!((p.col, p.cols, p.rows, p.size), (2, 3, 2, (30.0, 40.0)));
+        assert_eq!((g.cursor_row, g.cursor_col), (1, 5));
+    }
+}
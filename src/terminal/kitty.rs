@@ -0,0 +1,722 @@
+use super::graphics::{ImageData, ImagePlacement};
+use super::grid::TerminalGrid;
+use base64::alphabet;
+use base64::engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig};
+use base64::Engine;
+use flate2::read::ZlibDecoder;
+use std::collections::HashMap;
+use std::io::{Read, Seek, SeekFrom};
+use parking_lot::Mutex;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Largest decoded image (RGBA bytes) we accept, as in kitty.
+const MAX_IMAGE_BYTES: usize = 400 * 1024 * 1024;
+
+/// Largest image side in pixels: the texture size wgpu's default limits
+/// guarantee.
+pub const MAX_IMAGE_DIMENSION: u32 = 8192;
+
+/// Where files named by `t=f` may not be read from, as in kitty.
+const FORBIDDEN_DIRS: [&str; 3] = ["/proc", "/sys", "/dev"];
+
+/// Budget for image data held by the store. The oldest images are
+/// forgotten beyond it; their placements keep showing.
+const STORE_LIMIT: usize = 320 * 1024 * 1024;
+
+/// Kitty payloads may come with or without padding, split into chunks.
+const BASE64: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+/// The control data of a graphics command (`key=value` pairs before `;`).
+#[derive(Debug, Clone, PartialEq)]
+struct Command {
+    /// `a`: t(ransmit), T(ransmit and place), q(uery), p(lace), d(elete)
+    action: u8,
+    /// `q`: 1 suppresses OK replies, 2 suppresses all replies
+    quiet: u32,
+    /// `f`: 24 (RGB), 32 (RGBA) or 100 (PNG)
+    format: u32,
+    /// `t`: d(irect), f(ile), t(emporary file), s(hared memory)
+    medium: u8,
+    /// `o`: z for zlib compressed data
+    compression: Option<u8>,
+    /// `s`, `v`: pixel size of raw data
+    width: u32,
+    height: u32,
+    /// `S`, `O`: bytes to read from a file and where to start
+    size: u64,
+    offset: u64,
+    /// `i`, `I`, `p`: image id, image number, placement id
+    id: u32,
+    number: u32,
+    placement: u32,
+    /// `m`: more chunks follow
+    more: bool,
+    /// `x`, `y`, `w`, `h`: source rectangle (for `a=d`, `x`/`y` are cells)
+    x: u32,
+    y: u32,
+    w: u32,
+    h: u32,
+    /// `X`, `Y`: offset within the first cell in pixels
+    x_offset: u32,
+    y_offset: u32,
+    /// `c`, `r`: columns and rows to scale the image to
+    cols: u32,
+    rows: u32,
+    /// `C=1`: leave the cursor where it is
+    no_cursor_move: bool,
+    /// `z`: stacking order relative to text
+    z: i32,
+    /// `d`: what to delete
+    delete: u8,
+}
+
+impl Default for Command {
+    fn default() -> Self {
+        Self {
+            action: b't',
+            quiet: 0,
+            format: 32,
+            medium: b'd',
+            compression: None,
+            width: 0,
+            height: 0,
+            size: 0,
+            offset: 0,
+            id: 0,
+            number: 0,
+            placement: 0,
+            more: false,
+            x: 0,
+            y: 0,
+            w: 0,
+            h: 0,
+            x_offset: 0,
+            y_offset: 0,
+            cols: 0,
+            rows: 0,
+            no_cursor_move: false,
+            z: 0,
+            delete: b'a',
+        }
+    }
+}
+
+impl Command {
+    /// Parse `a=T,f=100,i=1`. Unknown keys and malformed values are ignored.
+    fn parse(control: &[u8]) -> Self {
+        let mut cmd = Command::default();
+        for pair in control.split(|&b| b == b',') {
+            let Some((&key, value)) = pair.split_first() else {
+                continue;
+            };
+            let Some(value) = value.strip_prefix(b"=") else {
+                continue;
+            };
+            let number = || std::str::from_utf8(value).ok().and_then(|v| v.parse::<u32>().ok());
+            let letter = || value.first().copied();
+            match key {
+                b'a' => cmd.action = letter().unwrap_or(cmd.action),
+                b't' => cmd.medium = letter().unwrap_or(cmd.medium),
+                b'd' => cmd.delete = letter().unwrap_or(cmd.delete),
+                b'o' => cmd.compression = letter(),
+                b'z' => {
+                    cmd.z = std::str::from_utf8(value).ok().and_then(|v| v.parse().ok()).unwrap_or(0)
+                }
+                _ => {
+                    let Some(n) = number() else {
+                        continue;
+                    };
+                    match key {
+                        b'q' => cmd.quiet = n,
+                        b'f' => cmd.format = n,
+                        b's' => cmd.width = n,
+                        b'v' => cmd.height = n,
+                        b'S' => cmd.size = n as u64,
+                        b'O' => cmd.offset = n as u64,
+                        b'i' => cmd.id = n,
+                        b'I' => cmd.number = n,
+                        b'p' => cmd.placement = n,
+                        b'm' => cmd.more = n == 1,
+                        b'x' => cmd.x = n,
+                        b'y' => cmd.y = n,
+                        b'w' => cmd.w = n,
+                        b'h' => cmd.h = n,
+                        b'X' => cmd.x_offset = n,
+                        b'Y' => cmd.y_offset = n,
+                        b'c' => cmd.cols = n,
+                        b'r' => cmd.rows = n,
+                        b'C' => cmd.no_cursor_move = n == 1,
+                        _ => {}
+                    }
+                }
+            }
+        }
+        cmd
+    }
+}
+
+#[derive(Debug, Clone)]
+struct StoredImage {
+    data: Arc<ImageData>,
+    number: u32,
+    /// Transmission order, for eviction and number lookups
+    sequence: u64,
+}
+
+/// Images transmitted with the kitty graphics protocol, by image id.
+/// Placements live in `TerminalGrid::images`; they share the pixel data.
+#[derive(Debug, Clone, Default)]
+pub struct KittyImageStore {
+    images: HashMap<u32, StoredImage>,
+    /// Chunked transmission in progress: its first command and the base64
+    /// payload collected so far.
+    pending: Option<(Command, Vec<u8>)>,
+    next_id: u32,
+    sequence: u64,
+}
+
+impl KittyImageStore {
+    fn insert(&mut self, id: u32, number: u32, data: Arc<ImageData>) {
+        self.sequence += 1;
+        self.images.insert(id, StoredImage { data, number, sequence: self.sequence });
+        let mut total: usize = self.images.values().map(|i| i.data.byte_size()).sum();
+        while total > STORE_LIMIT && self.images.len() > 1 {
+            let Some((&oldest, _)) = self.images.iter().min_by_key(|(_, i)| i.sequence) else {
+                break;
+            };
+            total -= self.images.remove(&oldest).map_or(0, |i| i.data.byte_size());
+        }
+    }
+
+    /// An unused id for an image sent with only a number (`I`).
+    fn allocate_id(&mut self) -> u32 {
+        loop {
+            self.next_id = self.next_id.wrapping_add(1).max(1);
+            if !self.images.contains_key(&self.next_id) {
+                return self.next_id;
+            }
+        }
+    }
+
+    /// Id of the newest image with the given number.
+    fn id_of_number(&self, number: u32) -> Option<u32> {
+        self.images
+            .iter()
+            .filter(|(_, i)| i.number == number)
+            .max_by_key(|(_, i)| i.sequence)
+            .map(|(&id, _)| id)
+    }
+}
+
+/// Data read for a file transmission, or why it could not be.
+type FileData = Result<Vec<u8>, String>;
+
+/// Split a command into its control data and payload.
+fn split_command(data: &[u8]) -> (&[u8], &[u8]) {
+    match data.iter().position(|&b| b == b';') {
+        Some(i) => (&data[..i], &data[i + 1..]),
+        None => (data, &[][..]),
+    }
+}
+
+/// Handle the body of an APC `G` command (without the `G`) for the grid
+/// behind `grid`. A file named by a single-chunk transmission is read
+/// before the grid is locked, so a slow path doesn't hold up drawing.
+pub fn dispatch(grid: &Mutex<TerminalGrid>, data: &[u8]) {
+    let (control, payload) = split_command(data);
+    let cmd = Command::parse(control);
+    let file = (matches!(cmd.medium, b'f' | b't') && !cmd.more).then(|| read_named_file(&cmd, payload));
+    handle(&mut grid.lock(), data, file);
+}
+
+/// Handle the body of an APC `G` command (without the `G`). `file` holds
+/// what a file transmission named by it read, if that was done already.
+fn handle(grid: &mut TerminalGrid, data: &[u8], file: Option<FileData>) {
+    let (control, payload) = split_command(data);
+    let mut cmd = Command::parse(control);
+
+    // Later chunks of a transmission carry only `m` (and maybe `q`)
+    if let Some((first, mut collected)) = grid.kitty.pending.take() {
+        collected.extend_from_slice(payload);
+        if cmd.more {
+            if collected.len() <= MAX_IMAGE_BYTES / 3 * 4 + 4 {
+                grid.kitty.pending = Some((first, collected));
+            }
+            return;
+        }
+        return run(grid, first, &collected, None);
+    }
+    if cmd.more && matches!(cmd.action, b't' | b'T' | b'q') {
+        cmd.more = false;
+        grid.kitty.pending = Some((cmd, payload.to_vec()));
+        return;
+    }
+    run(grid, cmd, payload, file);
+}
+
+fn run(grid: &mut TerminalGrid, mut cmd: Command, payload: &[u8], file: Option<FileData>) {
+    // Commands that name no image are carried out silently
+    let wants_reply = cmd.id != 0 || cmd.number != 0;
+    if cmd.id != 0 && cmd.number != 0 {
+        return reply(grid, &cmd, Err("EINVAL:both i and I given".into()));
+    }
+    let result = match cmd.action {
+        b't' | b'T' => transmit(grid, &mut cmd, payload, file),
+        b'q' => load_image(&cmd, payload, file).map(|_| ()),
+        b'p' => put(grid, &mut cmd),
+        b'd' => {
+            delete(grid, &cmd);
+            return;
+        }
+        _ => Err("EINVAL:unknown action".into()),
+    };
+    if wants_reply {
+        reply(grid, &cmd, result);
+    }
+}
+
+fn transmit(grid: &mut TerminalGrid, cmd: &mut Command, payload: &[u8], file: Option<FileData>) -> Result<(), String> {
+    let image = Arc::new(load_image(cmd, payload, file)?);
+    if cmd.id == 0 {
+        cmd.id = grid.kitty.allocate_id();
+    }
+    // Retransmitting an id replaces the image along with its placements
+    let id = cmd.id;
+    grid.remove_images(|p| p.kitty_id == id);
+    grid.kitty.insert(id, cmd.number, image.clone());
+    if cmd.action == b'T' {
+        place(grid, cmd, image)?;
+    }
+    Ok(())
+}
+
+fn put(grid: &mut TerminalGrid, cmd: &mut Command) -> Result<(), String> {
+    if cmd.number != 0 {
+        cmd.id = grid.kitty.id_of_number(cmd.number).ok_or("ENOENT:no such image number")?;
+    }
+    let image = grid.kitty.images.get(&cmd.id).ok_or("ENOENT:no such image")?.data.clone();
+    place(grid, cmd, image)
+}
+
+/// Show `image` at the cursor as described by `cmd`.
+fn place(grid: &mut TerminalGrid, cmd: &Command, image: Arc<ImageData>) -> Result<(), String> {
+    let x = cmd.x.min(image.width);
+    let y = cmd.y.min(image.height);
+    let w = if cmd.w == 0 { image.width - x } else { cmd.w.min(image.width - x) };
+    let h = if cmd.h == 0 { image.height - y } else { cmd.h.min(image.height - y) };
+    if w == 0 || h == 0 {
+        return Err("EINVAL:empty source rectangle".into());
+    }
+
+    // Scale to the requested cells, keeping the aspect ratio when only
+    // one side is given
+    let (cell_w, cell_h) = grid.cell_size;
+    let (width, height) = match (cmd.cols, cmd.rows) {
+        (0, 0) => (w as f32, h as f32),
+        (c, 0) => {
+            let width = c as f32 * cell_w;
+            (width, width * h as f32 / w as f32)
+        }
+        (0, r) => {
+            let height = r as f32 * cell_h;
+            (height * w as f32 / h as f32, height)
+        }
+        (c, r) => (c as f32 * cell_w, r as f32 * cell_h),
+    };
+    let offset = (
+        (cmd.x_offset as f32).min(cell_w - 1.0).max(0.0) as u32,
+        (cmd.y_offset as f32).min(cell_h - 1.0).max(0.0) as u32,
+    );
+    let cols = ((offset.0 as f32 + width) / cell_w.max(1.0)).ceil().max(1.0) as usize;
+    let rows = ((offset.1 as f32 + height) / cell_h.max(1.0)).ceil().max(1.0) as usize;
+
+    let (id, placement_id) = (cmd.id, cmd.placement);
+    if placement_id != 0 {
+        grid.remove_images(|p| p.kitty_id == id && p.kitty_placement == placement_id);
+    }
+    let col = grid.cursor_col;
+    let mut placement = ImagePlacement::new(image, grid.cursor_line(), col, cols, rows);
+    placement.offset = offset;
+    placement.source = (x, y, w, h);
+    placement.size = (width, height);
+    placement.z_index = cmd.z;
+    placement.kitty_id = id;
+    placement.kitty_placement = placement_id;
+    grid.add_image(placement);
+
+    // The cursor ends up after the image, on its last row
+    if !cmd.no_cursor_move {
+        for _ in 1..rows {
+            grid.newline();
+        }
+        grid.cursor_col = (col + cols).min(grid.cols.saturating_sub(1));
+        grid.pending_wrap = false;
+    }
+    Ok(())
+}
+
+fn delete(grid: &mut TerminalGrid, cmd: &Command) {
+    let first_visible = grid.line_of_abs_row(grid.scrollback.len());
+    let visible = |p: &ImagePlacement| p.end_line() > first_visible;
+    // `x` and `y` address cells, 1-based
+    let cell_line = first_visible + cmd.y.saturating_sub(1) as u64;
+    let cell_col = cmd.x.saturating_sub(1) as usize;
+    let cursor_line = grid.cursor_line();
+    let cursor_col = grid.cursor_col;
+    let number_id = grid.kitty.id_of_number(cmd.number);
+
+    let removed = match cmd.delete.to_ascii_lowercase() {
+        b'a' => grid.remove_images(|p| p.kitty_id != 0 && visible(p)),
+        b'i' => grid.remove_images(|p| {
+            p.kitty_id != 0
+                && p.kitty_id == cmd.id
+                && (cmd.placement == 0 || p.kitty_placement == cmd.placement)
+        }),
+        b'n' => grid.remove_images(|p| {
+            Some(p.kitty_id) == number_id && (cmd.placement == 0 || p.kitty_placement == cmd.placement)
+        }),
+        b'c' => grid.remove_images(|p| p.kitty_id != 0 && p.covers(cursor_line, cursor_col)),
+        b'p' => grid.remove_images(|p| p.kitty_id != 0 && p.covers(cell_line, cell_col)),
+        b'x' => grid.remove_images(|p| p.kitty_id != 0 && (p.col..p.col + p.cols).contains(&cell_col)),
+        b'y' => grid.remove_images(|p| p.kitty_id != 0 && (p.line..p.end_line()).contains(&cell_line)),
+        b'z' => grid.remove_images(|p| p.kitty_id != 0 && visible(p) && p.z_index == cmd.z),
+        b'r' => grid.remove_images(|p| p.kitty_id != 0 && (cmd.x..=cmd.y).contains(&p.kitty_id)),
+        _ => return,
+    };
+
+    // Uppercase also frees image data nothing shows any more
+    if cmd.delete.is_ascii_uppercase() {
+        let mut ids: Vec<u32> = removed.iter().map(|p| p.kitty_id).collect();
+        match cmd.delete {
+            b'I' => ids.push(cmd.id),
+            b'N' => ids.extend(number_id),
+            b'R' => ids.extend(grid.kitty.images.keys().filter(|id| (cmd.x..=cmd.y).contains(id))),
+            _ => {}
+        }
+        for id in ids {
+            if !grid.kitty_image_in_use(id) {
+                grid.kitty.images.remove(&id);
+            }
+        }
+    }
+}
+
+/// Send the reply for `cmd`, unless `q` asks for silence.
+fn reply(grid: &mut TerminalGrid, cmd: &Command, result: Result<(), String>) {
+    if cmd.quiet >= 2 || (cmd.quiet == 1 && result.is_ok()) {
+        return;
+    }
+    let mut keys = format!("i={}", cmd.id);
+    if cmd.number != 0 {
+        keys.push_str(&format!(",I={}", cmd.number));
+    }
+    if cmd.placement != 0 {
+        keys.push_str(&format!(",p={}", cmd.placement));
+    }
+    let message = result.err().unwrap_or_else(|| "OK".into());
+    grid.response_queue.push(format!("\x1b_G{};{}\x1b\\", keys, message).into_bytes());
+}
+
+/// Decode the payload of a transmission into pixels. `file` is the data
+/// of a file transmission if it has been read already.
+fn load_image(cmd: &Command, payload: &[u8], file: Option<FileData>) -> Result<ImageData, String> {
+    let data = match cmd.medium {
+        b'd' => BASE64.decode(payload).map_err(|_| "EINVAL:bad base64 payload".to_string())?,
+        b'f' | b't' => file.unwrap_or_else(|| read_named_file(cmd, payload))?,
+        b's' => return Err("EINVAL:shared memory is not supported".into()),
+        _ => return Err("EINVAL:unknown transmission medium".into()),
+    };
+    let data = match cmd.compression {
+        None => data,
+        Some(b'z') => {
+            let mut inflated = Vec::new();
+            ZlibDecoder::new(&data[..])
+                .take(MAX_IMAGE_BYTES as u64 + 1)
+                .read_to_end(&mut inflated)
+                .map_err(|_| "EINVAL:bad zlib data".to_string())?;
+            inflated
+        }
+        Some(_) => return Err("EINVAL:unknown compression".into()),
+    };
+    if data.len() > MAX_IMAGE_BYTES {
+        return Err("EFBIG:image too large".into());
+    }
+
+    let (width, height, rgba) = match cmd.format {
+        100 => {
+            let image = image::load_from_memory_with_format(&data, image::ImageFormat::Png)
+                .map_err(|e| format!("EBADPNG:{}", e))?
+                .to_rgba8();
+            (image.width(), image.height(), image.into_raw())
+        }
+        24 | 32 => {
+            let bytes_per_pixel = (cmd.format / 8) as usize;
+            let (width, height) = (cmd.width, cmd.height);
+            if width == 0 || height == 0 {
+                return Err("EINVAL:missing image size".into());
+            }
+            if data.len() != width as usize * height as usize * bytes_per_pixel {
+                return Err("ENODATA:data does not match image size".into());
+            }
+            let rgba = if bytes_per_pixel == 4 {
+                data
+            } else {
+                data.chunks_exact(3).flat_map(|px| [px[0], px[1], px[2], 255]).collect()
+            };
+            (width, height, rgba)
+        }
+        _ => return Err("EINVAL:unknown format".into()),
+    };
+    if width == 0 || height == 0 || width > MAX_IMAGE_DIMENSION || height > MAX_IMAGE_DIMENSION {
+        return Err("EINVAL:bad image size".into());
+    }
+    Ok(ImageData::new(width, height, rgba))
+}
+
+/// Read the file whose name is the payload of a file transmission.
+fn read_named_file(cmd: &Command, payload: &[u8]) -> FileData {
+    let decoded = BASE64.decode(payload).map_err(|_| "EINVAL:bad base64 payload".to_string())?;
+    let path = String::from_utf8(decoded).map_err(|_| "EINVAL:bad file name".to_string())?;
+    read_file(Path::new(&path), cmd)
+}
+
+/// Read the data of a file transmission (`t=f` or `t=t`). Temporary files
+/// are deleted afterwards, but only ones that look like they were made for
+/// this purpose.
+fn read_file(path: &Path, cmd: &Command) -> FileData {
+    let canonical = path.canonicalize().map_err(|e| format!("ENOENT:{}", e))?;
+    let temporary = cmd.medium == b't' && is_temporary_file(path);
+    if !temporary && FORBIDDEN_DIRS.iter().any(|dir| canonical.starts_with(dir)) {
+        return Err("EPERM:files in this directory may not be read".into());
+    }
+    let metadata = std::fs::metadata(&canonical).map_err(|e| format!("ENOENT:{}", e))?;
+    if !metadata.is_file() {
+        return Err("EINVAL:not a regular file".into());
+    }
+    if cmd.size == 0 && metadata.len().saturating_sub(cmd.offset) > MAX_IMAGE_BYTES as u64 {
+        return Err("EFBIG:file too large".into());
+    }
+    let result = (|| {
+        let mut file = std::fs::File::open(&canonical)?;
+        file.seek(SeekFrom::Start(cmd.offset))?;
+        let limit = if cmd.size == 0 { MAX_IMAGE_BYTES as u64 + 1 } else { cmd.size };
+        let mut data = Vec::new();
+        file.take(limit).read_to_end(&mut data)?;
+        Ok::<_, std::io::Error>(data)
+    })();
+    if temporary {
+        let _ = std::fs::remove_file(path);
+    }
+    result.map_err(|e| format!("EBADF:{}", e))
+}
+
+fn is_temporary_file(path: &Path) -> bool {
+    let named = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|n| n.contains("tty-graphics-protocol"));
+    let temp_dirs = [std::env::temp_dir(), "/tmp".into(), "/dev/shm".into()];
+    named && temp_dirs.iter().any(|dir| path.starts_with(dir))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::engine::general_purpose::STANDARD;
+
+    fn handle_command(grid: &mut TerminalGrid, data: &[u8]) {
+        handle(grid, data, None);
+    }
+
+    fn send(grid: &mut TerminalGrid, control: &str, payload: &[u8]) {
+        let mut data = format!("{};", control).into_bytes();
+        data.extend(STANDARD.encode(payload).as_bytes());
+        handle_command(grid, &data);
+    }
+
+    fn replies(grid: &mut TerminalGrid) -> Vec<String> {
+        grid.response_queue.drain(..).map(|r| String::from_utf8(r).unwrap()).collect()
+    }
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let image = image::RgbaImage::from_pixel(width, height, image::Rgba([255, 0, 0, 255]));
+        let mut bytes = Vec::new();
+        image.write_to(&mut std::io::Cursor::new(&mut bytes), image::ImageFormat::Png).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn parses_control_keys() {
+        let cmd = Command::parse(b"a=T,f=100,i=7,p=2,z=-3,C=1,q=2,unknown=5,x");
+        assert_eq!(cmd.action, b'T');
+        assert_eq!(cmd.format, 100);
+        assert_eq!((cmd.id, cmd.placement, cmd.z, cmd.quiet), (7, 2, -3, 2));
+        assert!(cmd.no_cursor_move);
+    }
+
+    #[test]
+    fn transmit_raw_rgb_and_reply() {
+        let mut g = TerminalGrid::new(10, 5);
+        send(&mut g, "a=t,f=24,s=2,v=1,i=5", &[1, 2, 3, 4, 5, 6]);
+        assert_eq!(replies(&mut g), vec!["\x1b_Gi=5;OK\x1b\\"]);
+        let data = &g.kitty.images[&5].data;
+        assert_eq!(data.rgba, vec![1, 2, 3, 255, 4, 5, 6, 255]);
+        assert!(g.images.is_empty());
+    }
+
+    #[test]
+    fn size_mismatch_is_an_error() {
+        let mut g = TerminalGrid::new(10, 5);
+        send(&mut g, "a=t,f=32,s=2,v=2,i=1", &[0; 4]);
+        let reply = replies(&mut g);
+        assert!(reply[0].starts_with("\x1b_Gi=1;ENODATA"), "{:?}", reply);
+        assert!(g.kitty.images.is_empty());
+    }
+
+    #[test]
+    fn oversized_image_is_an_error() {
+        let mut g = TerminalGrid::new(10, 5);
+        let width = MAX_IMAGE_DIMENSION + 1;
+        send(&mut g, &format!("a=T,f=32,s={},v=1,i=1", width), &vec![0; width as usize * 4]);
+        let reply = replies(&mut g);
+        assert!(reply[0].starts_with("\x1b_Gi=1;EINVAL"), "{:?}", reply);
+        assert!(g.images.is_empty());
+    }
+
+    #[test]
+    fn chunked_png_transmit_and_place() {
+        let mut g = TerminalGrid::new(20, 10);
+        g.cell_size = (10.0, 20.0);
+        let encoded = STANDARD.encode(png(25, 30));
+        let (first, rest) = encoded.as_bytes().split_at(encoded.len() / 2 / 4 * 4);
+        handle_command(&mut g, &[b"a=T,f=100,i=3,m=1;".as_slice(), first].concat());
+        assert!(g.images.is_empty());
+        handle_command(&mut g, &[b"m=0;".as_slice(), rest].concat());
+        assert_eq!(replies(&mut g), vec!["\x1b_Gi=3;OK\x1b\\"]);
+
+        let p = &g.images[0];
+        assert_eq!((p.image.width, p.image.height), (25, 30));
+        assert_eq!((p.col, p.cols, p.rows, p.kitty_id), (0, 3, 2, 3));
+        // Cursor moves to the column after the image on its last row
+        assert_eq!((g.cursor_row, g.cursor_col), (1, 3));
+    }
+
+    #[test]
+    fn place_with_scaling_offset_and_no_cursor_move() {
+        let mut g = TerminalGrid::new(20, 10);
+        g.cell_size = (10.0, 20.0);
+        send(&mut g, "a=t,f=32,s=4,v=2,i=1,q=1", &[0; 32]);
+        assert!(replies(&mut g).is_empty());
+        handle_command(&mut g, b"a=p,i=1,p=9,c=4,X=3,Y=2,x=2,w=2,C=1,z=5");
+        assert_eq!(replies(&mut g), vec!["\x1b_Gi=1,p=9;OK\x1b\\"]);
+        let p = &g.images[0];
+        assert_eq!(p.source, (2, 0, 2, 2));
+        assert_eq!(p.size, (40.0, 40.0));
+        assert_eq!(p.offset, (3, 2));
+        assert_eq!((p.cols, p.rows, p.z_index), (5, 3, 5));
+        assert_eq!((g.cursor_row, g.cursor_col), (0, 0));
+
+        // Same image and placement id replaces the placement
+        handle_command(&mut g, b"a=p,i=1,p=9,C=1");
+        assert_eq!(g.images.len(), 1);
+        assert_eq!(g.images[0].size, (4.0, 2.0));
+    }
+
+    #[test]
+    fn place_unknown_image_fails() {
+        let mut g = TerminalGrid::new(10, 5);
+        handle_command(&mut g, b"a=p,i=42");
+        assert_eq!(replies(&mut g), vec!["\x1b_Gi=42;ENOENT:no such image\x1b\\"]);
+    }
+
+    #[test]
+    fn image_number_gets_an_id() {
+        let mut g = TerminalGrid::new(10, 5);
+        send(&mut g, "a=t,f=24,s=1,v=1,I=13", &[0; 3]);
+        let id = g.kitty.id_of_number(13).unwrap();
+        assert_eq!(replies(&mut g), vec![format!("\x1b_Gi={},I=13;OK\x1b\\", id)]);
+        handle_command(&mut g, b"a=p,I=13,q=2");
+        assert!(replies(&mut g).is_empty());
+        assert_eq!(g.images[0].kitty_id, id);
+    }
+
+    #[test]
+    fn query_does_not_store() {
+        let mut g = TerminalGrid::new(10, 5);
+        send(&mut g, "a=q,f=24,s=1,v=1,i=31", &[0; 3]);
+        assert_eq!(replies(&mut g), vec!["\x1b_Gi=31;OK\x1b\\"]);
+        assert!(g.kitty.images.is_empty());
+    }
+
+    #[test]
+    fn zlib_compressed_payload() {
+        use flate2::write::ZlibEncoder;
+        use std::io::Write;
+        let mut encoder = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&[7; 16]).unwrap();
+        let mut g = TerminalGrid::new(10, 5);
+        send(&mut g, "a=t,f=32,s=2,v=2,o=z,i=2", &encoder.finish().unwrap());
+        assert_eq!(replies(&mut g), vec!["\x1b_Gi=2;OK\x1b\\"]);
+        assert_eq!(g.kitty.images[&2].data.rgba, vec![7; 16]);
+    }
+
+    #[test]
+    fn temporary_file_is_read_and_removed() {
+        let path = std::env::temp_dir().join(format!("tty-graphics-protocol-test-{}", std::process::id()));
+        std::fs::write(&path, [9, 9, 9]).unwrap();
+        let mut g = TerminalGrid::new(10, 5);
+        send(&mut g, "a=t,t=t,f=24,s=1,v=1,i=4", path.to_str().unwrap().as_bytes());
+        assert_eq!(replies(&mut g), vec!["\x1b_Gi=4;OK\x1b\\"]);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn file_is_read_before_locking() {
+        let path = std::env::temp_dir().join(format!("kitty-file-test-{}", std::process::id()));
+        std::fs::write(&path, [1, 2, 3]).unwrap();
+        let g = Mutex::new(TerminalGrid::new(10, 5));
+        let data = format!("a=t,t=f,f=24,s=1,v=1,i=6;{}", STANDARD.encode(path.to_str().unwrap()));
+        dispatch(&g, data.as_bytes());
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(replies(&mut g.lock()), vec!["\x1b_Gi=6;OK\x1b\\"]);
+        assert_eq!(g.lock().kitty.images[&6].data.rgba, vec![1, 2, 3, 255]);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn special_files_are_refused() {
+        let mut g = TerminalGrid::new(10, 5);
+        send(&mut g, "a=t,t=f,f=24,s=1,v=1,i=7", b"/proc/self/status");
+        let reply = replies(&mut g);
+        assert!(reply[0].starts_with("\x1b_Gi=7;EPERM"), "{:?}", reply);
+    }
+
+    #[test]
+    fn delete_placements_and_data() {
+        let mut g = TerminalGrid::new(10, 5);
+        send(&mut g, "a=t,f=24,s=1,v=1,i=1", &[0; 3]);
+        send(&mut g, "a=t,f=24,s=1,v=1,i=2", &[0; 3]);
+        handle_command(&mut g, b"a=p,i=1,C=1");
+        handle_command(&mut g, b"a=p,i=2,C=1");
+        g.cursor_col = 5;
+        handle_command(&mut g, b"a=p,i=2,C=1");
+
+        // Lowercase keeps the image data
+        handle_command(&mut g, b"a=d,d=i,i=1");
+        assert_eq!(g.images.len(), 2);
+        assert!(g.kitty.images.contains_key(&1));
+
+        // Deleting by cell only hits placements covering it
+        handle_command(&mut g, b"a=d,d=P,x=6,y=1");
+        assert_eq!(g.images.len(), 1);
+        assert!(g.kitty.images.contains_key(&2), "still placed at column 0");
+
+        handle_command(&mut g, b"a=d,d=A");
+        assert!(g.images.is_empty());
+        assert!(!g.kitty.images.contains_key(&2));
+    }
+}
@@ -1,7 +1,18 @@
+pub mod apc;
 pub mod cell;
+pub mod charset;
+pub mod clipboard;
+pub mod colors;
+pub mod dcs;
+pub mod graphics;
 pub mod grid;
+pub mod iterm;
+pub mod kitty;
+pub mod notification;
 pub mod parser;
+pub mod processor;
 pub mod pty;
+pub mod sixel;
 pub mod url;
 
 use anyhow::Result;
@@ -10,32 +21,38 @@ use std::path::Path;
 use std::sync::Arc;
 
 use grid::TerminalGrid;
-use parser::VtePerformer;
+use processor::OutputProcessor;
 use pty::PtyHandle;
 
 pub struct Terminal {
     pub grid: Arc<Mutex<TerminalGrid>>,
     pub pty: PtyHandle,
-    parser: vte::Parser,
-    performer: VtePerformer,
+    processor: OutputProcessor,
 }
 
 impl Terminal {
-    pub fn new(cols: usize, rows: usize, cwd: Option<&Path>) -> Result<Self> {
-        let grid = Arc::new(Mutex::new(TerminalGrid::new(cols, rows)));
-        let pty = PtyHandle::spawn(cols as u16, rows as u16, cwd)?;
-        let performer = VtePerformer::new(grid.clone());
-        let parser = vte::Parser::new();
-        Ok(Self { grid, pty, parser, performer })
+    /// Start a shell in a `cols` x `rows` terminal of `cell_size` pixel cells.
+    pub fn new(cols: usize, rows: usize, cell_size: (f32, f32), cwd: Option<&Path>) -> Result<Self> {
+        let mut grid = TerminalGrid::new(cols, rows);
+        grid.cell_size = cell_size;
+        let grid = Arc::new(Mutex::new(grid));
+        let pty = PtyHandle::spawn(cols as u16, rows as u16, cell_size, cwd)?;
+        let processor = OutputProcessor::new(grid.clone());
+        Ok(Self { grid, pty, processor })
     }
 
     /// Drain PTY output and process through VTE parser. Call every frame.
     pub fn drain_pty_output(&mut self) {
         let chunks = self.pty.try_recv_all();
         for chunk in chunks {
-            self.parser.advance(&mut self.performer, &chunk);
+            self.processor.advance(&chunk);
         }
-        // Send any queued responses (DA, DSR, etc.) back to the PTY
+        self.processor.end_stale_sync();
+        self.flush_responses();
+    }
+
+    /// Send any queued responses (DA, DSR, etc.) back to the PTY.
+    pub fn flush_responses(&mut self) {
         let responses: Vec<Vec<u8>> = {
             let mut grid = self.grid.lock();
             std::mem::take(&mut grid.response_queue)
@@ -52,12 +69,29 @@ impl Terminal {
     /// Feed raw bytes directly through the VTE parser (bypasses PTY).
     /// Used for benchmarking with synthetic content.
     pub fn feed_bytes(&mut self, data: &[u8]) {
-        self.parser.advance(&mut self.performer, data);
+        self.processor.advance(data);
+    }
+
+    /// Whether output is held back for a synchronized update (DEC mode
+    /// 2026). Frames must keep coming so a stalled update times out.
+    pub fn in_synchronized_update(&self) -> bool {
+        self.processor.in_synchronized_update()
     }
 
-    pub fn resize(&mut self, cols: usize, rows: usize) -> Result<()> {
-        self.grid.lock().resize(cols, rows);
-        self.pty.resize(cols as u16, rows as u16)?;
+    /// Reset the terminal to its initial state, for a pane an application
+    /// left in a bad state. The shell keeps running.
+    pub fn reset(&mut self) {
+        self.processor.reset();
+    }
+
+    /// Resize to `cols` x `rows` cells of `cell_size` pixels each.
+    pub fn resize(&mut self, cols: usize, rows: usize, cell_size: (f32, f32)) -> Result<()> {
+        {
+            let mut grid = self.grid.lock();
+            grid.cell_size = cell_size;
+            grid.resize(cols, rows);
+        }
+        self.pty.resize(cols as u16, rows as u16, cell_size)?;
         Ok(())
     }
 
@@ -0,0 +1,66 @@
+/// A desktop notification requested by the application with OSC 9 or
+/// OSC 777. The parser only records these; the app decides whether and how
+/// to show them, and drains them each frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    /// Empty for OSC 9, which only carries a body.
+    pub title: String,
+    pub body: String,
+}
+
+/// Parse the parameters after `9` in OSC 9 ; message ST (iTerm2/ntfy style).
+/// ConEmu reuses OSC 9 with a numeric first parameter (progress, sleep, ...);
+/// those are not notifications.
+pub fn parse_osc9(params: &[&[u8]]) -> Option<Notification> {
+    let first = params.first()?;
+    if !first.is_empty() && first.iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+    // The message may contain ';', which vte has split into further params
+    let body = String::from_utf8(params.join(&b';')).ok()?;
+    if body.is_empty() {
+        return None;
+    }
+    Some(Notification { title: String::new(), body })
+}
+
+/// Parse the parameters after `777` in OSC 777 ; notify ; title ; body ST
+/// (rxvt-unicode style).
+pub fn parse_osc777(params: &[&[u8]]) -> Option<Notification> {
+    let (&b"notify", [title, body @ ..]) = params.split_first()? else {
+        return None;
+    };
+    let title = std::str::from_utf8(title).ok()?.to_string();
+    let body = String::from_utf8(body.join(&b';')).ok()?;
+    if title.is_empty() && body.is_empty() {
+        return None;
+    }
+    Some(Notification { title, body })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notification(title: &str, body: &str) -> Option<Notification> {
+        Some(Notification { title: title.to_string(), body: body.to_string() })
+    }
+
+    #[test]
+    fn osc9_rejoins_split_message() {
+        assert_eq!(parse_osc9(&[b"build done", b" 0 errors"]), notification("", "build done; 0 errors"));
+    }
+
+    #[test]
+    fn osc9_ignores_conemu_sequences() {
+        assert_eq!(parse_osc9(&[b"4", b"1", b"50"]), None);
+        assert_eq!(parse_osc9(&[b""]), None);
+    }
+
+    #[test]
+    fn osc777_notify() {
+        assert_eq!(parse_osc777(&[b"notify", b"Tests", b"all passed"]), notification("Tests", "all passed"));
+        assert_eq!(parse_osc777(&[b"notify", b"Title"]), notification("Title", ""));
+        assert_eq!(parse_osc777(&[b"preexec", b"ls"]), None);
+    }
+}
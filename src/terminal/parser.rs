@@ -1,18 +1,56 @@
-use super::cell::{Cell, CellAttributes, Color};
-use super::grid::TerminalGrid;
+use super::cell::{Cell, CellAttributes, Color, SemanticZone, UnderlineStyle};
+use super::charset::{Charset, Charsets};
+use super::clipboard::parse_osc52;
+use super::colors::{format_color_spec, parse_color_spec};
+use super::dcs::{decrqss_response, xtgettcap_responses, DcsKind, DCS_MAX_BYTES};
+use super::notification::{parse_osc9, parse_osc777};
+use super::url::parse_osc7_cwd;
+use super::grid::{CursorShape, LineSize, MouseEncoding, MouseTracking, TerminalGrid};
+use super::{iterm, kitty};
+use super::sixel::SixelDecoder;
 use unicode_width::UnicodeWidthChar;
 use parking_lot::Mutex;
 use std::sync::Arc;
 use vte::Perform;
 
+/// Cursor state saved by DECSC (and `CSI s`, modes 1048/1049).
+#[derive(Debug, Clone, Copy, Default)]
+struct SavedCursor {
+    row: usize,
+    col: usize,
+    charsets: Charsets,
+    origin_mode: bool,
+}
+
+impl SavedCursor {
+    fn new(grid: &TerminalGrid, charsets: Charsets, origin_mode: bool) -> Self {
+        Self { row: grid.cursor_row, col: grid.cursor_col, charsets, origin_mode }
+    }
+}
+
 pub struct VtePerformer {
     pub grid: Arc<Mutex<TerminalGrid>>,
     /// Saved cursor state
-    saved_cursor: Option<(usize, usize)>,
-    /// Origin mode (DECOM)
+    saved_cursor: Option<SavedCursor>,
+    /// G0–G3 character sets (SCS, SI/SO, single shifts)
+    charsets: Charsets,
+    /// Origin mode (DECOM): cursor addressing is relative to the margins
     origin_mode: bool,
+    /// Left/right margin mode (DECLRMM): `CSI Pl ; Pr s` sets margins
+    /// instead of saving the cursor
+    lr_margin_mode: bool,
     /// Auto-wrap mode
     auto_wrap: bool,
+    /// DCS string being collected between `hook` and `unhook`, if it is
+    /// one we answer.
+    dcs: Option<(DcsKind, Vec<u8>)>,
+    /// Sixel image being decoded; its data streams through `put`.
+    sixel: Option<SixelDecoder>,
+    /// Synchronized output (DEC mode 2026).
+    synchronized_output: bool,
+    /// Set when a synchronized update begins; stops the parser so the
+    /// caller can hold back what follows.
+    sync_start: bool,
 }
 
 impl VtePerformer {
@@ -20,14 +58,72 @@ impl VtePerformer {
         Self {
             grid,
             saved_cursor: None,
+            charsets: Charsets::default(),
             origin_mode: false,
+            lr_margin_mode: false,
             auto_wrap: true,
+            dcs: None,
+            sixel: None,
+            synchronized_output: false,
+            sync_start: false,
+        }
+    }
+
+    /// Top-left corner cursor addressing is relative to: the margins in
+    /// origin mode, otherwise the screen.
+    fn origin(&self, grid: &TerminalGrid) -> (usize, usize) {
+        if self.origin_mode {
+            (grid.scroll_top, grid.scroll_left)
+        } else {
+            (0, 0)
+        }
+    }
+
+    /// Move the cursor to 0-based `row`/`col` relative to the origin,
+    /// staying within the margins in origin mode.
+    fn move_cursor_to(&self, grid: &mut TerminalGrid, row: Option<usize>, col: Option<usize>) {
+        let (top, left) = self.origin(grid);
+        let (bottom, right) = if self.origin_mode {
+            (grid.scroll_bottom, grid.scroll_right)
+        } else {
+            (grid.rows - 1, grid.cols - 1)
+        };
+        if let Some(row) = row {
+            grid.cursor_row = (top + row).min(bottom);
+        }
+        if let Some(col) = col {
+            grid.cursor_col = (left + col).min(right);
+        }
+        grid.pending_wrap = false;
+    }
+
+    /// Full reset (RIS) of the grid and of the parser-side modes.
+    pub fn reset(&mut self) {
+        self.grid.lock().reset();
+        *self = Self::new(self.grid.clone());
+    }
+
+    /// Whether a synchronized update began since the last call.
+    pub fn take_sync_start(&mut self) -> bool {
+        std::mem::take(&mut self.sync_start)
+    }
+
+    /// Handle an APC string (`ESC _ ... ESC \`). Only kitty graphics
+    /// commands (`G`) are understood.
+    pub fn apc_dispatch(&mut self, data: &[u8]) {
+        if let Some(command) = data.strip_prefix(b"G") {
+            kitty::dispatch(&self.grid, command);
         }
     }
 }
 
 impl Perform for VtePerformer {
+    fn terminated(&self) -> bool {
+        self.sync_start
+    }
+
     fn print(&mut self, c: char) {
+        let c = self.charsets.map(c);
         // Determine display width: 2 for wide chars (CJK, emoji, etc.), 1 for normal.
         let width = c.width().unwrap_or(1).max(1);
         let mut grid = self.grid.lock();
@@ -40,7 +136,7 @@ impl Perform for VtePerformer {
             } else if row < grid.rows - 1 {
                 grid.cursor_row += 1;
             }
-            grid.cursor_col = 0;
+            grid.cursor_col = grid.scroll_left;
             grid.pending_wrap = false;
         }
         let col = grid.cursor_col;
@@ -50,7 +146,13 @@ impl Perform for VtePerformer {
         // subsequent characters don't overwrite the right half of the glyph.
         if width == 2 {
             if col + 1 < grid.cols {
-                grid.cells[row][col + 1] = Cell::default();
+                let spacer = Cell {
+                    link: grid.current_link,
+                    zone: grid.current_zone,
+                    command: grid.current_command,
+                    ..Cell::default()
+                };
+                grid.cells[row][col + 1] = spacer;
             }
         }
         grid.advance_cursor_by_width(width);
@@ -67,11 +169,8 @@ impl Perform for VtePerformer {
                 grid.pending_wrap = false;
             }
             0x09 => {
-                // Tab — advance to next tab stop (every 8 cols)
-                let col = grid.cursor_col;
-                let next_tab = ((col / 8) + 1) * 8;
-                grid.cursor_col = next_tab.min(grid.cols - 1);
-                grid.pending_wrap = false;
+                // Tab — advance to next tab stop
+                grid.tab_forward(1);
             }
             0x0a | 0x0b | 0x0c => {
                 // LF, VT, FF
@@ -84,17 +183,73 @@ impl Perform for VtePerformer {
                 grid.carriage_return();
             }
             0x07 => {
-                // Bell — ignore
+                // Bell — the app flashes, beeps or flags the pane
+                grid.bell = true;
             }
+            // Shift In (LS0) / Shift Out (LS1)
+            0x0f => self.charsets.lock_shift(0),
+            0x0e => self.charsets.lock_shift(1),
             _ => {}
         }
     }
 
-    fn hook(&mut self, _params: &vte::Params, _intermediates: &[u8], _ignore: bool, _action: char) {}
-    fn put(&mut self, _byte: u8) {}
-    fn unhook(&mut self) {}
+    fn hook(&mut self, params: &vte::Params, intermediates: &[u8], ignore: bool, action: char) {
+        self.sixel = None;
+        self.dcs = None;
+        if ignore {
+            return;
+        }
+        if intermediates.is_empty() && action == 'q' {
+            let params: Vec<u16> = params.iter().map(|p| p[0]).collect();
+            self.sixel = Some(SixelDecoder::new(&params));
+            return;
+        }
+        self.dcs = DcsKind::from_hook(intermediates, action).map(|kind| (kind, Vec::new()));
+    }
+
+    fn put(&mut self, byte: u8) {
+        if let Some(sixel) = &mut self.sixel {
+            sixel.put(byte);
+        } else if let Some((_, data)) = &mut self.dcs {
+            if data.len() < DCS_MAX_BYTES {
+                data.push(byte);
+            } else {
+                // Oversized: drop the whole request rather than answer a truncated one
+                self.dcs = None;
+            }
+        }
+    }
+
+    fn unhook(&mut self) {
+        if let Some(image) = self.sixel.take().and_then(SixelDecoder::finish) {
+            // Place the image at the cursor, then move the cursor to the
+            // line below it (scrolling as needed), as xterm does
+            let mut grid = self.grid.lock();
+            let size = (image.width as f32, image.height as f32);
+            let col = grid.cursor_col;
+            let (_, rows) = grid.place_image(Arc::new(image), size);
+            for _ in 0..rows {
+                grid.newline();
+            }
+            grid.cursor_col = col;
+            return;
+        }
+        let Some((kind, data)) = self.dcs.take() else {
+            return;
+        };
+        let mut grid = self.grid.lock();
+        match kind {
+            DcsKind::Decrqss => {
+                let response = decrqss_response(&grid, &data);
+                grid.response_queue.push(response);
+            }
+            DcsKind::Xtgettcap => {
+                grid.response_queue.extend(xtgettcap_responses(&data));
+            }
+        }
+    }
 
-    fn osc_dispatch(&mut self, params: &[&[u8]], _bell_terminated: bool) {
+    fn osc_dispatch(&mut self, params: &[&[u8]], bell_terminated: bool) {
         if params.is_empty() {
             return;
         }
@@ -108,6 +263,91 @@ impl Perform for VtePerformer {
                     }
                 }
             }
+            b"7" => {
+                // Working directory: OSC 7 ; file://host/path ST
+                if params.len() < 2 {
+                    return;
+                }
+                let uri = params[1..].join(&b';');
+                if let Some(cwd) = std::str::from_utf8(&uri).ok().and_then(parse_osc7_cwd) {
+                    self.grid.lock().cwd = Some(cwd);
+                }
+            }
+            b"8" => {
+                // Hyperlink: OSC 8 ; params ; URI ST. The URI itself may
+                // contain ';', which vte has split into further params.
+                if params.len() < 3 {
+                    return;
+                }
+                let uri = params[2..].join(&b';');
+                let mut grid = self.grid.lock();
+                if uri.is_empty() {
+                    grid.current_link = 0;
+                    return;
+                }
+                let Ok(uri) = std::str::from_utf8(&uri) else {
+                    return;
+                };
+                let id = std::str::from_utf8(params[1])
+                    .ok()
+                    .and_then(|p| p.split(':').find_map(|kv| kv.strip_prefix("id=")));
+                grid.current_link = grid.intern_hyperlink(id, uri);
+            }
+            b"4" | b"10" | b"11" | b"12" | b"104" | b"110" | b"111" | b"112" => {
+                let mut grid = self.grid.lock();
+                color_osc(&mut grid, params, bell_terminated);
+            }
+            b"133" => {
+                // Shell integration (FinalTerm): OSC 133 ; A|B|C|D [; ...] ST
+                let Some(mark) = params.get(1).and_then(|p| p.first()) else {
+                    return;
+                };
+                let mut grid = self.grid.lock();
+                match mark {
+                    b'A' => grid.begin_shell_command(),
+                    b'B' => grid.current_zone = SemanticZone::Input,
+                    b'C' => grid.current_zone = SemanticZone::Output,
+                    b'D' => {
+                        let exit_code = params
+                            .get(2)
+                            .and_then(|p| std::str::from_utf8(p).ok())
+                            .and_then(|p| p.parse().ok());
+                        grid.finish_shell_command(exit_code);
+                    }
+                    _ => {}
+                }
+            }
+            b"1337" => {
+                // iTerm2 inline image: OSC 1337 ; File=[args]:base64 ST. The
+                // arguments are ';'-separated, so vte has split them up.
+                let data = params[1..].join(&b';');
+                if let Some(file) = data.strip_prefix(b"File=") {
+                    iterm::place_file(&mut self.grid.lock(), file);
+                }
+            }
+            b"52" => {
+                // Clipboard: OSC 52 ; Pc ; Pd ST (Pd is base64 or '?')
+                if params.len() < 3 {
+                    return;
+                }
+                let mut grid = self.grid.lock();
+                let max_bytes = grid.clipboard_max_bytes;
+                if let Some(request) = parse_osc52(params[1], params[2], bell_terminated, max_bytes) {
+                    grid.clipboard_requests.push(request);
+                }
+            }
+            b"9" | b"777" => {
+                // Desktop notification: OSC 9 ; message ST or
+                // OSC 777 ; notify ; title ; body ST
+                let notification = if params[0] == b"9" {
+                    parse_osc9(&params[1..])
+                } else {
+                    parse_osc777(&params[1..])
+                };
+                if let Some(notification) = notification {
+                    self.grid.lock().notifications.push(notification);
+                }
+            }
             _ => {}
         }
     }
@@ -150,45 +390,43 @@ impl Perform for VtePerformer {
                 grid.cursor_row = (grid.cursor_row + n).min(rows - 1);
                 grid.pending_wrap = false;
             }
-            // Cursor forward
+            // Cursor forward, stopping at the right margin from inside it
             (None, 'C') => {
                 let n = ps.first().copied().unwrap_or(1).max(1) as usize;
-                grid.cursor_col = (grid.cursor_col + n).min(cols - 1);
+                let right = if grid.cursor_col <= grid.scroll_right { grid.scroll_right } else { cols - 1 };
+                grid.cursor_col = (grid.cursor_col + n).min(right);
                 grid.pending_wrap = false;
             }
-            // Cursor back
+            // Cursor back, stopping at the left margin from inside it
             (None, 'D') => {
                 let n = ps.first().copied().unwrap_or(1).max(1) as usize;
-                grid.cursor_col = grid.cursor_col.saturating_sub(n);
+                let left = if grid.cursor_col >= grid.scroll_left { grid.scroll_left } else { 0 };
+                grid.cursor_col = grid.cursor_col.saturating_sub(n).max(left);
                 grid.pending_wrap = false;
             }
             // Cursor Next Line
             (None, 'E') => {
                 let n = ps.first().copied().unwrap_or(1).max(1) as usize;
                 grid.cursor_row = (grid.cursor_row + n).min(rows - 1);
-                grid.cursor_col = 0;
-                grid.pending_wrap = false;
+                grid.carriage_return();
             }
             // Cursor Previous Line
             (None, 'F') => {
                 let n = ps.first().copied().unwrap_or(1).max(1) as usize;
                 grid.cursor_row = grid.cursor_row.saturating_sub(n);
-                grid.cursor_col = 0;
-                grid.pending_wrap = false;
+                grid.carriage_return();
             }
             // Cursor Horizontal Absolute (CHA)
             (None, 'G') | (None, '`') => {
                 let n = ps.first().copied().unwrap_or(1).max(1) as usize;
-                grid.cursor_col = (n - 1).min(cols - 1);
-                grid.pending_wrap = false;
+                self.move_cursor_to(&mut grid, None, Some(n - 1));
             }
-            // Cursor Position (row, col — 1-indexed)
+            // Cursor Position (row, col — 1-indexed, relative to the
+            // margins in origin mode)
             (None, 'H') | (None, 'f') => {
                 let row = ps.first().copied().unwrap_or(1).max(1) as usize;
                 let col = ps.get(1).copied().unwrap_or(1).max(1) as usize;
-                grid.cursor_row = (row - 1).min(rows - 1);
-                grid.cursor_col = (col - 1).min(cols - 1);
-                grid.pending_wrap = false;
+                self.move_cursor_to(&mut grid, Some(row - 1), Some(col - 1));
             }
             // Erase in Display
             (None, 'J') => {
@@ -198,28 +436,22 @@ impl Perform for VtePerformer {
                     0 => {
                         // Erase from cursor to end
                         grid.clear_line_range(cr, cc, cols);
-                        for r in (cr + 1)..rows {
-                            grid.clear_line(r);
-                        }
+                        grid.erase_rows(cr + 1..rows);
                     }
                     1 => {
                         // Erase from start to cursor
-                        for r in 0..cr {
-                            grid.clear_line(r);
-                        }
+                        grid.erase_rows(0..cr);
                         grid.clear_line_range(cr, 0, cc + 1);
                     }
                     2 => {
-                        for r in 0..rows {
-                            grid.clear_line(r);
-                        }
+                        grid.erase_rows(0..rows);
+                        grid.clear_visible_images();
                     }
                     3 => {
                         // Clear scrollback + visible
-                        grid.scrollback.clear();
-                        for r in 0..rows {
-                            grid.clear_line(r);
-                        }
+                        grid.clear_scrollback();
+                        grid.erase_rows(0..rows);
+                        grid.clear_visible_images();
                     }
                     _ => {}
                 }
@@ -238,27 +470,17 @@ impl Perform for VtePerformer {
             // Insert Lines
             (None, 'L') => {
                 let n = ps.first().copied().unwrap_or(1).max(1) as usize;
-                grid.scroll_down_region(n);
+                grid.insert_lines(n);
             }
             // Delete Lines
             (None, 'M') => {
                 let n = ps.first().copied().unwrap_or(1).max(1) as usize;
-                grid.scroll_up_region(n);
+                grid.delete_lines(n);
             }
             // Delete Characters
             (None, 'P') => {
                 let n = ps.first().copied().unwrap_or(1).max(1) as usize;
-                let cr = grid.cursor_row;
-                let cc = grid.cursor_col;
-                let end = cols;
-                let row = &mut grid.cells[cr];
-                let shift = n.min(end - cc);
-                for i in cc..(end - shift) {
-                    row[i] = row[i + shift];
-                }
-                for i in (end - shift)..end {
-                    row[i] = Cell::default();
-                }
+                grid.delete_chars(n);
             }
             // Erase Characters
             (None, 'X') => {
@@ -271,19 +493,7 @@ impl Perform for VtePerformer {
             // Insert Characters (ICH)
             (None, '@') => {
                 let n = ps.first().copied().unwrap_or(1).max(1) as usize;
-                let cr = grid.cursor_row;
-                let cc = grid.cursor_col;
-                let shift = n.min(cols - cc);
-                let row = &mut grid.cells[cr];
-                // Shift existing characters right to make room
-                for i in (cc + shift..cols).rev() {
-                    row[i] = row[i - shift];
-                }
-                // Clear the inserted positions
-                for i in cc..(cc + shift).min(cols) {
-                    row[i] = Cell::default();
-                }
-                grid.generation = grid.generation.wrapping_add(1);
+                grid.insert_chars(n);
             }
             // Vertical Position Absolute (VPA) — CSI Pn d
             // Moves cursor to absolute row Pn (1-based) without changing column.
@@ -291,8 +501,7 @@ impl Perform for VtePerformer {
             // cursor at the input-box row after rendering the full UI.
             (None, 'd') => {
                 let n = ps.first().copied().unwrap_or(1).max(1) as usize;
-                grid.cursor_row = (n - 1).min(rows - 1);
-                grid.pending_wrap = false;
+                self.move_cursor_to(&mut grid, Some(n - 1), None);
             }
             // Horizontal Position Relative (HPR) — CSI Pn a
             (None, 'a') => {
@@ -326,6 +535,24 @@ impl Perform for VtePerformer {
                     }
                 }
             }
+            // Cursor Forward Tabulation (CHT)
+            (None, 'I') => {
+                let n = ps.first().copied().unwrap_or(1).max(1) as usize;
+                grid.tab_forward(n);
+            }
+            // Cursor Backward Tabulation (CBT)
+            (None, 'Z') => {
+                let n = ps.first().copied().unwrap_or(1).max(1) as usize;
+                grid.tab_backward(n);
+            }
+            // Tab Clear (TBC)
+            (None, 'g') => {
+                match ps.first().copied().unwrap_or(0) {
+                    0 => grid.clear_tab_stop(),
+                    3 => grid.clear_all_tab_stops(),
+                    _ => {}
+                }
+            }
             // Scroll Up
             (None, 'S') => {
                 let n = ps.first().copied().unwrap_or(1).max(1) as usize;
@@ -345,24 +572,114 @@ impl Perform for VtePerformer {
                 // Ensure top <= bottom per ANSI spec; ignore invalid regions
                 grid.scroll_top = top_idx.min(bottom_idx);
                 grid.scroll_bottom = top_idx.max(bottom_idx);
-                grid.cursor_row = if self.origin_mode { grid.scroll_top } else { 0 };
-                grid.cursor_col = 0;
-                grid.pending_wrap = false;
+                self.move_cursor_to(&mut grid, Some(0), Some(0));
+            }
+            // Set Left and Right Margins (DECSLRM), only in DECLRMM;
+            // otherwise `CSI s` saves the cursor
+            (None, 's') if self.lr_margin_mode => {
+                let left = ps.first().copied().unwrap_or(1).max(1) as usize;
+                let right = ps.get(1).copied().filter(|&r| r > 0).unwrap_or(cols as u16) as usize;
+                let right = right.min(cols);
+                if left < right {
+                    grid.scroll_left = left - 1;
+                    grid.scroll_right = right - 1;
+                    self.move_cursor_to(&mut grid, Some(0), Some(0));
+                }
             }
             // SGR — Select Graphic Rendition
             (None, 'm') => {
-                apply_sgr(&mut grid.current_attrs, &ps);
+                let mut sgr_buf: [&[u16]; 32] = [&[]; 32];
+                let mut sgr_len = 0;
+                for param in params.iter().take(sgr_buf.len()) {
+                    sgr_buf[sgr_len] = param;
+                    sgr_len += 1;
+                }
+                apply_sgr(&mut grid.current_attrs, &sgr_buf[..sgr_len]);
             }
             // Save cursor (ANSI)
             (None, 's') => {
-                self.saved_cursor = Some((grid.cursor_row, grid.cursor_col));
+                self.saved_cursor = Some(SavedCursor::new(&grid, self.charsets, self.origin_mode));
             }
             // Restore cursor (ANSI)
             (None, 'u') => {
-                if let Some((row, col)) = self.saved_cursor {
-                    grid.cursor_row = row.min(rows - 1);
-                    grid.cursor_col = col.min(cols - 1);
+                if let Some(saved) = self.saved_cursor {
+                    grid.cursor_row = saved.row.min(rows - 1);
+                    grid.cursor_col = saved.col.min(cols - 1);
                     grid.pending_wrap = false;
+                    self.charsets = saved.charsets;
+                    self.origin_mode = saved.origin_mode;
+                }
+            }
+            // xterm modifyOtherKeys: CSI > 4 ; level m sets it, CSI > 4 n
+            // turns it off
+            (Some(b'>'), 'm') if ps.first() == Some(&4) => {
+                grid.modify_other_keys = ps.get(1).copied().unwrap_or(0).min(2) as u8;
+            }
+            (Some(b'>'), 'n') if ps.first() == Some(&4) => {
+                grid.modify_other_keys = 0;
+            }
+            // Set cursor style (DECSCUSR): 0 is the configured default, then
+            // blinking/steady pairs of block, underline and bar
+            (Some(b' '), 'q') if ps.first().is_none_or(|&p| p <= 6) => {
+                let ps = ps.first().copied().unwrap_or(0);
+                let shape = match ps {
+                    0 => None,
+                    1 | 2 => Some(CursorShape::Block),
+                    3 | 4 => Some(CursorShape::Underline),
+                    _ => Some(CursorShape::Bar),
+                };
+                grid.cursor_shape = shape;
+                grid.cursor_blinking = shape.map(|_| ps % 2 == 1);
+            }
+            // Kitty keyboard protocol: push, pop, set and query the flags
+            (Some(b'>'), 'u') => {
+                let flags = ps.first().copied().unwrap_or(0);
+                grid.push_keyboard_flags(flags.min(255) as u8);
+            }
+            (Some(b'<'), 'u') => {
+                let n = ps.first().copied().unwrap_or(1).max(1);
+                grid.pop_keyboard_flags(n as usize);
+            }
+            (Some(b'='), 'u') => {
+                let flags = ps.first().copied().unwrap_or(0);
+                let mode = ps.get(1).copied().unwrap_or(1);
+                grid.set_keyboard_flags(flags.min(255) as u8, mode);
+            }
+            (Some(b'?'), 'u') => {
+                let response = format!("\x1b[?{}u", grid.keyboard_flags().0);
+                grid.response_queue.push(response.into_bytes());
+            }
+            // Window operations (XTWINOPS): size reports and the title stack.
+            // Only the window title is tracked, so icon-title (1) pushes and
+            // pops are ignored.
+            (None, 't') => {
+                let (cell_w, cell_h) = grid.cell_size;
+                let report = match ps.first().copied().unwrap_or(0) {
+                    // Window state: never iconified
+                    11 => Some("\x1b[1t".to_string()),
+                    // Text area size in pixels
+                    14 => Some(format!(
+                        "\x1b[4;{};{}t",
+                        (rows as f32 * cell_h).round() as u32,
+                        (cols as f32 * cell_w).round() as u32,
+                    )),
+                    // Cell size in pixels
+                    16 => Some(format!("\x1b[6;{};{}t", cell_h.round() as u32, cell_w.round() as u32)),
+                    // Text area and screen size in characters
+                    18 => Some(format!("\x1b[8;{};{}t", rows, cols)),
+                    19 => Some(format!("\x1b[9;{};{}t", rows, cols)),
+                    22 if ps.get(1).copied().unwrap_or(0) != 1 => {
+                        grid.push_title();
+                        None
+                    }
+                    23 if ps.get(1).copied().unwrap_or(0) != 1 => {
+                        grid.pop_title();
+                        None
+                    }
+                    _ => None,
+                };
+                if let Some(report) = report {
+                    grid.response_queue.push(report.into_bytes());
                 }
             }
             // DEC private modes
@@ -370,19 +687,43 @@ impl Perform for VtePerformer {
                 for p in ps {
                     match p {
                         1 => { grid.application_cursor_keys = true; }
+                        5 => {
+                            grid.reverse_video = true;
+                            grid.generation = grid.generation.wrapping_add(1);
+                        }
+                        6 => {
+                            self.origin_mode = true;
+                            self.move_cursor_to(&mut grid, Some(0), Some(0));
+                        }
                         7 => { self.auto_wrap = true; }
+                        12 => { grid.cursor_blinking = Some(true); }
                         25 => { grid.cursor_visible = true; }
+                        // Alternate screen, contents kept from last use
+                        47 | 1047 => { grid.enter_alt_screen(); }
+                        // Save cursor (as DECSC)
+                        1048 => {
+                            self.saved_cursor = Some(SavedCursor::new(&grid, self.charsets, self.origin_mode));
+                        }
                         1049 => {
-                            // Alternate screen: save cursor, clear, reset margins
-                            self.saved_cursor = Some((grid.cursor_row, grid.cursor_col));
-                            for r in 0..rows { grid.clear_line(r); }
-                            grid.cursor_row = 0;
-                            grid.cursor_col = 0;
+                            // Save cursor, switch to a cleared alternate screen, reset margins
+                            self.saved_cursor = Some(SavedCursor::new(&grid, self.charsets, self.origin_mode));
+                            grid.enter_alt_screen();
+                            grid.erase_rows(0..rows);
+                            grid.clear_visible_images();
                             grid.scroll_top = 0;
                             grid.scroll_bottom = rows.saturating_sub(1);
-                            grid.pending_wrap = false;
                         }
+                        1000 => { grid.mouse_tracking = MouseTracking::Click; }
+                        1002 => { grid.mouse_tracking = MouseTracking::ButtonMotion; }
+                        1003 => { grid.mouse_tracking = MouseTracking::AnyMotion; }
+                        1006 => { grid.mouse_encoding = MouseEncoding::Sgr; }
+                        1015 => { grid.mouse_encoding = MouseEncoding::Urxvt; }
                         2004 => { grid.bracketed_paste = true; }
+                        69 => { self.lr_margin_mode = true; }
+                        2026 => {
+                            self.synchronized_output = true;
+                            self.sync_start = true;
+                        }
                         _ => {}
                     }
                 }
@@ -391,27 +732,105 @@ impl Perform for VtePerformer {
                 for p in ps {
                     match p {
                         1 => { grid.application_cursor_keys = false; }
+                        5 => {
+                            grid.reverse_video = false;
+                            grid.generation = grid.generation.wrapping_add(1);
+                        }
+                        6 => {
+                            self.origin_mode = false;
+                            self.move_cursor_to(&mut grid, Some(0), Some(0));
+                        }
                         7 => { self.auto_wrap = false; }
+                        12 => { grid.cursor_blinking = Some(false); }
+                        69 => {
+                            self.lr_margin_mode = false;
+                            grid.scroll_left = 0;
+                            grid.scroll_right = cols - 1;
+                        }
                         25 => { grid.cursor_visible = false; }
+                        1000 | 1002 | 1003 => { grid.mouse_tracking = MouseTracking::Off; }
+                        1006 if grid.mouse_encoding == MouseEncoding::Sgr => {
+                            grid.mouse_encoding = MouseEncoding::Default;
+                        }
+                        1015 if grid.mouse_encoding == MouseEncoding::Urxvt => {
+                            grid.mouse_encoding = MouseEncoding::Default;
+                        }
                         2004 => { grid.bracketed_paste = false; }
+                        2026 => { self.synchronized_output = false; }
+                        47 => { grid.exit_alt_screen(); }
+                        1047 => {
+                            // Clear the alternate screen on the way out
+                            if grid.alt_screen {
+                                grid.erase_rows(0..rows);
+                                grid.clear_visible_images();
+                            }
+                            grid.exit_alt_screen();
+                        }
+                        // Restore cursor (as DECRC)
+                        1048 => {
+                            if let Some(saved) = self.saved_cursor {
+                                grid.cursor_row = saved.row.min(rows - 1);
+                                grid.cursor_col = saved.col.min(cols - 1);
+                                grid.pending_wrap = false;
+                                self.charsets = saved.charsets;
+                                self.origin_mode = saved.origin_mode;
+                            }
+                        }
                         1049 => {
-                            // Exit alternate screen: clear, restore cursor & margins
-                            for r in 0..rows { grid.clear_line(r); }
-                            if let Some((row, col)) = self.saved_cursor {
-                                grid.cursor_row = row.min(rows - 1);
-                                grid.cursor_col = col.min(cols - 1);
-                            } else {
-                                grid.cursor_row = 0;
-                                grid.cursor_col = 0;
+                            // Back to the primary screen: restore cursor & margins
+                            grid.exit_alt_screen();
+                            if let Some(saved) = self.saved_cursor {
+                                grid.cursor_row = saved.row.min(rows - 1);
+                                grid.cursor_col = saved.col.min(cols - 1);
+                                self.charsets = saved.charsets;
+                                self.origin_mode = saved.origin_mode;
                             }
                             grid.scroll_top = 0;
                             grid.scroll_bottom = rows.saturating_sub(1);
-                            grid.pending_wrap = false;
                         }
                         _ => {}
                     }
                 }
             }
+            // Soft terminal reset (DECSTR)
+            (Some(b'!'), 'p') => {
+                grid.soft_reset();
+                // DECRC now homes the cursor with default charsets and DECOM off
+                self.saved_cursor = Some(SavedCursor::default());
+                self.charsets = Charsets::default();
+                self.origin_mode = false;
+                self.lr_margin_mode = false;
+                self.auto_wrap = true;
+            }
+            // Request DEC private mode (DECRQM): 1 set, 2 reset, 0 unknown
+            (Some(b'?'), 'p') if intermediates == b"?$" => {
+                let mode = ps.first().copied().unwrap_or(0);
+                let set = match mode {
+                    1 => Some(grid.application_cursor_keys),
+                    5 => Some(grid.reverse_video),
+                    6 => Some(self.origin_mode),
+                    7 => Some(self.auto_wrap),
+                    12 => Some(grid.cursor_blinking == Some(true)),
+                    25 => Some(grid.cursor_visible),
+                    69 => Some(self.lr_margin_mode),
+                    47 | 1047 | 1049 => Some(grid.alt_screen),
+                    1000 => Some(grid.mouse_tracking == MouseTracking::Click),
+                    1002 => Some(grid.mouse_tracking == MouseTracking::ButtonMotion),
+                    1003 => Some(grid.mouse_tracking == MouseTracking::AnyMotion),
+                    1006 => Some(grid.mouse_encoding == MouseEncoding::Sgr),
+                    1015 => Some(grid.mouse_encoding == MouseEncoding::Urxvt),
+                    2004 => Some(grid.bracketed_paste),
+                    2026 => Some(self.synchronized_output),
+                    _ => None,
+                };
+                let pm = match set {
+                    Some(true) => 1,
+                    Some(false) => 2,
+                    None => 0,
+                };
+                let response = format!("\x1b[?{};{}$y", mode, pm);
+                grid.response_queue.push(response.into_bytes());
+            }
             // Send Device Attributes (DA) — respond with VT220 identity
             (None, 'c') | (Some(b'?'), 'c') => {
                 // ESC[?62;4c  → VT220 with Sixel support flag
@@ -426,11 +845,13 @@ impl Perform for VtePerformer {
                         grid.response_queue.push(b"\x1b[0n".to_vec());
                     }
                     6 => {
-                        // Cursor position report (1-based)
+                        // Cursor position report (1-based, relative to the
+                        // margins in origin mode)
+                        let (top, left) = self.origin(&grid);
                         let response = format!(
                             "\x1b[{};{}R",
-                            grid.cursor_row + 1,
-                            grid.cursor_col + 1
+                            grid.cursor_row.saturating_sub(top) + 1,
+                            grid.cursor_col.saturating_sub(left) + 1
                         );
                         grid.response_queue.push(response.into_bytes());
                     }
@@ -449,14 +870,16 @@ impl Perform for VtePerformer {
         match (intermediates.first().copied(), byte) {
             // Save cursor (DECSC)
             (None, b'7') => {
-                self.saved_cursor = Some((grid.cursor_row, grid.cursor_col));
+                self.saved_cursor = Some(SavedCursor::new(&grid, self.charsets, self.origin_mode));
             }
             // Restore cursor (DECRC)
             (None, b'8') => {
-                if let Some((row, col)) = self.saved_cursor {
-                    grid.cursor_row = row.min(grid.rows - 1);
-                    grid.cursor_col = col.min(grid.cols - 1);
+                if let Some(saved) = self.saved_cursor {
+                    grid.cursor_row = saved.row.min(grid.rows - 1);
+                    grid.cursor_col = saved.col.min(grid.cols - 1);
                     grid.pending_wrap = false;
+                    self.charsets = saved.charsets;
+                    self.origin_mode = saved.origin_mode;
                 }
             }
             // Index (IND)
@@ -469,9 +892,13 @@ impl Perform for VtePerformer {
             (None, b'E') => {
                 drop(grid);
                 let mut grid = self.grid.lock();
-                grid.cursor_col = 0;
+                grid.carriage_return();
                 grid.newline();
             }
+            // Horizontal Tab Set (HTS)
+            (None, b'H') => {
+                grid.set_tab_stop();
+            }
             // Reverse Index (RI)
             (None, b'M') => {
                 if grid.cursor_row == grid.scroll_top {
@@ -480,77 +907,223 @@ impl Perform for VtePerformer {
                     grid.cursor_row -= 1;
                 }
             }
+            // Full reset (RIS)
+            (None, b'c') => {
+                drop(grid);
+                self.reset();
+            }
+            // Single shifts (SS2 / SS3) and locking shifts (LS2 / LS3)
+            (None, b'N') => self.charsets.single_shift(2),
+            (None, b'O') => self.charsets.single_shift(3),
+            (None, b'n') => self.charsets.lock_shift(2),
+            (None, b'o') => self.charsets.lock_shift(3),
+            // Line size: DECDHL top/bottom half, DECSWL, DECDWL
+            (Some(b'#'), b'3') => grid.set_line_size(LineSize::DoubleHeightTop),
+            (Some(b'#'), b'4') => grid.set_line_size(LineSize::DoubleHeightBottom),
+            (Some(b'#'), b'5') => grid.set_line_size(LineSize::Single),
+            (Some(b'#'), b'6') => grid.set_line_size(LineSize::DoubleWidth),
+            // Screen alignment test (DECALN)
+            (Some(b'#'), b'8') => grid.fill_alignment_pattern(),
+            // Designate a character set into G0–G3 (SCS)
+            (Some(slot @ b'('..=b'+'), designator) => {
+                if let Some(charset) = Charset::from_designator(designator) {
+                    self.charsets.designate((slot - b'(') as usize, charset);
+                }
+            }
             _ => {}
         }
     }
 }
 
-pub(crate) fn apply_sgr(attrs: &mut CellAttributes, params: &[u16]) {
+/// Handle the color OSCs: set or query palette entries (4) and the dynamic
+/// foreground/background/cursor colors (10/11/12), or reset them
+/// (104, 110/111/112). Queries are answered in xterm's `rgb:` format.
+fn color_osc(grid: &mut TerminalGrid, params: &[&[u8]], bell_terminated: bool) {
+    let terminator = if bell_terminated { "\x07" } else { "\x1b\\" };
+    let number = |p: &[u8]| std::str::from_utf8(p).ok().and_then(|s| s.parse::<u16>().ok());
+    let Some(code) = number(params[0]) else {
+        return;
+    };
+    let mut changed = false;
+    match code {
+        4 => {
+            // OSC 4 ; index ; spec [; index ; spec ...]
+            for pair in params[1..].chunks(2) {
+                let [index, spec] = pair else { break };
+                let Some(index) = number(index).and_then(|i| u8::try_from(i).ok()) else {
+                    continue;
+                };
+                if *spec == b"?" {
+                    let color = format_color_spec(grid.colors.palette_color(index));
+                    let reply = format!("\x1b]4;{};{}{}", index, color, terminator);
+                    grid.response_queue.push(reply.into_bytes());
+                } else if let Some(color) = std::str::from_utf8(spec).ok().and_then(parse_color_spec) {
+                    grid.colors.set_palette_color(index, Some(color));
+                    changed = true;
+                }
+            }
+        }
+        10..=12 => {
+            // Each further param addresses the next dynamic color (OSC 10 ; fg ; bg)
+            for (code, spec) in (code..=12).zip(&params[1..]) {
+                if *spec == b"?" {
+                    let color = match code {
+                        10 => grid.colors.foreground(),
+                        11 => grid.colors.background(),
+                        _ => grid.colors.cursor(),
+                    };
+                    let reply = format!("\x1b]{};{}{}", code, format_color_spec(color), terminator);
+                    grid.response_queue.push(reply.into_bytes());
+                } else if let Some(color) = std::str::from_utf8(spec).ok().and_then(parse_color_spec) {
+                    match code {
+                        10 => grid.colors.set_foreground(Some(color)),
+                        11 => grid.colors.set_background(Some(color)),
+                        _ => grid.colors.set_cursor(Some(color)),
+                    }
+                    changed = true;
+                }
+            }
+        }
+        104 => {
+            let indices: Vec<u8> = params[1..]
+                .iter()
+                .filter_map(|p| number(p).and_then(|i| u8::try_from(i).ok()))
+                .collect();
+            // No indices: reset the whole palette
+            if params[1..].iter().all(|p| p.is_empty()) {
+                grid.colors.reset_palette();
+            }
+            for index in indices {
+                grid.colors.set_palette_color(index, None);
+            }
+            changed = true;
+        }
+        110 => { grid.colors.set_foreground(None); changed = true; }
+        111 => { grid.colors.set_background(None); changed = true; }
+        112 => { grid.colors.set_cursor(None); changed = true; }
+        _ => {}
+    }
+    if changed {
+        grid.generation = grid.generation.wrapping_add(1);
+    }
+}
+
+/// Apply SGR parameters. Each entry holds a parameter and its
+/// colon-separated subparameters, so both `38;2;r;g;b` and `38:2::r:g:b`
+/// are understood.
+pub(crate) fn apply_sgr(attrs: &mut CellAttributes, params: &[&[u16]]) {
     let mut i = 0;
     if params.is_empty() {
         *attrs = CellAttributes::default();
         return;
     }
     while i < params.len() {
-        match params[i] {
+        let param = params[i];
+        match param[0] {
             0 => *attrs = CellAttributes::default(),
             1 => attrs.bold = true,
             2 => attrs.dim = true,
             3 => attrs.italic = true,
-            4 => attrs.underline = true,
+            4 => {
+                attrs.underline = match param.get(1) {
+                    None | Some(1) => UnderlineStyle::Single,
+                    Some(0) => UnderlineStyle::None,
+                    Some(2) => UnderlineStyle::Double,
+                    Some(3) => UnderlineStyle::Curly,
+                    Some(4) => UnderlineStyle::Dotted,
+                    Some(5) => UnderlineStyle::Dashed,
+                    Some(_) => attrs.underline,
+                };
+            }
             5 | 6 => attrs.blink = true,
             7 => attrs.reverse = true,
             8 => attrs.invisible = true,
             9 => attrs.strikethrough = true,
+            21 => attrs.underline = UnderlineStyle::Double,
             22 => { attrs.bold = false; attrs.dim = false; }
             23 => attrs.italic = false,
-            24 => attrs.underline = false,
+            24 => attrs.underline = UnderlineStyle::None,
             25 => attrs.blink = false,
             27 => attrs.reverse = false,
             28 => attrs.invisible = false,
             29 => attrs.strikethrough = false,
             // Foreground colors (30-37 → palette 0-7)
-            30..=37 => attrs.fg = Color::Indexed(params[i] as u8 - 30),
+            30..=37 => attrs.fg = Color::Indexed(param[0] as u8 - 30),
             38 => {
-                if i + 1 < params.len() && params[i + 1] == 5 && i + 2 < params.len() {
-                    attrs.fg = Color::Indexed(params[i + 2] as u8);
-                    i += 2;
-                } else if i + 1 < params.len() && params[i + 1] == 2 && i + 4 < params.len() {
-                    attrs.fg = Color::Rgb(params[i+2] as u8, params[i+3] as u8, params[i+4] as u8);
-                    i += 4;
+                if let Some(color) = extended_color(params, &mut i) {
+                    attrs.fg = color;
                 }
             }
             39 => attrs.fg = Color::Default,
             // Background colors (40-47 → palette 0-7, NOT 8-15)
-            40..=47 => attrs.bg = Color::Indexed(params[i] as u8 - 40),
+            40..=47 => attrs.bg = Color::Indexed(param[0] as u8 - 40),
             48 => {
-                if i + 1 < params.len() && params[i + 1] == 5 && i + 2 < params.len() {
-                    attrs.bg = Color::Indexed(params[i + 2] as u8);
-                    i += 2;
-                } else if i + 1 < params.len() && params[i + 1] == 2 && i + 4 < params.len() {
-                    attrs.bg = Color::Rgb(params[i+2] as u8, params[i+3] as u8, params[i+4] as u8);
-                    i += 4;
+                if let Some(color) = extended_color(params, &mut i) {
+                    attrs.bg = color;
                 }
             }
             49 => attrs.bg = Color::Default,
+            58 => {
+                if let Some(color) = extended_color(params, &mut i) {
+                    attrs.underline_color = color;
+                }
+            }
+            59 => attrs.underline_color = Color::Default,
             // Bright foreground (90-97 → palette 8-15)
-            90..=97 => attrs.fg = Color::Indexed(params[i] as u8 - 90 + 8),
+            90..=97 => attrs.fg = Color::Indexed(param[0] as u8 - 90 + 8),
             // Bright background (100-107 → palette 8-15, NOT 16-23)
-            100..=107 => attrs.bg = Color::Indexed(params[i] as u8 - 100 + 8),
+            100..=107 => attrs.bg = Color::Indexed(param[0] as u8 - 100 + 8),
             _ => {}
         }
         i += 1;
     }
 }
 
+/// Read the color after SGR 38/48/58 at `params[*i]`. The colon form keeps
+/// everything in one parameter (`38:5:n`, `38:2:cs:r:g:b`, or `38:2:r:g:b`
+/// without the color space); the semicolon form spreads it over the
+/// following parameters, which are consumed by advancing `i`.
+fn extended_color(params: &[&[u16]], i: &mut usize) -> Option<Color> {
+    let param = params[*i];
+    if param.len() > 1 {
+        return match param[1..] {
+            [5, n] => Some(Color::Indexed(n as u8)),
+            [2, _, r, g, b] | [2, r, g, b] => Some(Color::Rgb(r as u8, g as u8, b as u8)),
+            _ => None,
+        };
+    }
+    let next = |n: usize| params.get(*i + n).map(|p| p[0]);
+    match next(1) {
+        Some(5) => {
+            let n = next(2)?;
+            *i += 2;
+            Some(Color::Indexed(n as u8))
+        }
+        Some(2) => {
+            let (r, g, b) = (next(2)?, next(3)?, next(4)?);
+            *i += 4;
+            Some(Color::Rgb(r as u8, g as u8, b as u8))
+        }
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::terminal::clipboard::ClipboardRequest;
+    use crate::terminal::notification::Notification;
 
     fn fresh() -> CellAttributes {
         CellAttributes::default()
     }
 
+    /// Apply semicolon-separated SGR parameters.
+    fn sgr(attrs: &mut CellAttributes, params: &[u16]) {
+        let params: Vec<&[u16]> = params.iter().map(std::slice::from_ref).collect();
+        apply_sgr(attrs, &params);
+    }
+
     // ── SGR reset ───────────────────────────────────────────────────────
 
     #[test]
@@ -558,7 +1131,7 @@ mod tests {
         let mut a = fresh();
         a.bold = true;
         a.fg = Color::Indexed(1);
-        apply_sgr(&mut a, &[0]);
+        sgr(&mut a, &[0]);
         assert_eq!(a, fresh());
     }
 
@@ -566,7 +1139,7 @@ mod tests {
     fn empty_params_reset() {
         let mut a = fresh();
         a.bold = true;
-        apply_sgr(&mut a, &[]);
+        sgr(&mut a, &[]);
         assert_eq!(a, fresh());
     }
 
@@ -575,42 +1148,44 @@ mod tests {
     #[test]
     fn sgr_bold() {
         let mut a = fresh();
-        apply_sgr(&mut a, &[1]);
+        sgr(&mut a, &[1]);
         assert!(a.bold);
     }
 
     #[test]
     fn sgr_italic() {
         let mut a = fresh();
-        apply_sgr(&mut a, &[3]);
+        sgr(&mut a, &[3]);
         assert!(a.italic);
     }
 
     #[test]
     fn sgr_underline() {
         let mut a = fresh();
-        apply_sgr(&mut a, &[4]);
-        assert!(a.underline);
+        sgr(&mut a, &[4]);
+        assert_eq!(a.underline, UnderlineStyle::Single);
+        sgr(&mut a, &[21]);
+        assert_eq!(a.underline, UnderlineStyle::Double);
     }
 
     #[test]
     fn sgr_reverse() {
         let mut a = fresh();
-        apply_sgr(&mut a, &[7]);
+        sgr(&mut a, &[7]);
         assert!(a.reverse);
     }
 
     #[test]
     fn sgr_strikethrough() {
         let mut a = fresh();
-        apply_sgr(&mut a, &[9]);
+        sgr(&mut a, &[9]);
         assert!(a.strikethrough);
     }
 
     #[test]
     fn sgr_dim() {
         let mut a = fresh();
-        apply_sgr(&mut a, &[2]);
+        sgr(&mut a, &[2]);
         assert!(a.dim);
     }
 
@@ -621,7 +1196,7 @@ mod tests {
         let mut a = fresh();
         a.bold = true;
         a.dim = true;
-        apply_sgr(&mut a, &[22]);
+        sgr(&mut a, &[22]);
         assert!(!a.bold);
         assert!(!a.dim);
     }
@@ -630,23 +1205,23 @@ mod tests {
     fn sgr_23_unitalic() {
         let mut a = fresh();
         a.italic = true;
-        apply_sgr(&mut a, &[23]);
+        sgr(&mut a, &[23]);
         assert!(!a.italic);
     }
 
     #[test]
     fn sgr_24_ununderline() {
         let mut a = fresh();
-        a.underline = true;
-        apply_sgr(&mut a, &[24]);
-        assert!(!a.underline);
+        a.underline = UnderlineStyle::Curly;
+        sgr(&mut a, &[24]);
+        assert_eq!(a.underline, UnderlineStyle::None);
     }
 
     #[test]
     fn sgr_27_unreverse() {
         let mut a = fresh();
         a.reverse = true;
-        apply_sgr(&mut a, &[27]);
+        sgr(&mut a, &[27]);
         assert!(!a.reverse);
     }
 
@@ -654,7 +1229,7 @@ mod tests {
     fn sgr_29_unstrikethrough() {
         let mut a = fresh();
         a.strikethrough = true;
-        apply_sgr(&mut a, &[29]);
+        sgr(&mut a, &[29]);
         assert!(!a.strikethrough);
     }
 
@@ -663,23 +1238,23 @@ mod tests {
     #[test]
     fn sgr_30_37_fg_indexed() {
         let mut a = fresh();
-        apply_sgr(&mut a, &[31]); // red
+        sgr(&mut a, &[31]); // red
         assert_eq!(a.fg, Color::Indexed(1));
-        apply_sgr(&mut a, &[37]); // white
+        sgr(&mut a, &[37]); // white
         assert_eq!(a.fg, Color::Indexed(7));
     }
 
     #[test]
     fn sgr_38_5_n_fg_256() {
         let mut a = fresh();
-        apply_sgr(&mut a, &[38, 5, 200]);
+        sgr(&mut a, &[38, 5, 200]);
         assert_eq!(a.fg, Color::Indexed(200));
     }
 
     #[test]
     fn sgr_38_2_rgb_fg() {
         let mut a = fresh();
-        apply_sgr(&mut a, &[38, 2, 255, 128, 0]);
+        sgr(&mut a, &[38, 2, 255, 128, 0]);
         assert_eq!(a.fg, Color::Rgb(255, 128, 0));
     }
 
@@ -687,7 +1262,7 @@ mod tests {
     fn sgr_39_default_fg() {
         let mut a = fresh();
         a.fg = Color::Indexed(5);
-        apply_sgr(&mut a, &[39]);
+        sgr(&mut a, &[39]);
         assert_eq!(a.fg, Color::Default);
     }
 
@@ -696,23 +1271,23 @@ mod tests {
     #[test]
     fn sgr_40_47_bg_indexed() {
         let mut a = fresh();
-        apply_sgr(&mut a, &[41]); // red bg
+        sgr(&mut a, &[41]); // red bg
         assert_eq!(a.bg, Color::Indexed(1));
-        apply_sgr(&mut a, &[47]); // white bg
+        sgr(&mut a, &[47]); // white bg
         assert_eq!(a.bg, Color::Indexed(7));
     }
 
     #[test]
     fn sgr_48_5_n_bg_256() {
         let mut a = fresh();
-        apply_sgr(&mut a, &[48, 5, 100]);
+        sgr(&mut a, &[48, 5, 100]);
         assert_eq!(a.bg, Color::Indexed(100));
     }
 
     #[test]
     fn sgr_48_2_rgb_bg() {
         let mut a = fresh();
-        apply_sgr(&mut a, &[48, 2, 10, 20, 30]);
+        sgr(&mut a, &[48, 2, 10, 20, 30]);
         assert_eq!(a.bg, Color::Rgb(10, 20, 30));
     }
 
@@ -720,7 +1295,7 @@ mod tests {
     fn sgr_49_default_bg() {
         let mut a = fresh();
         a.bg = Color::Indexed(3);
-        apply_sgr(&mut a, &[49]);
+        sgr(&mut a, &[49]);
         assert_eq!(a.bg, Color::Default);
     }
 
@@ -729,18 +1304,18 @@ mod tests {
     #[test]
     fn sgr_90_97_bright_fg() {
         let mut a = fresh();
-        apply_sgr(&mut a, &[90]); // bright black
+        sgr(&mut a, &[90]); // bright black
         assert_eq!(a.fg, Color::Indexed(8));
-        apply_sgr(&mut a, &[97]); // bright white
+        sgr(&mut a, &[97]); // bright white
         assert_eq!(a.fg, Color::Indexed(15));
     }
 
     #[test]
     fn sgr_100_107_bright_bg() {
         let mut a = fresh();
-        apply_sgr(&mut a, &[100]); // bright black bg
+        sgr(&mut a, &[100]); // bright black bg
         assert_eq!(a.bg, Color::Indexed(8));
-        apply_sgr(&mut a, &[107]); // bright white bg
+        sgr(&mut a, &[107]); // bright white bg
         assert_eq!(a.bg, Color::Indexed(15));
     }
 
@@ -749,9 +1324,829 @@ mod tests {
     #[test]
     fn sgr_multiple_params() {
         let mut a = fresh();
-        apply_sgr(&mut a, &[1, 3, 31]); // bold + italic + red fg
+        sgr(&mut a, &[1, 3, 31]); // bold + italic + red fg
         assert!(a.bold);
         assert!(a.italic);
         assert_eq!(a.fg, Color::Indexed(1));
     }
+
+    // ── Escape sequences fed through the parser ─────────────────────────
+
+    fn feed(grid: &Arc<Mutex<TerminalGrid>>, bytes: &[u8]) {
+        let mut performer = VtePerformer::new(grid.clone());
+        let mut parser = vte::Parser::new();
+        parser.advance(&mut performer, bytes);
+    }
+
+    fn grid(cols: usize, rows: usize) -> Arc<Mutex<TerminalGrid>> {
+        Arc::new(Mutex::new(TerminalGrid::new(cols, rows)))
+    }
+
+    // ── Mouse reporting modes ───────────────────────────────────────────
+
+    #[test]
+    fn mouse_tracking_modes() {
+        let g = grid(10, 5);
+        feed(&g, b"\x1b[?1000h");
+        assert_eq!(g.lock().mouse_tracking, MouseTracking::Click);
+        feed(&g, b"\x1b[?1002h");
+        assert_eq!(g.lock().mouse_tracking, MouseTracking::ButtonMotion);
+        feed(&g, b"\x1b[?1003h");
+        assert_eq!(g.lock().mouse_tracking, MouseTracking::AnyMotion);
+        feed(&g, b"\x1b[?1003l");
+        assert_eq!(g.lock().mouse_tracking, MouseTracking::Off);
+    }
+
+    #[test]
+    fn mouse_encoding_modes() {
+        let g = grid(10, 5);
+        feed(&g, b"\x1b[?1000;1006h");
+        assert_eq!(g.lock().mouse_encoding, MouseEncoding::Sgr);
+        // Resetting a different encoding leaves SGR in place
+        feed(&g, b"\x1b[?1015l");
+        assert_eq!(g.lock().mouse_encoding, MouseEncoding::Sgr);
+        feed(&g, b"\x1b[?1006l");
+        assert_eq!(g.lock().mouse_encoding, MouseEncoding::Default);
+        feed(&g, b"\x1b[?1015h");
+        assert_eq!(g.lock().mouse_encoding, MouseEncoding::Urxvt);
+    }
+
+    // ── Alternate screen ────────────────────────────────────────────────
+
+    fn row_text(g: &TerminalGrid, row: usize) -> String {
+        g.cells[row].iter().map(|c| if c.ch == '\0' { ' ' } else { c.ch }).collect::<String>().trim_end().to_string()
+    }
+
+    #[test]
+    fn alt_screen_1049_preserves_primary() {
+        let g = grid(10, 3);
+        let mut performer = VtePerformer::new(g.clone());
+        let mut parser = vte::Parser::new();
+        parser.advance(&mut performer, b"$ ls\r\nfoo");
+        parser.advance(&mut performer, b"\x1b[?1049h");
+        {
+            let grid = g.lock();
+            assert!(grid.alt_screen);
+            assert_eq!(row_text(&grid, 0), "");
+        }
+        parser.advance(&mut performer, b"\x1b[2;5Hvim");
+        parser.advance(&mut performer, b"\x1b[?1049l");
+        let grid = g.lock();
+        assert!(!grid.alt_screen);
+        assert_eq!(row_text(&grid, 0), "$ ls");
+        assert_eq!(row_text(&grid, 1), "foo");
+        assert_eq!((grid.cursor_row, grid.cursor_col), (1, 3));
+    }
+
+    #[test]
+    fn alt_screen_does_not_feed_scrollback() {
+        let g = grid(10, 2);
+        feed(&g, b"\x1b[?1049h1\r\n2\r\n3\r\n4");
+        assert!(g.lock().scrollback.is_empty());
+        feed(&g, b"\x1b[?1049l");
+        let grid = g.lock();
+        assert!(grid.scrollback.is_empty());
+        assert_eq!(row_text(&grid, 0), "");
+    }
+
+    #[test]
+    fn alt_screen_47_keeps_contents() {
+        let g = grid(10, 3);
+        feed(&g, b"main\x1b[?47h\x1b[Halt\x1b[?47l");
+        assert_eq!(row_text(&g.lock(), 0), "main");
+        // Re-entering shows what the alternate screen last held
+        feed(&g, b"\x1b[?47h");
+        assert_eq!(row_text(&g.lock(), 0), "alt");
+    }
+
+    #[test]
+    fn alt_screen_1047_clears_on_exit() {
+        let g = grid(10, 3);
+        feed(&g, b"main\x1b[?1047h\x1b[Halt\x1b[?1047l");
+        assert_eq!(row_text(&g.lock(), 0), "main");
+        feed(&g, b"\x1b[?1047h");
+        assert_eq!(row_text(&g.lock(), 0), "");
+    }
+
+    #[test]
+    fn mode_1048_saves_and_restores_cursor() {
+        let g = grid(10, 5);
+        let mut performer = VtePerformer::new(g.clone());
+        let mut parser = vte::Parser::new();
+        parser.advance(&mut performer, b"\x1b[3;4H\x1b[?1048h\x1b[H\x1b[?1048l");
+        let grid = g.lock();
+        assert_eq!((grid.cursor_row, grid.cursor_col), (2, 3));
+        assert!(!grid.alt_screen);
+    }
+
+    #[test]
+    fn alt_screen_survives_resize() {
+        let g = grid(10, 3);
+        feed(&g, b"main\x1b[?1049h");
+        g.lock().resize(20, 5);
+        feed(&g, b"\x1b[?1049l");
+        assert_eq!(row_text(&g.lock(), 0), "main");
+    }
+
+    // ── Dynamic colors (OSC 4/10/11/12/104/110-112) ────────────────────
+
+    #[test]
+    fn osc4_sets_and_queries_palette() {
+        let g = grid(10, 2);
+        feed(&g, b"\x1b]4;1;rgb:00/ff/00;200;#0000ff\x07");
+        feed(&g, b"\x1b]4;1;?\x1b\\");
+        let grid = g.lock();
+        assert_eq!(grid.colors.palette_color(1), [0.0, 1.0, 0.0, 1.0]);
+        assert_eq!(grid.colors.palette_color(200), [0.0, 0.0, 1.0, 1.0]);
+        assert_eq!(grid.response_queue, vec![b"\x1b]4;1;rgb:0000/ffff/0000\x1b\\".to_vec()]);
+    }
+
+    #[test]
+    fn osc104_resets_palette() {
+        let g = grid(10, 2);
+        let original = g.lock().colors.palette_color(1);
+        feed(&g, b"\x1b]4;1;#123456;2;#123456\x07\x1b]104;1\x07");
+        assert_eq!(g.lock().colors.palette_color(1), original);
+        assert_eq!(g.lock().colors.palette_color(2), [0x12 as f32 / 255.0, 0x34 as f32 / 255.0, 0x56 as f32 / 255.0, 1.0]);
+        feed(&g, b"\x1b]104\x07");
+        assert_ne!(g.lock().colors.palette_color(2)[0], 0x12 as f32 / 255.0);
+    }
+
+    #[test]
+    fn osc10_11_query_defaults() {
+        let g = grid(10, 2);
+        feed(&g, b"\x1b]10;?\x07\x1b]11;?\x07");
+        let grid = g.lock();
+        assert_eq!(grid.response_queue, vec![
+            b"\x1b]10;rgb:ffff/ffff/ffff\x07".to_vec(),
+            b"\x1b]11;rgb:0000/0000/0000\x07".to_vec(),
+        ]);
+    }
+
+    #[test]
+    fn osc10_sets_following_dynamic_colors() {
+        let g = grid(10, 2);
+        let gen = g.lock().generation;
+        feed(&g, b"\x1b]10;#ff0000;#00ff00\x07\x1b]12;#0000ff\x07");
+        {
+            let grid = g.lock();
+            assert_eq!(grid.colors.foreground(), [1.0, 0.0, 0.0, 1.0]);
+            assert_eq!(grid.colors.background(), [0.0, 1.0, 0.0, 1.0]);
+            assert_eq!(grid.colors.cursor(), [0.0, 0.0, 1.0, 1.0]);
+            assert_ne!(grid.generation, gen);
+        }
+        feed(&g, b"\x1b]110\x07\x1b]111\x07\x1b]112\x07");
+        let grid = g.lock();
+        assert_eq!(grid.colors.foreground(), [1.0, 1.0, 1.0, 1.0]);
+        assert_eq!(grid.colors.background(), [0.0, 0.0, 0.0, 1.0]);
+    }
+
+    // ── OSC 7 working directory ────────────────────────────────────────
+
+    #[test]
+    fn osc7_sets_cwd() {
+        let g = grid(10, 2);
+        feed(&g, b"\x1b]7;file://host/home/me/a%3Bb;c\x07");
+        assert_eq!(g.lock().cwd, Some(std::path::PathBuf::from("/home/me/a;b;c")));
+    }
+
+    // ── OSC 52 clipboard ────────────────────────────────────────────────
+
+    #[test]
+    fn osc52_queues_requests() {
+        let g = grid(10, 2);
+        feed(&g, b"\x1b]52;c;aGk=\x07\x1b]52;;?\x1b\\");
+        let grid = g.lock();
+        assert_eq!(grid.clipboard_requests, vec![
+            ClipboardRequest::Write { data: b"aGk=".to_vec() },
+            ClipboardRequest::Read { selection: "c".to_string(), bell_terminated: false },
+        ]);
+    }
+
+    #[test]
+    fn osc52_drops_oversized_writes() {
+        let g = grid(10, 2);
+        g.lock().clipboard_max_bytes = 2;
+        feed(&g, b"\x1b]52;c;aGVsbG8=\x07\x1b]52;c;aGk=\x07");
+        assert_eq!(g.lock().clipboard_requests, vec![ClipboardRequest::Write { data: b"aGk=".to_vec() }]);
+    }
+
+    // ── Bell ────────────────────────────────────────────────────────────
+
+    #[test]
+    fn bel_sets_bell() {
+        let g = grid(10, 2);
+        // A BEL that terminates an OSC string doesn't ring
+        feed(&g, b"\x1b]0;title\x07");
+        assert!(!g.lock().bell);
+        feed(&g, b"a\x07b");
+        assert!(g.lock().bell);
+        assert_eq!(row_text(&g.lock(), 0), "ab");
+    }
+
+    // ── OSC 9 / OSC 777 notifications ───────────────────────────────────
+
+    #[test]
+    fn osc9_and_osc777_queue_notifications() {
+        let g = grid(10, 2);
+        feed(&g, b"\x1b]9;done; 3 warnings\x07\x1b]9;4;1;50\x07\x1b]777;notify;make;ok\x1b\\");
+        let grid = g.lock();
+        assert_eq!(grid.notifications, vec![
+            Notification { title: String::new(), body: "done; 3 warnings".to_string() },
+            Notification { title: "make".to_string(), body: "ok".to_string() },
+        ]);
+    }
+
+    // ── OSC 8 hyperlinks ────────────────────────────────────────────────
+
+    #[test]
+    fn osc8_links_printed_cells() {
+        let g = grid(20, 2);
+        feed(&g, b"a\x1b]8;;https://example.com/a;b\x1b\\link\x1b]8;;\x1b\\z");
+        let grid = g.lock();
+        assert_eq!(grid.cells[0][0].link, 0);
+        let link = grid.cells[0][1].link;
+        assert_ne!(link, 0);
+        assert!(grid.cells[0][1..5].iter().all(|c| c.link == link));
+        assert_eq!(grid.cells[0][5].link, 0);
+        assert_eq!(grid.hyperlinks[link as usize - 1].uri, "https://example.com/a;b");
+    }
+
+    #[test]
+    fn osc8_id_param() {
+        let g = grid(20, 2);
+        feed(&g, b"\x1b]8;foo=1:id=x;file:///tmp\x07a\x1b]8;;\x07");
+        let grid = g.lock();
+        assert_eq!(grid.hyperlinks[0].id.as_deref(), Some("x"));
+    }
+
+    #[test]
+    fn osc8_survives_sgr_reset_and_wraps() {
+        let g = grid(4, 3);
+        feed(&g, b"ab\x1b]8;;https://x.example\x07\x1b[1mcd\x1b[0mef\x1b]8;;\x07");
+        let grid = g.lock();
+        assert_eq!(grid.hyperlink_range(1, 1), Some((0, 2, 1, 1, "https://x.example")));
+    }
+
+    // ── OSC 133 shell integration ───────────────────────────────────────
+
+    #[test]
+    fn osc133_marks_zones() {
+        let g = grid(20, 3);
+        feed(&g, b"\x1b]133;A\x07> \x1b]133;B\x07ls\r\n\x1b]133;C\x07out\r\n\x1b]133;D;2\x07");
+        let grid = g.lock();
+        assert_eq!(grid.cells[0][0].zone, SemanticZone::Prompt);
+        assert_eq!(grid.cells[0][2].zone, SemanticZone::Input);
+        assert_eq!(grid.cells[1][0].zone, SemanticZone::Output);
+        assert_eq!(grid.cells[0][0].command, 1);
+        assert_eq!(grid.cells[1][2].command, 1);
+        assert_eq!(grid.commands[0].exit_code, Some(2));
+        assert_eq!(grid.current_zone, SemanticZone::None);
+    }
+
+    #[test]
+    fn osc133_zones_follow_rows_into_scrollback() {
+        let g = grid(10, 2);
+        feed(&g, b"\x1b]133;A\x1b\\$ \x1b]133;B\x1b\\pwd\r\n\x1b]133;C\x1b\\/tmp\r\n\x1b]133;D;0\x1b\\\x1b]133;A\x1b\\$ ");
+        let grid = g.lock();
+        assert_eq!(grid.scrollback.len(), 1);
+        assert_eq!(grid.scrollback[0][2].zone, SemanticZone::Input);
+        assert_eq!(grid.scrollback[0][2].command, 1);
+        assert_eq!(grid.cells[1][0].command, 2);
+        assert_eq!(grid.commands[0].exit_code, Some(0));
+        assert_eq!(grid.commands[1].exit_code, None);
+    }
+
+    #[test]
+    fn osc133_input_range_ignores_prompt_characters() {
+        // A prompt that ends in "$ " inside the input would fool the heuristic
+        let g = grid(30, 3);
+        feed(&g, b"\x1b]133;A\x07~ \xe2\x9d\xaf \x1b]133;B\x07echo a$ b");
+        let grid = g.lock();
+        assert_eq!(grid.cursor_line_input_range(), Some((0, 4, 0, 12)));
+    }
+
+    // ── Tab stops ───────────────────────────────────────────────────────
+
+    #[test]
+    fn tab_uses_default_stops() {
+        let g = grid(20, 2);
+        feed(&g, b"a\tb");
+        let grid = g.lock();
+        assert_eq!(grid.cells[0][8].ch, 'b');
+    }
+
+    #[test]
+    fn hts_and_tbc() {
+        let g = grid(30, 2);
+        // Clear all, set stops at 4 and 10
+        feed(&g, b"\x1b[3g\x1b[5G\x1bH\x1b[11G\x1bH\r\tA\tB\tC");
+        {
+            let grid = g.lock();
+            assert_eq!(grid.cells[0][4].ch, 'A');
+            assert_eq!(grid.cells[0][10].ch, 'B');
+            // No more stops: the tab stops at the right margin
+            assert_eq!(grid.cells[0][29].ch, 'C');
+        }
+        // Clear the stop at column 4 only
+        feed(&g, b"\x1b[5G\x1b[0g\r\tD");
+        assert_eq!(g.lock().cells[0][10].ch, 'D');
+    }
+
+    #[test]
+    fn cht_and_cbt() {
+        let g = grid(40, 2);
+        feed(&g, b"\x1b[2I");
+        assert_eq!(g.lock().cursor_col, 16);
+        feed(&g, b"\x1b[Z");
+        assert_eq!(g.lock().cursor_col, 8);
+        feed(&g, b"\x1b[5Z");
+        assert_eq!(g.lock().cursor_col, 0);
+    }
+
+    // ── DCS requests ────────────────────────────────────────────────────
+
+    #[test]
+    fn decrqss_reports_margins() {
+        let g = grid(10, 5);
+        feed(&g, b"\x1b[2;4r\x1bP$qr\x1b\\\x1bP$q\"q\x1b\\");
+        let grid = g.lock();
+        assert_eq!(grid.response_queue, vec![
+            b"\x1bP1$r2;4r\x1b\\".to_vec(),
+            b"\x1bP0$r\x1b\\".to_vec(),
+        ]);
+    }
+
+    #[test]
+    fn xtgettcap_answers_each_name() {
+        let g = grid(10, 5);
+        feed(&g, b"\x1bP+q5463;7878\x1b\\");
+        let grid = g.lock();
+        assert_eq!(grid.response_queue, vec![
+            b"\x1bP1+r5463\x1b\\".to_vec(),
+            b"\x1bP0+r7878\x1b\\".to_vec(),
+        ]);
+    }
+
+    #[test]
+    fn oversized_dcs_is_dropped() {
+        let g = grid(10, 5);
+        let mut bytes = b"\x1bP+q".to_vec();
+        bytes.extend(std::iter::repeat_n(b'4', DCS_MAX_BYTES + 1));
+        bytes.extend(b"\x1b\\x");
+        feed(&g, &bytes);
+        let grid = g.lock();
+        assert!(grid.response_queue.is_empty());
+        assert_eq!(grid.cells[0][0].ch, 'x');
+    }
+
+    // ── Sixel ───────────────────────────────────────────────────────────
+
+    #[test]
+    fn sixel_image_is_placed_at_cursor() {
+        let g = grid(20, 10);
+        g.lock().cell_size = (2.0, 4.0);
+        // 5 x 6 pixels = 3 x 2 cells
+        feed(&g, b"ab\x1bP0;1q#1;2;0;100;0!5~\x1b\\");
+        let grid = g.lock();
+        assert_eq!(grid.images.len(), 1);
+        let placement = &grid.images[0];
+        assert_eq!((placement.col, placement.cols, placement.rows), (2, 3, 2));
+        assert_eq!(grid.abs_row_of_line(placement.line), Some(0));
+        // Cursor moves below the image, keeping its column
+        assert_eq!((grid.cursor_row, grid.cursor_col), (2, 2));
+    }
+
+    #[test]
+    fn sixel_images_scroll_into_scrollback() {
+        let g = grid(10, 3);
+        g.lock().cell_size = (1.0, 6.0);
+        feed(&g, b"\x1bPq#1~\x1b\\\r\n\n\n\n");
+        let grid = g.lock();
+        assert_eq!(grid.scrollback.len(), 3);
+        assert_eq!(grid.abs_row_of_line(grid.images[0].line), Some(0));
+    }
+
+    #[test]
+    fn erase_display_removes_images() {
+        let g = grid(10, 3);
+        feed(&g, b"\x1bPq#1~\x1b\\\x1b[2J");
+        assert!(g.lock().images.is_empty());
+    }
+
+    #[test]
+    fn apc_kitty_command_is_dispatched() {
+        let g = grid(10, 3);
+        let mut performer = VtePerformer::new(g.clone());
+        // 1x1 RGB pixel, "AAAA" = three zero bytes
+        performer.apc_dispatch(b"Ga=T,f=24,s=1,v=1,i=1;AAAA");
+        performer.apc_dispatch(b"Xnot kitty");
+        let grid = g.lock();
+        assert_eq!(grid.images.len(), 1);
+        assert_eq!(grid.response_queue, vec![b"\x1b_Gi=1;OK\x1b\\".to_vec()]);
+    }
+
+    #[test]
+    fn osc_1337_inline_image() {
+        let g = grid(10, 5);
+        // 1x1 PNG
+        let png = "iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg==";
+        feed(&g, format!("\x1b]1337;File=name=YS5wbmc=;inline=1:{}\x07", png).as_bytes());
+        let grid = g.lock();
+        assert_eq!(grid.images.len(), 1);
+        assert_eq!((grid.images[0].image.width, grid.images[0].image.height), (1, 1));
+    }
+
+    #[test]
+    fn kitty_keyboard_flags() {
+        let g = grid(10, 3);
+        feed(&g, b"\x1b[>1u\x1b[>5u\x1b[?u");
+        feed(&g, b"\x1b[=2;2u\x1b[?u\x1b[<u\x1b[?u\x1b[<u\x1b[?u");
+        let responses: Vec<Vec<u8>> = g.lock().response_queue.drain(..).collect();
+        assert_eq!(responses, vec![
+            b"\x1b[?5u".to_vec(),
+            b"\x1b[?7u".to_vec(),
+            b"\x1b[?1u".to_vec(),
+            b"\x1b[?0u".to_vec(),
+        ]);
+    }
+
+    #[test]
+    fn modify_other_keys_levels() {
+        let g = grid(10, 3);
+        feed(&g, b"\x1b[>4;2m");
+        assert_eq!(g.lock().modify_other_keys, 2);
+        feed(&g, b"\x1b[>4;1m\x1b[1m");
+        assert_eq!(g.lock().modify_other_keys, 1);
+        assert!(g.lock().current_attrs.bold);
+        feed(&g, b"\x1b[>4n");
+        assert_eq!(g.lock().modify_other_keys, 0);
+        feed(&g, b"\x1b[>4;2m\x1b[>4m");
+        assert_eq!(g.lock().modify_other_keys, 0);
+    }
+
+    #[test]
+    fn decrqm_reports_private_modes() {
+        let g = grid(10, 3);
+        feed(&g, b"\x1b[?2004h\x1b[?2004$p\x1b[?25l\x1b[?25$p\x1b[?9999$p");
+        assert_eq!(
+            std::mem::take(&mut g.lock().response_queue),
+            vec![b"\x1b[?2004;1$y".to_vec(), b"\x1b[?25;2$y".to_vec(), b"\x1b[?9999;0$y".to_vec()]
+        );
+        // Synchronized output lives on the performer
+        let mut performer = VtePerformer::new(g.clone());
+        let mut parser = vte::Parser::new();
+        parser.advance(&mut performer, b"\x1b[?2026$p\x1b[?2026h\x1b[?2026$p");
+        assert!(performer.take_sync_start());
+        assert_eq!(
+            std::mem::take(&mut g.lock().response_queue),
+            vec![b"\x1b[?2026;2$y".to_vec(), b"\x1b[?2026;1$y".to_vec()]
+        );
+    }
+
+    // ── Colon subparameters ─────────────────────────────────────────────
+
+    #[test]
+    fn sgr_underline_styles_from_subparams() {
+        let g = grid(10, 3);
+        let cases = [
+            ("4:0", UnderlineStyle::None),
+            ("4:1", UnderlineStyle::Single),
+            ("4:2", UnderlineStyle::Double),
+            ("4:3", UnderlineStyle::Curly),
+            ("4:4", UnderlineStyle::Dotted),
+            ("4:5", UnderlineStyle::Dashed),
+        ];
+        for (param, style) in cases {
+            feed(&g, format!("\x1b[4:3m\x1b[{}m", param).as_bytes());
+            assert_eq!(g.lock().current_attrs.underline, style, "{}", param);
+        }
+    }
+
+    #[test]
+    fn sgr_colon_colors() {
+        let g = grid(10, 3);
+        feed(&g, b"\x1b[38:2::10:20:30;48:5:100;58:2:1:2:3m");
+        let attrs = g.lock().current_attrs;
+        assert_eq!(attrs.fg, Color::Rgb(10, 20, 30));
+        assert_eq!(attrs.bg, Color::Indexed(100));
+        assert_eq!(attrs.underline_color, Color::Rgb(1, 2, 3));
+        // The color space may be present, and a following parameter is
+        // not swallowed by the colon form
+        feed(&g, b"\x1b[38:2:0:40:50:60;1;58:5:9m");
+        let attrs = g.lock().current_attrs;
+        assert_eq!(attrs.fg, Color::Rgb(40, 50, 60));
+        assert!(attrs.bold);
+        assert_eq!(attrs.underline_color, Color::Indexed(9));
+        feed(&g, b"\x1b[58;2;7;8;9m");
+        assert_eq!(g.lock().current_attrs.underline_color, Color::Rgb(7, 8, 9));
+        feed(&g, b"\x1b[59m");
+        assert_eq!(g.lock().current_attrs.underline_color, Color::Default);
+    }
+
+    // ── Character sets ──────────────────────────────────────────────────
+
+    #[test]
+    fn dec_special_graphics_in_g0() {
+        let g = grid(10, 3);
+        feed(&g, b"\x1b(0lqk\x1b(Bq\r\n\x1b(0x\x1b(Bx");
+        let g = g.lock();
+        assert_eq!(row_text(&g, 0), "┌─┐q");
+        assert_eq!(row_text(&g, 1), "│x");
+    }
+
+    #[test]
+    fn shift_out_uses_g1() {
+        let g = grid(10, 3);
+        // The ncurses way: G1 holds line drawing, SO/SI switch to it and back
+        feed(&g, b"\x1b)0a\x0emqj\x0fb\x1b*A\x1bN#\x1bN#");
+        assert_eq!(row_text(&g.lock(), 0), "a└─┘b££");
+        // \x1bN only applies to one character
+        let g = grid(10, 3);
+        feed(&g, b"\x1b*A\x1bN##");
+        assert_eq!(row_text(&g.lock(), 0), "£#");
+    }
+
+    #[test]
+    fn decsc_saves_charsets() {
+        let g = grid(10, 3);
+        feed(&g, b"\x1b(0\x1b7\x1b(Bq\x1b8q");
+        assert_eq!(row_text(&g.lock(), 0), "─");
+    }
+
+    // ── Cursor style ────────────────────────────────────────────────────
+
+    #[test]
+    fn decscusr_sets_shape_and_blinking() {
+        let g = grid(10, 3);
+        let style = |g: &Arc<Mutex<TerminalGrid>>| {
+            let g = g.lock();
+            (g.cursor_shape, g.cursor_blinking)
+        };
+        feed(&g, b"\x1b[5 q");
+        assert_eq!(style(&g), (Some(CursorShape::Bar), Some(true)));
+        feed(&g, b"\x1b[4 q");
+        assert_eq!(style(&g), (Some(CursorShape::Underline), Some(false)));
+        feed(&g, b"\x1b[?12h");
+        assert_eq!(style(&g), (Some(CursorShape::Underline), Some(true)));
+        // Out of range: ignored
+        feed(&g, b"\x1b[9 q");
+        assert_eq!(style(&g), (Some(CursorShape::Underline), Some(true)));
+        feed(&g, b"\x1b[0 q");
+        assert_eq!(style(&g), (None, None));
+    }
+
+    // ── Margins and origin mode ─────────────────────────────────────────
+
+    fn margins(g: &Arc<Mutex<TerminalGrid>>) -> (usize, usize) {
+        let g = g.lock();
+        (g.scroll_left, g.scroll_right)
+    }
+
+    fn cursor(g: &Arc<Mutex<TerminalGrid>>) -> (usize, usize) {
+        let g = g.lock();
+        (g.cursor_row, g.cursor_col)
+    }
+
+    #[test]
+    fn slrm_needs_declrmm() {
+        let g = grid(10, 3);
+        // Without mode 69, CSI s saves the cursor
+        feed(&g, b"\x1b[3;6s");
+        assert_eq!(margins(&g), (0, 9));
+        feed(&g, b"\x1b[?69h\x1b[3;6s");
+        assert_eq!(margins(&g), (2, 5));
+    }
+
+    #[test]
+    fn autowrap_stays_within_margins() {
+        let g = grid(10, 3);
+        feed(&g, b"\x1b[?69h\x1b[3;6s\x1b[1;3Habcdefg");
+        assert_eq!(row_text(&g.lock(), 0), "  abcd");
+        assert_eq!(row_text(&g.lock(), 1), "  efg");
+    }
+
+    #[test]
+    fn ich_and_dch_stop_at_right_margin() {
+        let g = grid(10, 3);
+        feed(&g, b"0123456789\x1b[?69h\x1b[3;6s\x1b[1;4H\x1b[2@");
+        assert_eq!(row_text(&g.lock(), 0), "012  36789");
+        feed(&g, b"\x1b[?69h\x1b[3;6s\x1b[1;3H\x1b[3P");
+        assert_eq!(row_text(&g.lock(), 0), "013   6789");
+    }
+
+    #[test]
+    fn cnl_cpl_and_nel_return_to_left_margin() {
+        let g = grid(10, 4);
+        feed(&g, b"\x1b[?69h\x1b[3;6s\x1b[1;5H\x1b[E");
+        assert_eq!(cursor(&g), (1, 2));
+        feed(&g, b"\x1b[?69h\x1b[3;6s\x1b[3;5H\x1b[F");
+        assert_eq!(cursor(&g), (1, 2));
+        feed(&g, b"\x1b[?69h\x1b[3;6s\x1b[1;5H\x1bE");
+        assert_eq!(cursor(&g), (1, 2));
+        // Left of the margin the cursor goes to column 0
+        feed(&g, b"\x1b[?69h\x1b[3;6s\x1b[1;2H\x1b[E");
+        assert_eq!(cursor(&g), (1, 0));
+    }
+
+    #[test]
+    fn tab_stops_at_right_margin() {
+        let g = grid(20, 2);
+        feed(&g, b"\x1b[?69h\x1b[3;6s\x1b[1;3H\t");
+        assert_eq!(cursor(&g), (0, 5));
+        // Past the margin the tab runs to the last column
+        feed(&g, b"\x1b[?69h\x1b[3;6s\x1b[1;8H\t\t\t");
+        assert_eq!(cursor(&g), (0, 19));
+    }
+
+    #[test]
+    fn il_and_dl_shift_only_margin_columns() {
+        let g = grid(6, 3);
+        feed(&g, b"aaaaaa\r\nbbbbbb\r\ncccccc");
+        feed(&g, b"\x1b[?69h\x1b[2;4s\x1b[2;3H\x1b[L");
+        assert_eq!(row_text(&g.lock(), 0), "aaaaaa");
+        assert_eq!(row_text(&g.lock(), 1), "b   bb");
+        assert_eq!(row_text(&g.lock(), 2), "cbbbcc");
+        // The cursor moves to the left margin
+        assert_eq!(g.lock().cursor_col, 1);
+        feed(&g, b"\x1b[?69h\x1b[2;4s\x1b[1;2H\x1b[M");
+        assert_eq!(row_text(&g.lock(), 0), "a   aa");
+        assert_eq!(row_text(&g.lock(), 2), "c   cc");
+        // Outside the margins IL is ignored
+        feed(&g, b"\x1b[?69h\x1b[2;4s\x1b[1;6H\x1b[L");
+        assert_eq!(row_text(&g.lock(), 0), "a   aa");
+    }
+
+    #[test]
+    fn scrolling_with_side_margins() {
+        let g = grid(4, 2);
+        feed(&g, b"abcd\r\nefgh\x1b[?69h\x1b[2;3s\x1b[2;2H\n");
+        assert_eq!(row_text(&g.lock(), 0), "afgd");
+        assert_eq!(row_text(&g.lock(), 1), "e  h");
+        assert!(g.lock().scrollback.is_empty());
+    }
+
+    #[test]
+    fn origin_mode_addresses_within_margins() {
+        let g = grid(10, 6);
+        feed(&g, b"\x1b[?69h\x1b[3;8s\x1b[2;5r\x1b[?6h\x1b[2;3H");
+        assert_eq!(cursor(&g), (2, 4));
+        // Clamped to the margins
+        feed(&g, b"\x1b[?69h\x1b[3;8s\x1b[?6h\x1b[9;9H");
+        assert_eq!(cursor(&g), (4, 7));
+        feed(&g, b"\x1b[?69h\x1b[3;8s\x1b[?6h\x1b[2;3H\x1b[6n");
+        let responses: Vec<Vec<u8>> = g.lock().response_queue.drain(..).collect();
+        assert_eq!(responses, vec![b"\x1b[2;3R".to_vec()]);
+        // Resetting DECOM homes to the screen corner
+        feed(&g, b"\x1b[?6l");
+        assert_eq!(cursor(&g), (0, 0));
+    }
+
+    #[test]
+    fn decsc_saves_origin_mode() {
+        let g = grid(10, 6);
+        feed(&g, b"\x1b[3;5r\x1b[?6h\x1b7\x1b[?6l\x1b8\x1b[1;1H");
+        assert_eq!(g.lock().cursor_row, 2);
+    }
+
+    // ── Line size ───────────────────────────────────────────────────────
+
+    #[test]
+    fn double_width_line_wraps_at_half_width() {
+        let g = grid(10, 3);
+        feed(&g, b"\x1b#6abcdefg");
+        assert_eq!(g.lock().line_sizes[0], LineSize::DoubleWidth);
+        assert_eq!(row_text(&g.lock(), 0), "abcde");
+        assert_eq!(row_text(&g.lock(), 1), "fg");
+        assert_eq!(g.lock().line_sizes[1], LineSize::Single);
+    }
+
+    #[test]
+    fn line_size_follows_scrolled_rows_and_resets_on_erase() {
+        let g = grid(10, 3);
+        feed(&g, b"\x1b[3H\x1b#3top\r\n\x1b#4top");
+        assert_eq!(g.lock().line_sizes, vec![LineSize::Single, LineSize::DoubleHeightTop, LineSize::DoubleHeightBottom]);
+        feed(&g, b"\x1b[2J");
+        assert_eq!(g.lock().line_sizes, vec![LineSize::Single; 3]);
+    }
+
+    #[test]
+    fn line_size_follows_deleted_lines() {
+        let g = grid(10, 4);
+        feed(&g, b"\x1b[3H\x1b#6wide\x1b[4H\x1b#6\x1b[1H\x1b[M");
+        assert_eq!(row_text(&g.lock(), 1), "wide");
+        assert_eq!(g.lock().line_sizes, vec![
+            LineSize::Single,
+            LineSize::DoubleWidth,
+            LineSize::DoubleWidth,
+            LineSize::Single,
+        ]);
+    }
+
+    #[test]
+    fn decaln_fills_screen_and_resets_margins() {
+        let g = grid(4, 2);
+        feed(&g, b"\x1b[2;2r\x1b#6\x1b#8");
+        let g = g.lock();
+        assert_eq!(row_text(&g, 0), "EEEE");
+        assert_eq!(row_text(&g, 1), "EEEE");
+        assert_eq!((g.scroll_top, g.scroll_bottom), (0, 1));
+        assert_eq!((g.cursor_row, g.cursor_col), (0, 0));
+        assert_eq!(g.line_sizes[0], LineSize::Single);
+    }
+
+    // ── Resets ──────────────────────────────────────────────────────────
+
+    #[test]
+    fn ris_resets_screen_modes_and_colors() {
+        let g = grid(10, 2);
+        feed(&g, b"one\r\ntwo\r\nthree\x1b[1;31m\x1b[?1h\x1b[?5h\x1b[3g\x1b]4;1;#00ff00\x07\x1b#6");
+        feed(&g, b"\x1bc\tx");
+        let g = g.lock();
+        assert_eq!(row_text(&g, 0), "        x");
+        assert_eq!(row_text(&g, 1), "");
+        assert_eq!(g.scrollback.len(), 1);
+        assert_eq!(g.current_attrs, CellAttributes::default());
+        assert!(!g.application_cursor_keys && !g.reverse_video);
+        assert_eq!(g.line_sizes[0], LineSize::Single);
+        assert_eq!(g.colors.palette_color(1), TerminalGrid::new(1, 1).colors.palette_color(1));
+    }
+
+    #[test]
+    fn ris_resets_parser_modes() {
+        let g = grid(10, 3);
+        feed(&g, b"\x1b(0\x1b[?7l\x1bcq\x1b[1;10Hab");
+        assert_eq!(row_text(&g.lock(), 0), "q        a");
+        assert_eq!(row_text(&g.lock(), 1), "b");
+    }
+
+    #[test]
+    fn decstr_keeps_screen_but_resets_modes() {
+        let g = grid(10, 4);
+        let fresh = TerminalGrid::new(1, 1);
+        feed(&g, b"text\x1b[2;3r\x1b[?6h\x1b[4m\x1b[?25l");
+        feed(&g, b"\x1b[?1002h\x1b[?1006h\x1b[?2004h\x1b[>1u\x1b[>4;2m\x1b[?5h\x1b[4 q");
+        // DECRC after DECSTR restores the home position, DECOM off and ASCII in G0
+        feed(&g, b"\x1b]4;1;#00ff00\x07\x1b[3;4H\x1b7\x1b[3g\x1b[?69h\x1b[!p\x1b(0\x1b[4;4H\x1b8x");
+        // With DECLRMM off again this saves the cursor instead of setting margins
+        feed(&g, b"\x1b[2;5s\ty");
+        let g = g.lock();
+        assert_eq!(row_text(&g, 0), "xext    y");
+        assert_eq!((g.scroll_top, g.scroll_bottom), (0, 3));
+        assert_eq!((g.scroll_left, g.scroll_right), (0, 9));
+        assert_eq!(g.cells[0][0].attrs, CellAttributes::default());
+        assert!(g.cursor_visible);
+        assert_eq!(g.mouse_tracking, MouseTracking::Off);
+        assert_eq!(g.mouse_encoding, MouseEncoding::Default);
+        assert!(!g.bracketed_paste);
+        assert_eq!(g.keyboard_flags(), crate::terminal::grid::KeyboardFlags::default());
+        assert_eq!(g.modify_other_keys, 0);
+        assert!(!g.reverse_video);
+        assert_eq!((g.cursor_shape, g.cursor_blinking), (None, None));
+        assert_eq!(g.colors.palette_color(1), fresh.colors.palette_color(1));
+    }
+
+    #[test]
+    fn decscnm_reverses_default_colors() {
+        let g = grid(10, 2);
+        let (fg, bg) = g.lock().default_colors();
+        feed(&g, b"\x1b[?5h\x1b[?5$p");
+        assert_eq!(g.lock().default_colors(), (bg, fg));
+        let responses: Vec<Vec<u8>> = g.lock().response_queue.drain(..).collect();
+        assert_eq!(responses, vec![b"\x1b[?5;1$y".to_vec()]);
+        feed(&g, b"\x1b[?5l");
+        assert_eq!(g.lock().default_colors(), (fg, bg));
+    }
+
+    // ── Window operations ───────────────────────────────────────────────
+
+    #[test]
+    fn xtwinops_size_reports() {
+        let g = grid(80, 24);
+        g.lock().cell_size = (9.0, 18.5);
+        feed(&g, b"\x1b[14t\x1b[16t\x1b[18t\x1b[19t\x1b[11t\x1b[13t");
+        let responses: Vec<Vec<u8>> = g.lock().response_queue.drain(..).collect();
+        assert_eq!(responses, vec![
+            b"\x1b[4;444;720t".to_vec(),
+            b"\x1b[6;19;9t".to_vec(),
+            b"\x1b[8;24;80t".to_vec(),
+            b"\x1b[9;24;80t".to_vec(),
+            b"\x1b[1t".to_vec(),
+        ]);
+    }
+
+    #[test]
+    fn title_stack_saves_and_restores() {
+        let g = grid(10, 2);
+        feed(&g, b"\x1b]2;shell\x07\x1b[22;0t\x1b]2;vim\x07");
+        assert_eq!(g.lock().title, "vim");
+        // Icon-title pops leave the window title alone
+        feed(&g, b"\x1b[23;1t");
+        assert_eq!(g.lock().title, "vim");
+        feed(&g, b"\x1b[23;0t");
+        assert_eq!(g.lock().title, "shell");
+        // Popping an empty stack keeps the title
+        feed(&g, b"\x1b]2;other\x07\x1b[23t");
+        assert_eq!(g.lock().title, "other");
+    }
 }
@@ -0,0 +1,266 @@
+use super::apc::{ApcScanner, Segment};
+use super::grid::TerminalGrid;
+use super::parser::VtePerformer;
+use parking_lot::Mutex;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Longest a synchronized update may hold back output.
+const SYNC_TIMEOUT: Duration = Duration::from_millis(150);
+
+/// Most output held back during a synchronized update.
+const SYNC_MAX_BYTES: usize = 2 * 1024 * 1024;
+
+/// End Synchronized Update.
+const ESU: &[u8] = b"\x1b[?2026l";
+
+/// A piece of output held back during a synchronized update, already
+/// split by the APC scanner.
+enum HeldSegment {
+    Output(Vec<u8>),
+    Apc(Vec<u8>),
+}
+
+/// Output held back during a synchronized update (DEC mode 2026).
+struct SyncUpdate {
+    started: Instant,
+    segments: Vec<HeldSegment>,
+    /// Bytes held in `segments`
+    size: usize,
+    /// How far the last output segment has been searched for ESU
+    scanned: usize,
+    /// Whether ESU has turned up in any held output
+    found_end: bool,
+}
+
+impl SyncUpdate {
+    fn new(rest: &[u8]) -> Self {
+        let mut sync = Self { started: Instant::now(), segments: Vec::new(), size: 0, scanned: 0, found_end: false };
+        sync.push(Segment::Output(rest));
+        sync
+    }
+
+    /// Hold back a segment. Output joins the output before it, so an ESU
+    /// split across chunks is found in one piece.
+    fn push(&mut self, segment: Segment) {
+        match segment {
+            Segment::Output(bytes) => {
+                self.size += bytes.len();
+                if !matches!(self.segments.last(), Some(HeldSegment::Output(_))) {
+                    self.segments.push(HeldSegment::Output(Vec::new()));
+                    self.scanned = 0;
+                }
+                let Some(HeldSegment::Output(held)) = self.segments.last_mut() else {
+                    return;
+                };
+                held.extend_from_slice(bytes);
+                // ESU may be in any output segment, not just the last one
+                let from = self.scanned.saturating_sub(ESU.len() - 1);
+                self.scanned = held.len();
+                self.found_end |= held[from..].windows(ESU.len()).any(|w| w == ESU);
+            }
+            Segment::Apc(body) => {
+                self.size += body.len();
+                self.segments.push(HeldSegment::Apc(body.to_vec()));
+            }
+        }
+    }
+
+    /// Whether the update has ended (or grown too big to keep waiting).
+    fn is_complete(&self) -> bool {
+        self.found_end || self.size >= SYNC_MAX_BYTES
+    }
+}
+
+/// Turns PTY output into grid changes. APC strings, which vte drops, are
+/// split out first, and output inside a synchronized update is held back
+/// until the update ends, so the grid never shows a half-drawn frame.
+pub struct OutputProcessor {
+    parser: vte::Parser,
+    performer: VtePerformer,
+    apc: ApcScanner,
+    sync: Option<SyncUpdate>,
+}
+
+impl OutputProcessor {
+    pub fn new(grid: Arc<Mutex<TerminalGrid>>) -> Self {
+        Self {
+            parser: vte::Parser::new(),
+            performer: VtePerformer::new(grid),
+            apc: ApcScanner::default(),
+            sync: None,
+        }
+    }
+
+    pub fn advance(&mut self, data: &[u8]) {
+        self.parse(data);
+        // Finish updates whose end has arrived; what follows may start another
+        while self.sync.as_ref().is_some_and(SyncUpdate::is_complete) {
+            self.end_sync();
+        }
+    }
+
+    /// Give up on an update the application never ended, so the pane
+    /// doesn't freeze. Call every frame.
+    pub fn end_stale_sync(&mut self) {
+        if self.sync.as_ref().is_some_and(|s| s.started.elapsed() >= SYNC_TIMEOUT) {
+            self.end_sync();
+        }
+    }
+
+    /// Whether output is being held back for a synchronized update.
+    pub fn in_synchronized_update(&self) -> bool {
+        self.sync.is_some()
+    }
+
+    /// Full reset: drop any half-parsed sequence and held-back output,
+    /// then reset the terminal as RIS does.
+    pub fn reset(&mut self) {
+        self.parser = vte::Parser::new();
+        self.apc = ApcScanner::default();
+        self.sync = None;
+        self.performer.reset();
+    }
+
+    /// Replay the held-back segments. They were split already, so they
+    /// skip the APC scanner, whose state belongs to the live stream.
+    fn end_sync(&mut self) {
+        let Some(held) = self.sync.take() else {
+            return;
+        };
+        for segment in &held.segments {
+            let segment = match segment {
+                HeldSegment::Output(bytes) => Segment::Output(bytes),
+                HeldSegment::Apc(body) => Segment::Apc(body),
+            };
+            handle_segment(&mut self.parser, &mut self.performer, &mut self.sync, segment);
+        }
+    }
+
+    fn parse(&mut self, data: &[u8]) {
+        let Self { parser, performer, apc, sync } = self;
+        apc.split(data, |segment| handle_segment(parser, performer, sync, segment));
+    }
+}
+
+fn handle_segment(
+    parser: &mut vte::Parser,
+    performer: &mut VtePerformer,
+    sync: &mut Option<SyncUpdate>,
+    segment: Segment,
+) {
+    // Everything after the start of an update waits for its end
+    if let Some(sync) = sync {
+        sync.push(segment);
+        return;
+    }
+    match segment {
+        Segment::Output(bytes) => {
+            let parsed = parser.advance_until_terminated(performer, bytes);
+            if performer.take_sync_start() {
+                *sync = Some(SyncUpdate::new(&bytes[parsed..]));
+            }
+        }
+        Segment::Apc(body) => {
+            // Cancel whatever sequence the parser was in the middle of
+            parser.advance(performer, &[0x18]);
+            performer.apc_dispatch(body);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn processor(cols: usize, rows: usize) -> (Arc<Mutex<TerminalGrid>>, OutputProcessor) {
+        let grid = Arc::new(Mutex::new(TerminalGrid::new(cols, rows)));
+        (grid.clone(), OutputProcessor::new(grid))
+    }
+
+    fn first_row(grid: &Arc<Mutex<TerminalGrid>>) -> String {
+        grid.lock().cells[0].iter().map(|c| c.ch).filter(|&c| c != '\0').collect()
+    }
+
+    #[test]
+    fn output_is_held_until_update_ends() {
+        let (g, mut p) = processor(10, 2);
+        p.advance(b"ab\x1b[?2026hcd");
+        assert_eq!(first_row(&g), "ab");
+        assert!(p.in_synchronized_update());
+        p.advance(b"ef\x1b[?20");
+        assert_eq!(first_row(&g), "ab");
+        p.advance(b"26lgh");
+        assert_eq!(first_row(&g), "abcdefgh");
+        assert!(!p.in_synchronized_update());
+    }
+
+    #[test]
+    fn back_to_back_updates() {
+        let (g, mut p) = processor(10, 2);
+        p.advance(b"\x1b[?2026ha\x1b[?2026l\x1b[?2026hb\x1b[?2026l\x1b[?2026hc");
+        assert_eq!(first_row(&g), "ab");
+        assert!(p.in_synchronized_update());
+    }
+
+    #[test]
+    fn stale_update_is_flushed() {
+        let (g, mut p) = processor(10, 2);
+        p.advance(b"\x1b[?2026hxy");
+        p.end_stale_sync();
+        assert_eq!(first_row(&g), "");
+        p.sync.as_mut().unwrap().started -= SYNC_TIMEOUT;
+        p.end_stale_sync();
+        assert_eq!(first_row(&g), "xy");
+        // Later output is no longer held back
+        p.advance(b"z");
+        assert_eq!(first_row(&g), "xyz");
+    }
+
+    #[test]
+    fn apc_inside_update_is_kept() {
+        let (g, mut p) = processor(10, 2);
+        p.advance(b"\x1b[?2026h\x1b_Ga=T,f=24,s=1,v=1,i=1;AAAA\x1b\\");
+        assert!(g.lock().images.is_empty());
+        p.advance(b"\x1b[?2026l");
+        assert_eq!(g.lock().images.len(), 1);
+    }
+
+    #[test]
+    fn update_ending_before_apc() {
+        let (g, mut p) = processor(10, 2);
+        p.advance(b"\x1b[?2026hab\x1b[?2026l\x1b_Ga=T,f=24,s=1,v=1,i=1;AAAA\x1b\\");
+        assert!(!p.in_synchronized_update());
+        assert_eq!(first_row(&g), "ab");
+        assert_eq!(g.lock().images.len(), 1);
+    }
+
+    #[test]
+    fn update_ending_before_apc_and_text() {
+        let (g, mut p) = processor(10, 2);
+        p.advance(b"\x1b[?2026hab\x1b[?2026l\x1b_Ga=T,f=24,s=1,v=1,i=1;AAAA\x1b\\cd");
+        assert!(!p.in_synchronized_update());
+        assert_eq!(first_row(&g), "abcd");
+        assert_eq!(g.lock().images.len(), 1);
+    }
+
+    #[test]
+    fn chunk_ending_in_escape_inside_update() {
+        let (g, mut p) = processor(20, 2);
+        p.advance(b"\x1b[?2026hab\x1b");
+        p.advance(b"[1mcd\x1b[?2026l");
+        assert_eq!(first_row(&g), "abcd");
+        assert!(g.lock().cells[0][2].attrs.bold);
+        assert!(!p.in_synchronized_update());
+    }
+
+    #[test]
+    fn end_of_update_split_after_escape() {
+        let (g, mut p) = processor(20, 2);
+        p.advance(b"\x1b[?2026hab\x1b");
+        assert!(p.in_synchronized_update());
+        p.advance(b"[?2026l");
+        assert_eq!(first_row(&g), "ab");
+        assert!(!p.in_synchronized_update());
+    }
+}
@@ -40,6 +40,17 @@ fn get_user_shell() -> String {
     "/bin/zsh".to_string()
 }
 
+/// Window size for the PTY, with the pixel size of the text area so
+/// programs reading `TIOCGWINSZ` can size images.
+fn pty_size(cols: u16, rows: u16, (cell_w, cell_h): (f32, f32)) -> PtySize {
+    PtySize {
+        rows,
+        cols,
+        pixel_width: (cols as f32 * cell_w).round().min(u16::MAX as f32) as u16,
+        pixel_height: (rows as f32 * cell_h).round().min(u16::MAX as f32) as u16,
+    }
+}
+
 pub struct PtyHandle {
     pub master: Box<dyn MasterPty + Send>,
     pub writer: Box<dyn Write + Send>,
@@ -48,14 +59,9 @@ pub struct PtyHandle {
 }
 
 impl PtyHandle {
-    pub fn spawn(cols: u16, rows: u16, cwd: Option<&Path>) -> Result<Self> {
+    pub fn spawn(cols: u16, rows: u16, cell_size: (f32, f32), cwd: Option<&Path>) -> Result<Self> {
         let pty_system = native_pty_system();
-        let pair = pty_system.openpty(PtySize {
-            rows,
-            cols,
-            pixel_width: 0,
-            pixel_height: 0,
-        })?;
+        let pair = pty_system.openpty(pty_size(cols, rows, cell_size))?;
 
         let shell = get_user_shell();
         let mut cmd = CommandBuilder::new(&shell);
@@ -126,13 +132,8 @@ impl PtyHandle {
         Ok(())
     }
 
-    pub fn resize(&self, cols: u16, rows: u16) -> Result<()> {
-        self.master.resize(PtySize {
-            rows,
-            cols,
-            pixel_width: 0,
-            pixel_height: 0,
-        })?;
+    pub fn resize(&self, cols: u16, rows: u16, cell_size: (f32, f32)) -> Result<()> {
+        self.master.resize(pty_size(cols, rows, cell_size))?;
         Ok(())
     }
 
@@ -145,47 +146,68 @@ impl PtyHandle {
     }
 
     /// Get the current working directory of the shell process.
-    /// Uses the macOS `proc_pidinfo` API (libproc) for reliability.
+    /// Prefer the OSC 7 report on the grid when there is one; this only
+    /// sees the local shell, not a subshell or remote session.
     pub fn get_cwd(&self) -> Option<PathBuf> {
         let pid = self.child.lock().ok()?.process_id()? as i32;
+        process_cwd(pid)
+    }
+}
 
-        // Use libproc's proc_pidinfo with PROC_PIDVNODEPATHINFO to get cwd
-        #[repr(C)]
-        struct VnodeInfoPath {
-            _vip_vi: [u8; 152],  // struct vnode_info (padding)
-            vip_path: [u8; 1024], // MAXPATHLEN
-        }
-        #[repr(C)]
-        struct ProcVnodePathInfo {
-            pvi_cdir: VnodeInfoPath,
-            pvi_rdir: VnodeInfoPath,
-        }
-        const PROC_PIDVNODEPATHINFO: i32 = 9;
-        extern "C" {
-            fn proc_pidinfo(
-                pid: i32,
-                flavor: i32,
-                arg: u64,
-                buffer: *mut std::ffi::c_void,
-                buffersize: i32,
-            ) -> i32;
-        }
+/// Working directory of a process via the macOS `proc_pidinfo` API (libproc).
+#[cfg(target_os = "macos")]
+fn process_cwd(pid: i32) -> Option<PathBuf> {
+    // Use libproc's proc_pidinfo with PROC_PIDVNODEPATHINFO to get cwd
+    #[repr(C)]
+    struct VnodeInfoPath {
+        _vip_vi: [u8; 152],  // struct vnode_info (padding)
+        vip_path: [u8; 1024], // MAXPATHLEN
+    }
+    #[repr(C)]
+    struct ProcVnodePathInfo {
+        pvi_cdir: VnodeInfoPath,
+        pvi_rdir: VnodeInfoPath,
+    }
+    const PROC_PIDVNODEPATHINFO: i32 = 9;
+    extern "C" {
+        fn proc_pidinfo(
+            pid: i32,
+            flavor: i32,
+            arg: u64,
+            buffer: *mut std::ffi::c_void,
+            buffersize: i32,
+        ) -> i32;
+    }
 
-        let mut info: ProcVnodePathInfo = unsafe { std::mem::zeroed() };
-        let size = std::mem::size_of::<ProcVnodePathInfo>() as i32;
-        let ret = unsafe {
-            proc_pidinfo(pid, PROC_PIDVNODEPATHINFO, 0, &mut info as *mut _ as *mut _, size)
-        };
-        if ret <= 0 {
-            return None;
-        }
+    let mut info: ProcVnodePathInfo = unsafe { std::mem::zeroed() };
+    let size = std::mem::size_of::<ProcVnodePathInfo>() as i32;
+    let ret = unsafe {
+        proc_pidinfo(pid, PROC_PIDVNODEPATHINFO, 0, &mut info as *mut _ as *mut _, size)
+    };
+    if ret <= 0 {
+        return None;
+    }
 
-        let bytes = &info.pvi_cdir.vip_path;
-        let len = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
-        let path = std::str::from_utf8(&bytes[..len]).ok()?;
-        if path.is_empty() || path == "/" {
-            return None;
-        }
-        Some(PathBuf::from(path))
+    let bytes = &info.pvi_cdir.vip_path;
+    let len = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    let path = std::str::from_utf8(&bytes[..len]).ok()?;
+    if path.is_empty() || path == "/" {
+        return None;
     }
+    Some(PathBuf::from(path))
+}
+
+/// Working directory of a process via procfs.
+#[cfg(target_os = "linux")]
+fn process_cwd(pid: i32) -> Option<PathBuf> {
+    let path = std::fs::read_link(format!("/proc/{}/cwd", pid)).ok()?;
+    if path == Path::new("/") {
+        return None;
+    }
+    Some(path)
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+fn process_cwd(_pid: i32) -> Option<PathBuf> {
+    None
 }
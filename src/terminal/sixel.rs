@@ -0,0 +1,311 @@
+use super::graphics::ImageData;
+
+/// Largest sixel image we decode, in pixels per side. Data beyond it is dropped.
+pub const SIXEL_MAX_DIMENSION: usize = 4096;
+
+/// Number of color registers, as advertised by xterm.
+const COLOR_REGISTERS: usize = 256;
+
+/// VT340 default color registers, in percent.
+const VT340_PALETTE: [[u8; 3]; 16] = [
+    [0, 0, 0], [20, 20, 80], [80, 13, 13], [20, 80, 20],
+    [80, 20, 80], [20, 80, 80], [80, 80, 20], [53, 53, 53],
+    [26, 26, 26], [33, 33, 60], [60, 26, 26], [33, 60, 33],
+    [60, 33, 60], [33, 60, 60], [60, 60, 33], [80, 80, 80],
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Data,
+    /// `!Pn`: repeat the next sixel
+    Repeat,
+    /// `#Pc[;Pu;Px;Py;Pz]`: select or define a color
+    Color,
+    /// `"Pan;Pad;Ph;Pv`: raster attributes
+    Raster,
+}
+
+/// Streaming decoder for the body of a sixel DCS (`DCS P1;P2;P3 q ... ST`).
+/// Bytes are fed one at a time from `put`, so no payload is buffered.
+pub struct SixelDecoder {
+    state: State,
+    params: Vec<u32>,
+    /// Count from a `!` introducer, applied to the next sixel
+    repeat: Option<u32>,
+    palette: [[u8; 4]; COLOR_REGISTERS],
+    color: usize,
+    x: usize,
+    y: usize,
+    /// Pixel buffer, `buf_w * buf_h` RGBA; unset pixels have alpha 0.
+    pixels: Vec<u8>,
+    buf_w: usize,
+    buf_h: usize,
+    /// Extent actually drawn (or declared by raster attributes).
+    width: usize,
+    height: usize,
+    /// P2 = 1: pixels that are never set stay transparent.
+    transparent_bg: bool,
+}
+
+impl SixelDecoder {
+    /// `params` are the DCS parameters; only P2 (background select) matters.
+    pub fn new(params: &[u16]) -> Self {
+        let mut palette = [[0, 0, 0, 255]; COLOR_REGISTERS];
+        for (dst, [r, g, b]) in palette.iter_mut().zip(VT340_PALETTE) {
+            *dst = [percent(r as u32), percent(g as u32), percent(b as u32), 255];
+        }
+        Self {
+            state: State::Data,
+            params: Vec::new(),
+            repeat: None,
+            palette,
+            color: 0,
+            x: 0,
+            y: 0,
+            pixels: Vec::new(),
+            buf_w: 0,
+            buf_h: 0,
+            width: 0,
+            height: 0,
+            transparent_bg: params.get(1) == Some(&1),
+        }
+    }
+
+    pub fn put(&mut self, byte: u8) {
+        if self.state != State::Data {
+            match byte {
+                b'0'..=b'9' => {
+                    let digit = (byte - b'0') as u32;
+                    match self.params.last_mut() {
+                        Some(p) => *p = p.saturating_mul(10).saturating_add(digit),
+                        None => self.params.push(digit),
+                    }
+                    return;
+                }
+                b';' => {
+                    if self.params.is_empty() {
+                        self.params.push(0);
+                    }
+                    self.params.push(0);
+                    return;
+                }
+                _ => self.finish_params(),
+            }
+        }
+        match byte {
+            b'!' => self.state = State::Repeat,
+            b'#' => self.state = State::Color,
+            b'"' => self.state = State::Raster,
+            b'$' => self.x = 0,
+            b'-' => {
+                self.x = 0;
+                self.y += 6;
+            }
+            b'?'..=b'~' => self.draw_sixel(byte - b'?'),
+            _ => {}
+        }
+    }
+
+    /// Finish decoding. Returns `None` if nothing was drawn.
+    pub fn finish(mut self) -> Option<ImageData> {
+        if self.state != State::Data {
+            self.finish_params();
+        }
+        if self.width == 0 || self.height == 0 {
+            return None;
+        }
+        let (w, h) = (self.width, self.height);
+        self.ensure_size(w, h);
+        let background = self.palette[0];
+        let mut rgba = Vec::with_capacity(w * h * 4);
+        for row in self.pixels.chunks_exact(self.buf_w * 4).take(h) {
+            for px in row[..w * 4].chunks_exact(4) {
+                if px[3] == 0 && !self.transparent_bg {
+                    rgba.extend_from_slice(&background);
+                } else {
+                    rgba.extend_from_slice(px);
+                }
+            }
+        }
+        Some(ImageData::new(w as u32, h as u32, rgba))
+    }
+
+    fn finish_params(&mut self) {
+        let params = std::mem::take(&mut self.params);
+        let state = std::mem::replace(&mut self.state, State::Data);
+        match state {
+            // The count applies to the sixel that follows
+            State::Repeat => self.repeat = Some(params.first().copied().unwrap_or(1).max(1)),
+            State::Color => self.apply_color(&params),
+            State::Raster => {
+                // Pan;Pad;Ph;Pv: only the declared size is used
+                if let (Some(&ph), Some(&pv)) = (params.get(2), params.get(3)) {
+                    let (ph, pv) = (ph as usize, pv as usize);
+                    if ph <= SIXEL_MAX_DIMENSION && pv <= SIXEL_MAX_DIMENSION {
+                        self.width = self.width.max(ph);
+                        self.height = self.height.max(pv);
+                        self.ensure_size(ph, pv);
+                    }
+                }
+            }
+            State::Data => {}
+        }
+    }
+
+    fn apply_color(&mut self, params: &[u32]) {
+        let Some(&index) = params.first() else {
+            return;
+        };
+        let index = index as usize % COLOR_REGISTERS;
+        if let [_, space, a, b, c] = *params {
+            let rgb = match space {
+                1 => hls_to_rgb(a, b, c),
+                2 => [percent(a), percent(b), percent(c)],
+                _ => return,
+            };
+            self.palette[index] = [rgb[0], rgb[1], rgb[2], 255];
+        }
+        self.color = index;
+    }
+
+    fn draw_sixel(&mut self, bits: u8) {
+        let count = self.repeat.take().unwrap_or(1) as usize;
+        let x_end = (self.x + count).min(SIXEL_MAX_DIMENSION);
+        if bits != 0 && self.x < x_end && self.y < SIXEL_MAX_DIMENSION {
+            let y_end = (self.y + 6).min(SIXEL_MAX_DIMENSION);
+            self.ensure_size(x_end, y_end);
+            let color = self.palette[self.color];
+            for bit in 0..(y_end - self.y) {
+                if bits & (1 << bit) == 0 {
+                    continue;
+                }
+                let y = self.y + bit;
+                let row = y * self.buf_w * 4;
+                for x in self.x..x_end {
+                    self.pixels[row + x * 4..row + x * 4 + 4].copy_from_slice(&color);
+                }
+                self.height = self.height.max(y + 1);
+            }
+            self.width = self.width.max(x_end);
+        }
+        self.x = (self.x + count).min(SIXEL_MAX_DIMENSION);
+    }
+
+    /// Grow the pixel buffer to at least `w` x `h`, doubling to amortize.
+    fn ensure_size(&mut self, w: usize, h: usize) {
+        if w <= self.buf_w && h <= self.buf_h {
+            return;
+        }
+        let new_w = if w > self.buf_w { w.max(self.buf_w * 2).min(SIXEL_MAX_DIMENSION) } else { self.buf_w };
+        let new_h = if h > self.buf_h { h.max(self.buf_h * 2).min(SIXEL_MAX_DIMENSION) } else { self.buf_h };
+        let mut pixels = vec![0u8; new_w * new_h * 4];
+        for y in 0..self.buf_h {
+            let src = &self.pixels[y * self.buf_w * 4..(y + 1) * self.buf_w * 4];
+            pixels[y * new_w * 4..y * new_w * 4 + src.len()].copy_from_slice(src);
+        }
+        self.pixels = pixels;
+        self.buf_w = new_w;
+        self.buf_h = new_h;
+    }
+}
+
+fn percent(v: u32) -> u8 {
+    ((v.min(100) * 255 + 50) / 100) as u8
+}
+
+/// DEC HLS (hue 0 = blue, 120 = red, 240 = green) to RGB.
+fn hls_to_rgb(h: u32, l: u32, s: u32) -> [u8; 3] {
+    let h = ((h + 240) % 360) as f32 / 360.0;
+    let l = l.min(100) as f32 / 100.0;
+    let s = s.min(100) as f32 / 100.0;
+    if s == 0.0 {
+        let v = (l * 255.0).round() as u8;
+        return [v, v, v];
+    }
+    let q = if l < 0.5 { l * (1.0 + s) } else { l + s - l * s };
+    let p = 2.0 * l - q;
+    let channel = |t: f32| {
+        let t = t.rem_euclid(1.0);
+        let v = if t < 1.0 / 6.0 {
+            p + (q - p) * 6.0 * t
+        } else if t < 0.5 {
+            q
+        } else if t < 2.0 / 3.0 {
+            p + (q - p) * (2.0 / 3.0 - t) * 6.0
+        } else {
+            p
+        };
+        (v * 255.0).round() as u8
+    };
+    [channel(h + 1.0 / 3.0), channel(h), channel(h - 1.0 / 3.0)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(params: &[u16], data: &[u8]) -> Option<ImageData> {
+        let mut decoder = SixelDecoder::new(params);
+        for &b in data {
+            decoder.put(b);
+        }
+        decoder.finish()
+    }
+
+    fn pixel(image: &ImageData, x: usize, y: usize) -> [u8; 4] {
+        let i = (y * image.width as usize + x) * 4;
+        image.rgba[i..i + 4].try_into().unwrap()
+    }
+
+    #[test]
+    fn decodes_rgb_color_and_repeat() {
+        let image = decode(&[0, 1], b"#1;2;100;0;0!3~").unwrap();
+        assert_eq!((image.width, image.height), (3, 6));
+        assert_eq!(pixel(&image, 2, 5), [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn graphics_newline_starts_next_band() {
+        let image = decode(&[0, 1], b"#2@-#2@").unwrap();
+        assert_eq!((image.width, image.height), (1, 7));
+        // '@' sets only the top pixel of each band
+        assert_eq!(pixel(&image, 0, 6), pixel(&image, 0, 0));
+        assert_eq!(pixel(&image, 0, 3)[3], 0);
+    }
+
+    #[test]
+    fn background_select() {
+        // P2 = 1 leaves unset pixels transparent, otherwise color 0 fills them
+        let transparent = decode(&[0, 1], b"#1A").unwrap();
+        assert_eq!(pixel(&transparent, 0, 0), [0, 0, 0, 0]);
+        let filled = decode(&[0, 0], b"#0;2;0;0;100#1A").unwrap();
+        assert_eq!(pixel(&filled, 0, 0), [0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn raster_attributes_set_size() {
+        let image = decode(&[], b"\"1;1;4;12#1@").unwrap();
+        assert_eq!((image.width, image.height), (4, 12));
+    }
+
+    #[test]
+    fn hls_colors() {
+        assert_eq!(hls_to_rgb(0, 50, 100), [0, 0, 255]);
+        assert_eq!(hls_to_rgb(120, 50, 100), [255, 0, 0]);
+        assert_eq!(hls_to_rgb(240, 50, 100), [0, 255, 0]);
+        assert_eq!(hls_to_rgb(0, 100, 0), [255, 255, 255]);
+    }
+
+    #[test]
+    fn empty_image_is_none() {
+        assert!(decode(&[], b"#1;2;0;0;0").is_none());
+    }
+
+    #[test]
+    fn oversized_image_is_clipped() {
+        let mut data = format!("!{}~", SIXEL_MAX_DIMENSION + 100).into_bytes();
+        data.extend(b"~");
+        let image = decode(&[0, 1], &data).unwrap();
+        assert_eq!(image.width as usize, SIXEL_MAX_DIMENSION);
+    }
+}
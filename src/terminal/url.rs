@@ -1,4 +1,5 @@
 use crate::terminal::cell::Cell;
+use std::path::PathBuf;
 
 /// Detect URLs in a row of terminal cells.
 /// Returns `(col_start, col_end_exclusive, url_string)` tuples.
@@ -108,6 +109,37 @@ fn is_url_char(ch: char) -> bool {
     )
 }
 
+/// Parse the working directory reported with OSC 7: `file://host/path`
+/// (percent-encoded) or kitty's unencoded `kitty-shell-cwd://host/path`.
+/// The host is ignored; callers decide whether the path exists locally.
+pub fn parse_osc7_cwd(uri: &str) -> Option<PathBuf> {
+    let (rest, encoded) = if let Some(rest) = uri.strip_prefix("file://") {
+        (rest, true)
+    } else if let Some(rest) = uri.strip_prefix("kitty-shell-cwd://") {
+        (rest, false)
+    } else {
+        return None;
+    };
+    let path = &rest[rest.find('/')?..];
+    if !encoded {
+        return Some(PathBuf::from(path));
+    }
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok()?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok().map(PathBuf::from)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -212,4 +244,32 @@ mod tests {
         assert_eq!(urls[0].0, 3);  // start col
         assert_eq!(urls[0].1, 16); // end col (exclusive) — "https://x.com" is 13 chars
     }
+
+    // ── parse_osc7_cwd ──────────────────────────────────────────────────
+
+    #[test]
+    fn osc7_file_uri() {
+        assert_eq!(parse_osc7_cwd("file://mac.local/Users/me/src"), Some(PathBuf::from("/Users/me/src")));
+    }
+
+    #[test]
+    fn osc7_empty_host() {
+        assert_eq!(parse_osc7_cwd("file:///tmp"), Some(PathBuf::from("/tmp")));
+    }
+
+    #[test]
+    fn osc7_percent_decoding() {
+        assert_eq!(parse_osc7_cwd("file://h/My%20Docs/%C3%A9t%C3%A9"), Some(PathBuf::from("/My Docs/été")));
+    }
+
+    #[test]
+    fn osc7_kitty_scheme_is_not_decoded() {
+        assert_eq!(parse_osc7_cwd("kitty-shell-cwd://h/a%20b"), Some(PathBuf::from("/a%20b")));
+    }
+
+    #[test]
+    fn osc7_rejects_other_schemes() {
+        assert_eq!(parse_osc7_cwd("http://h/tmp"), None);
+        assert_eq!(parse_osc7_cwd("file://hostonly"), None);
+    }
 }